@@ -22,6 +22,12 @@ fn main() -> std::io::Result<()> {
     // env-vars to avoid recompilation of build.rs
     let docking_enabled = std::env::var_os("CARGO_FEATURE_DOCKING").is_some();
     let wasm_enabled = std::env::var_os("CARGO_FEATURE_WASM").is_some();
+    let draw_idx_32_enabled = std::env::var_os("CARGO_FEATURE_DRAW_IDX_32").is_some();
+
+    // 32-bit vertex indices, matching the `ImDrawIdx` type of the bindings
+    if draw_idx_32_enabled {
+        println!("cargo:DEFINE_ImDrawIdx=unsigned int");
+    }
 
     let cimgui_dir = if docking_enabled {
         manifest_dir.join("third-party/imgui-docking")
@@ -44,6 +50,9 @@ fn main() -> std::io::Result<()> {
         for (key, value) in DEFINES.iter() {
             build.define(key, *value);
         }
+        if draw_idx_32_enabled {
+            build.define("ImDrawIdx", "unsigned int");
+        }
 
         // Freetype font rasterizer feature
         #[cfg(feature = "freetype")]
@@ -44,6 +44,21 @@ cfg_if::cfg_if! {
     }
 }
 
+// Defined in cimgui.cpp without a declaration in cimgui.h, so the generated bindings miss them
+#[cfg(all(feature = "docking", not(feature = "wasm")))]
+extern "C" {
+    /// Sets `Platform_GetWindowPos` from a callback that writes the position through a pointer
+    pub fn ImGuiPlatformIO_Set_Platform_GetWindowPos(
+        platform_io: *mut ImGuiPlatformIO,
+        user_callback: Option<unsafe extern "C" fn(vp: *mut ImGuiViewport, out_pos: *mut ImVec2)>,
+    );
+    /// Sets `Platform_GetWindowSize` from a callback that writes the size through a pointer
+    pub fn ImGuiPlatformIO_Set_Platform_GetWindowSize(
+        platform_io: *mut ImGuiPlatformIO,
+        user_callback: Option<unsafe extern "C" fn(vp: *mut ImGuiViewport, out_size: *mut ImVec2)>,
+    );
+}
+
 impl ImVec2 {
     #[inline]
     pub const fn new(x: f32, y: f32) -> ImVec2 {
@@ -192,7 +192,10 @@ pub type ImGuiTreeNodeFlags = cty::c_int;
 pub type ImGuiViewportFlags = cty::c_int;
 pub type ImGuiWindowFlags = cty::c_int;
 pub type ImTextureID = *mut cty::c_void;
+#[cfg(not(feature = "draw-idx-32"))]
 pub type ImDrawIdx = cty::c_ushort;
+#[cfg(feature = "draw-idx-32")]
+pub type ImDrawIdx = cty::c_uint;
 pub type ImGuiID = cty::c_uint;
 pub type ImS8 = cty::c_schar;
 pub type ImU8 = cty::c_uchar;
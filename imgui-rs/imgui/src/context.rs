@@ -1,4 +1,6 @@
 use parking_lot::ReentrantMutex;
+#[cfg(not(feature = "docking"))]
+use std::cell::Cell;
 use std::cell::UnsafeCell;
 use std::ffi::{CStr, CString};
 use std::ops::Drop;
@@ -59,8 +61,16 @@ pub struct Context {
     // we also put it in an unsafecell since we're going to give
     // imgui a mutable pointer to it.
     clipboard_ctx: Box<UnsafeCell<ClipboardContext>>,
-
-    ui: Ui,
+    // boxed for the same reason, imgui reports the IME position through a callback that finds
+    // it via `io.ime_window_handle`
+    #[cfg(not(feature = "docking"))]
+    ime_input_pos: Box<Cell<[f32; 2]>>,
+    // boxed so the viewport callbacks can find the backends via `io.backend_platform_user_data`
+    #[cfg(feature = "docking")]
+    pub(crate) viewport_backends: Box<UnsafeCell<crate::viewports::ViewportBackends>>,
+
+    // boxed so results carrying a `SuspendedContext` stay small
+    ui: Box<Ui>,
 }
 
 // This mutex needs to be used to guard all public functions that can affect the underlying
@@ -107,6 +117,37 @@ impl Context {
         clear_current_context();
         SuspendedContext(self)
     }
+    /// Runs `f` with a suspended context active in place of this one, then makes this context
+    /// active again.
+    ///
+    /// This lets several contexts take turns without giving up the active one, for example one
+    /// context per OS window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this context is not the active context
+    #[doc(alias = "SetCurrentContext")]
+    pub fn with_suspended<R>(
+        &mut self,
+        suspended: &mut SuspendedContext,
+        f: impl FnOnce(&mut Context) -> R,
+    ) -> R {
+        struct Restore(*mut sys::ImGuiContext);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                unsafe { sys::igSetCurrentContext(self.0) };
+            }
+        }
+
+        let _guard = CTX_MUTEX.lock();
+        assert!(
+            self.is_current_context(),
+            "context to be swapped out is not the active context"
+        );
+        let _restore = Restore(self.raw);
+        unsafe { sys::igSetCurrentContext(suspended.0.raw) };
+        f(&mut suspended.0)
+    }
     /// Returns the path to the ini file, or None if not set
     pub fn ini_filename(&self) -> Option<PathBuf> {
         let io = self.io();
@@ -239,9 +280,14 @@ impl Context {
             platform_name: None,
             renderer_name: None,
             clipboard_ctx: Box::new(ClipboardContext::dummy().into()),
-            ui: Ui {
+            #[cfg(not(feature = "docking"))]
+            ime_input_pos: Box::new(Cell::new([1.0, 1.0])),
+            #[cfg(feature = "docking")]
+            viewport_backends: Box::default(),
+            ui: Box::new(Ui {
                 buffer: UnsafeCell::new(crate::string::UiBuffer::new(1024)),
-            },
+                drag_drop_payloads: Default::default(),
+            }),
         }
     }
     fn is_current_context(&self) -> bool {
@@ -250,6 +296,15 @@ impl Context {
     }
 }
 
+/// Stores the position reported by imgui in the cell `io.ime_window_handle` points to
+#[cfg(not(feature = "docking"))]
+unsafe extern "C" fn record_ime_input_pos(x: std::os::raw::c_int, y: std::os::raw::c_int) {
+    let handle = (*sys::igGetIO()).ImeWindowHandle as *const Cell<[f32; 2]>;
+    if let Some(pos) = handle.as_ref() {
+        pos.set([x as f32, y as f32]);
+    }
+}
+
 impl Drop for Context {
     #[doc(alias = "DestroyContext")]
     fn drop(&mut self) {
@@ -257,8 +312,9 @@ impl Drop for Context {
         // If this context is the active context, Dear ImGui automatically deactivates it during
         // destruction
         unsafe {
-            // end the frame if necessary...
-            if !sys::igGetCurrentContext().is_null() && sys::igGetFrameCount() > 0 {
+            // end the frame if necessary, but only for this context and not whichever one is
+            // active while a suspended context is dropped
+            if self.is_current_context() && sys::igGetFrameCount() > 0 {
                 sys::igEndFrame();
             }
             sys::igDestroyContext(self.raw);
@@ -317,9 +373,13 @@ impl SuspendedContext {
             Err(self)
         }
     }
-    fn create_internal(shared_font_atlas: Option<SharedFontAtlas>) -> Self {
+    fn create_internal(mut shared_font_atlas: Option<SharedFontAtlas>) -> Self {
         let _guard = CTX_MUTEX.lock();
-        let raw = unsafe { sys::igCreateContext(ptr::null_mut()) };
+        let shared_font_atlas_ptr = match &mut shared_font_atlas {
+            Some(shared_font_atlas) => shared_font_atlas.as_ptr_mut(),
+            None => ptr::null_mut(),
+        };
+        let raw = unsafe { sys::igCreateContext(shared_font_atlas_ptr) };
         let ctx = Context {
             raw,
             shared_font_atlas,
@@ -328,9 +388,14 @@ impl SuspendedContext {
             platform_name: None,
             renderer_name: None,
             clipboard_ctx: Box::new(ClipboardContext::dummy().into()),
-            ui: Ui {
+            #[cfg(not(feature = "docking"))]
+            ime_input_pos: Box::new(Cell::new([1.0, 1.0])),
+            #[cfg(feature = "docking")]
+            viewport_backends: Box::default(),
+            ui: Box::new(Ui {
                 buffer: UnsafeCell::new(crate::string::UiBuffer::new(1024)),
-            },
+                drag_drop_payloads: Default::default(),
+            }),
         };
         if ctx.is_current_context() {
             // Oops, the context was activated -> deactivate
@@ -423,6 +488,37 @@ fn test_shared_font_atlas() {
     let _borrow = ctx.fonts();
 }
 
+#[test]
+fn test_with_suspended() {
+    let _guard = crate::test::TEST_MUTEX.lock();
+    let atlas = SharedFontAtlas::create();
+    let mut ctx = Context::create_with_shared_font_atlas(atlas.clone());
+    let mut suspended = SuspendedContext::create_with_shared_font_atlas(atlas);
+    ctx.set_ini_filename(None);
+    ctx.io_mut().display_size = [100.0, 100.0];
+
+    let size = ctx.with_suspended(&mut suspended, |other| {
+        assert!(other.is_current_context());
+        other.io_mut().display_size = [200.0, 200.0];
+        other
+            .fonts()
+            .add_font(&[crate::FontSource::DefaultFontData { config: None }]);
+        other.io().display_size
+    });
+    assert_eq!(size, [200.0, 200.0]);
+    assert!(ctx.is_current_context());
+    assert_eq!(ctx.io().display_size, [100.0, 100.0]);
+    // Both contexts use the same atlas
+    assert_eq!(ctx.fonts().fonts().len(), 1);
+
+    // Dropping the other context leaves this one active and usable
+    drop(suspended);
+    assert!(ctx.is_current_context());
+    ctx.fonts().build_rgba32_texture();
+    ctx.io_mut().delta_time = 1.0 / 60.0;
+    let _ui = ctx.frame();
+}
+
 #[test]
 fn test_ini_load_save() {
     let (_guard, mut ctx) = crate::test::test_ctx();
@@ -523,10 +619,18 @@ impl Context {
         if !default_font.is_null() && self.fonts().get_font(FontId(default_font)).is_none() {
             self.io_mut().font_default = ptr::null_mut();
         }
+        #[cfg(not(feature = "docking"))]
+        {
+            let handle = &*self.ime_input_pos as *const Cell<[f32; 2]>;
+            let io = self.io_mut();
+            io.ime_set_input_screen_pos_fn = Some(record_ime_input_pos);
+            io.ime_window_handle = handle as *mut std::os::raw::c_void;
+        }
         // TODO: precondition checks
         unsafe {
             sys::igNewFrame();
         }
+        self.ui.drag_drop_payloads.get_mut().collect();
 
         &mut self.ui
     }
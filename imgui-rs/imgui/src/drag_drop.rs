@@ -21,12 +21,25 @@
 //!     it back to the Target. Please note: users are of course free to not drop any drag (cancel a drag),
 //!     so this data could easily be lost forever. Our `'static + Copy` bound is intended to keep users
 //!     to simplistic types.
-//! 3.  An unsafe implementation is provided which allows for any data to be unsafely copied. Note that once
-//!     you use this method, the safe implementations in #1 and #2 can create memory unsafety problems; notably,
-//!     they both assume that a payload has certain header information within it.
+//! 3.  Users can give [any owned value](DragDropPayloadOwned) with [begin_owned_payload](DragDropSource::begin_owned_payload).
+//!     The value stays on the Rust side, keyed by an id sent through Dear ImGui, and the target gets
+//!     it back as an `Rc`. It's dropped once the drag ends, whether it was delivered or cancelled.
+//! 4.  An unsafe implementation is provided which allows for any data to be unsafely copied. Note that once
+//!     you use this method, the safe implementations in #1, #2 and #3 can create memory unsafety problems; notably,
+//!     they all assume that a payload has certain header information within it.
 //!
 //! For examples of each payload type, see [DragDropSource].
-use std::{any, ffi, marker::PhantomData};
+//!
+//! Files dragged onto the window from outside the application, such as from the file manager,
+//! arrive as a payload named [DRAG_DROP_FILES] once the platform backend reports them, and targets
+//! receive their paths through [accept_files](DragDropTarget::accept_files).
+use std::{
+    any::{self, Any},
+    ffi,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use crate::{sys, Condition, Ui};
 use bitflags::bitflags;
@@ -248,6 +261,66 @@ impl<'ui, T: AsRef<str>> DragDropSource<'ui, T> {
         }
     }
 
+    /// Creates the source of a drag with a payload of any `'static` type, such as a `String`,
+    /// a `Vec` or an `Arc` handle, and returns a handle on the tooltip.
+    ///
+    /// The payload isn't copied to Dear ImGui: it's kept on the Rust side and only an id goes
+    /// through the drag. Targets receive it with
+    /// [accept_owned_payload](DragDropTarget::accept_owned_payload), and it's dropped once the drag
+    /// is delivered or cancelled. With `Condition::Once`, the payload of the first call is kept
+    /// and the later ones are dropped right away.
+    ///
+    /// ```no_run
+    /// # use imgui::*;
+    /// fn show_ui(ui: &Ui, asset: &std::sync::Arc<String>) {
+    ///     ui.button("Asset");
+    ///     ui.drag_drop_source_config("ASSET")
+    ///         .begin_owned_payload_with_preview(asset.clone(), |asset| ui.text(asset.as_str()));
+    ///
+    ///     ui.button("Slot");
+    ///     if let Some(target) = ui.drag_drop_target() {
+    ///         if let Some(Ok(payload)) = target
+    ///             .accept_owned_payload::<std::sync::Arc<String>, _>("ASSET", DragDropFlags::empty())
+    ///         {
+    ///             println!("Dropped {}", payload.data);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn begin_owned_payload<P: 'static>(self, payload: P) -> Option<DragDropSourceToolTip<'ui>> {
+        let ui = self.ui;
+        let id = ui.drag_drop_payloads.borrow_mut().next_id();
+        let tooltip = self.begin_payload(OwnedPayloadId(id))?;
+
+        // Dear ImGui keeps the previous payload with `Condition::Once`, the new one is dropped then
+        if current_owned_payload_id() == Some(id) {
+            ui.drag_drop_payloads.borrow_mut().set(id, payload);
+        }
+        Some(tooltip)
+    }
+
+    /// Like [begin_owned_payload](Self::begin_owned_payload), drawing the tooltip of the drag
+    /// with a callback that gets the payload. Returns true while dragging.
+    pub fn begin_owned_payload_with_preview<P: 'static>(
+        self,
+        payload: P,
+        preview: impl FnOnce(&P),
+    ) -> bool {
+        let ui = self.ui;
+        match self.begin_owned_payload(payload) {
+            Some(tooltip) => {
+                if let Some(payload) = ui.drag_drop_payloads.borrow().get() {
+                    if let Ok(payload) = payload.downcast::<P>() {
+                        preview(&payload);
+                    }
+                }
+                tooltip.end();
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Creates the source of a drag and returns a handle on the tooltip.
     /// This handle can be immediately dropped without binding it, in which case a default empty
     /// circle will be used for the "blank" tooltip as this item is being dragged around.
@@ -317,6 +390,26 @@ impl Drop for DragDropSourceToolTip<'_> {
 }
 
 impl Ui {
+    /// Drags files from outside the application, like files from the file manager held over the
+    /// window. Platform backends call this every frame while the OS reports files over the window
+    /// and the mouse button as held, then release the mouse button to drop them. Targets receive
+    /// the paths with [accept_files](DragDropTarget::accept_files).
+    pub fn drag_drop_external_files(&self, files: &[PathBuf]) {
+        let data = encode_files(files);
+        let tooltip = unsafe {
+            self.drag_drop_source_config(DRAG_DROP_FILES)
+                .flags(DragDropFlags::SOURCE_EXTERN)
+                .begin_payload_unchecked(data.as_ptr() as *const ffi::c_void, data.len())
+        };
+        if let Some(tooltip) = tooltip {
+            match files {
+                [file] => self.text(file.display().to_string()),
+                _ => self.text(format!("{} files", files.len())),
+            }
+            tooltip.end();
+        }
+    }
+
     /// Creates a new DragDropTarget, which gives methods for handling
     /// accepting payloads.
     #[doc(alias = "BeginDragDropTarget")]
@@ -430,6 +523,61 @@ impl<'ui> DragDropTarget<'ui> {
         })
     }
 
+    /// Accepts a payload sent with [begin_owned_payload](DragDropSource::begin_owned_payload).
+    /// This returns a Result, since you can specify any type. The sent type must match the return
+    /// type (via TypeId) to receive an `Ok`.
+    ///
+    /// On delivery, the payload is handed over: the returned `Rc` is the only one left, so
+    /// `Rc::try_unwrap` gives the value back.
+    pub fn accept_owned_payload<T: 'static, Name: AsRef<str>>(
+        &self,
+        name: Name,
+        flags: DragDropFlags,
+    ) -> Option<Result<DragDropPayloadOwned<T>, PayloadIsWrongType>> {
+        let payload = match self.accept_payload::<OwnedPayloadId, _>(name, flags)? {
+            Ok(payload) => payload,
+            Err(err) => {
+                return Some(Err(PayloadIsWrongType {
+                    received: err.received,
+                    expected: TypedPayloadHeader::new::<T>(),
+                }))
+            }
+        };
+
+        let mut payloads = self.0.drag_drop_payloads.borrow_mut();
+        let (data, received) = payloads.get_with_id(payload.data.0)?;
+        let data = match data.downcast::<T>() {
+            Ok(data) => data,
+            Err(_) => {
+                return Some(Err(PayloadIsWrongType {
+                    received,
+                    expected: TypedPayloadHeader::new::<T>(),
+                }))
+            }
+        };
+        if payload.delivery {
+            payloads.clear();
+        }
+        Some(Ok(DragDropPayloadOwned {
+            data,
+            preview: payload.preview,
+            delivery: payload.delivery,
+        }))
+    }
+
+    /// Accepts files dragged onto the window from outside the application. With
+    /// `ACCEPT_BEFORE_DELIVERY`, the paths can be looked at while the files are held over the
+    /// target, before they're dropped.
+    pub fn accept_files(&self, flags: DragDropFlags) -> Option<DragDropFiles> {
+        let payload = unsafe { self.accept_payload_unchecked(DRAG_DROP_FILES, flags) }?;
+        let data = unsafe { std::slice::from_raw_parts(payload.data as *const u8, payload.size) };
+        Some(DragDropFiles {
+            paths: decode_files(data),
+            preview: payload.preview,
+            delivery: payload.delivery,
+        })
+    }
+
     /// Accepts a drag and drop payload  which contains a raw pointer to [c_void](std::ffi::c_void)
     /// and a size in bytes. Users should generally avoid using this function
     /// if one of the safer variants is acceptable.
@@ -539,6 +687,180 @@ pub struct DragDropPayload {
     pub delivery: bool,
 }
 
+/// A DragDropPayload with status information and a value kept on the Rust side.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DragDropPayloadOwned<T: 'static> {
+    /// The value the source gave.
+    pub data: Rc<T>,
+
+    /// Set when [`accept_owned_payload`](DragDropTarget::accept_owned_payload) was called
+    /// and mouse has been hovering the target item.
+    pub preview: bool,
+
+    /// Set when [`accept_owned_payload`](DragDropTarget::accept_owned_payload) was
+    /// called and mouse button is released over the target item.
+    pub delivery: bool,
+}
+
+/// The id sent through Dear ImGui for a payload kept on the Rust side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OwnedPayloadId(u64);
+
+/// Id of the owned payload of the current drag, if the drag has one.
+fn current_owned_payload_id() -> Option<u64> {
+    let payload = unsafe { sys::igGetDragDropPayload() };
+    if payload.is_null() {
+        return None;
+    }
+    let payload = unsafe { &*payload };
+    if payload.Data.is_null()
+        || (payload.DataSize as usize) < std::mem::size_of::<TypedPayload<OwnedPayloadId>>()
+    {
+        return None;
+    }
+    let header = unsafe { (payload.Data as *const TypedPayloadHeader).read_unaligned() };
+    if header.type_id != any::TypeId::of::<OwnedPayloadId>() {
+        return None;
+    }
+    let data = unsafe { (payload.Data as *const TypedPayload<OwnedPayloadId>).read_unaligned() };
+    Some(data.data.0)
+}
+
+/// The payload of the current drag when it's kept on the Rust side. Dear ImGui runs one drag at a
+/// time, so there's at most one.
+#[derive(Default)]
+pub(crate) struct OwnedPayloads {
+    last_id: u64,
+    current: Option<(u64, Rc<dyn Any>, TypedPayloadHeader)>,
+}
+
+impl OwnedPayloads {
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn set<T: 'static>(&mut self, id: u64, payload: T) {
+        self.current = Some((id, Rc::new(payload), TypedPayloadHeader::new::<T>()));
+    }
+
+    fn get(&self) -> Option<Rc<dyn Any>> {
+        self.current.as_ref().map(|(_, payload, _)| payload.clone())
+    }
+
+    fn get_with_id(&self, id: u64) -> Option<(Rc<dyn Any>, TypedPayloadHeader)> {
+        match &self.current {
+            Some((current, payload, header)) if *current == id => Some((payload.clone(), *header)),
+            _ => None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.current = None;
+    }
+
+    /// Drops the payload once its drag is over, delivered or cancelled. Called when a frame starts.
+    pub(crate) fn collect(&mut self) {
+        if let Some((id, ..)) = self.current {
+            if current_owned_payload_id() != Some(id) {
+                self.current = None;
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for OwnedPayloads {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OwnedPayloads")
+            .field("current", &self.current.as_ref().map(|(id, ..)| id))
+            .finish()
+    }
+}
+
+/// Files dragged from outside the application.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DragDropFiles {
+    /// Paths of the files.
+    pub paths: Vec<PathBuf>,
+
+    /// Set when [`accept_files`](DragDropTarget::accept_files) was called
+    /// and the files are held over the target item.
+    pub preview: bool,
+
+    /// Set when [`accept_files`](DragDropTarget::accept_files) was
+    /// called and the files are dropped on the target item.
+    pub delivery: bool,
+}
+
+/// Name of the payload of files dragged from outside the application.
+pub const DRAG_DROP_FILES: &str = "EXTERNAL_FILES";
+
+/// Packs paths in a payload, each one after its length in bytes.
+fn encode_files(files: &[PathBuf]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for path in files {
+        let bytes = path_to_bytes(path);
+        data.extend_from_slice(&(bytes.len() as u32).to_ne_bytes());
+        data.extend_from_slice(&bytes);
+    }
+    data
+}
+
+fn decode_files(mut data: &[u8]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    while data.len() >= 4 {
+        let (len, rest) = data.split_at(4);
+        let len = u32::from_ne_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let (bytes, rest) = rest.split_at(len.min(rest.len()));
+        files.push(path_from_bytes(bytes));
+        data = rest;
+    }
+    files
+}
+
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    ffi::OsStr::from_bytes(bytes).into()
+}
+
+#[cfg(windows)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::windows::ffi::OsStrExt;
+    path.as_os_str()
+        .encode_wide()
+        .flat_map(u16::to_ne_bytes)
+        .collect()
+}
+
+#[cfg(windows)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::windows::ffi::OsStringExt;
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+        .collect();
+    ffi::OsString::from_wide(&wide).into()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
+
 /// A typed payload.
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -611,3 +933,101 @@ impl std::fmt::Display for PayloadIsWrongType {
 }
 
 impl std::error::Error for PayloadIsWrongType {}
+
+#[test]
+fn test_external_files() {
+    let (_guard, mut ctx) = crate::test::test_ctx_initialized();
+    let files = vec![
+        PathBuf::from("textures/grass.png"),
+        PathBuf::from("model.gltf"),
+    ];
+    assert_eq!(decode_files(&encode_files(&files)), files);
+
+    let mut delivered = None;
+    let mut clicked = false;
+    // held over the button for a few frames, then released
+    for frame in 0..4 {
+        let io = ctx.io_mut();
+        io.mouse_pos = [20.0, 35.0];
+        io.mouse_down[0] = false;
+        if frame < 3 {
+            io.hold_mouse_button(crate::MouseButton::Left);
+        }
+        let ui = ctx.frame();
+        ui.window("Target")
+            .position([0.0, 0.0], crate::Condition::Always)
+            .build(|| {
+                clicked |= ui.button("Drop here");
+                if let Some(target) = ui.drag_drop_target() {
+                    if let Some(payload) = target.accept_files(DragDropFlags::empty()) {
+                        delivered = Some(payload.paths);
+                    }
+                }
+            });
+        if frame < 3 {
+            ui.drag_drop_external_files(&files);
+        }
+        ctx.render();
+        assert_eq!(delivered.is_some(), frame == 3);
+    }
+    assert_eq!(delivered, Some(files));
+    assert!(!clicked);
+}
+
+#[test]
+fn test_owned_payload() {
+    let (_guard, mut ctx) = crate::test::test_ctx_initialized();
+    let value = Rc::new(String::from("grass.png"));
+
+    // drags from the source to the target, or cancels over empty space
+    let drag = |ctx: &mut crate::Context, drop_on_target: bool| {
+        let mut received = None;
+        let end = if drop_on_target {
+            [20.0, 60.0]
+        } else {
+            [400.0, 400.0]
+        };
+        // the window shows up on the first frame, the button is pressed on the third. Dear ImGui
+        // lets go of a cancelled payload a frame after the release, and it's dropped on the next.
+        for frame in 0..11 {
+            let io = ctx.io_mut();
+            io.mouse_pos = if frame < 4 { [20.0, 35.0] } else { end };
+            io.mouse_down[0] = (2..8).contains(&frame);
+            let ui = ctx.frame();
+            ui.window("Drag")
+                .position([0.0, 0.0], crate::Condition::Always)
+                .size([200.0, 100.0], crate::Condition::Always)
+                .build(|| {
+                    ui.button("Source");
+                    ui.drag_drop_source_config("ASSET")
+                        .begin_owned_payload_with_preview(value.clone(), |value| {
+                            ui.text(value.as_str())
+                        });
+                    ui.button("Target");
+                    if let Some(target) = ui.drag_drop_target() {
+                        assert!(matches!(
+                            target.accept_owned_payload::<u32, _>(
+                                "ASSET",
+                                DragDropFlags::ACCEPT_PEEK_ONLY
+                            ),
+                            Some(Err(_))
+                        ));
+                        if let Some(Ok(payload)) = target
+                            .accept_owned_payload::<Rc<String>, _>("ASSET", DragDropFlags::empty())
+                        {
+                            received = Some(payload.data);
+                        }
+                    }
+                });
+            ctx.render();
+        }
+        received
+    };
+
+    let received = drag(&mut ctx, true).expect("the payload was not delivered");
+    assert_eq!(Rc::try_unwrap(received).unwrap(), value);
+    assert_eq!(Rc::strong_count(&value), 1);
+
+    assert!(drag(&mut ctx, false).is_none());
+    assert_eq!(Rc::strong_count(&value), 1);
+}
@@ -9,7 +9,7 @@ use crate::fonts::font::Font;
 use crate::fonts::glyph_ranges::FontGlyphRanges;
 use crate::internal::{ImVector, RawCast};
 use crate::sys;
-use crate::TextureId;
+use crate::{MouseCursor, TextureId};
 
 bitflags! {
     /// Font atlas configuration flags
@@ -28,6 +28,10 @@ bitflags! {
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct FontId(pub(crate) *const Font);
 
+/// Identifier of a custom rectangle packed into the font atlas
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CustomRectId(c_int);
+
 /// A font atlas that builds a single texture
 #[repr(C)]
 pub struct FontAtlas {
@@ -133,6 +137,73 @@ impl FontAtlas {
         }
         None
     }
+    /// Adds a rectangle to pack into the atlas, to draw custom pixels into once it's built.
+    ///
+    /// The size is limited to 65535 pixels on each side.
+    #[doc(alias = "AddCustomRectRegular")]
+    pub fn add_custom_rect_regular(&mut self, width: u32, height: u32) -> CustomRectId {
+        assert!(width <= u16::MAX as u32 && height <= u16::MAX as u32);
+        let index = unsafe {
+            sys::ImFontAtlas_AddCustomRectRegular(self.raw_mut(), width as c_int, height as c_int)
+        };
+        CustomRectId(index)
+    }
+    /// Adds a glyph with custom pixels to a font, packed into the atlas like the glyphs of the
+    /// font are. The pixels are drawn into the rectangle once the atlas is built.
+    ///
+    /// `advance_x` is how far the text after the glyph is moved, and `offset` where the glyph
+    /// is drawn from the top left of the line.
+    #[doc(alias = "AddCustomRectFontGlyph")]
+    pub fn add_custom_rect_font_glyph(
+        &mut self,
+        font: FontId,
+        c: char,
+        width: u32,
+        height: u32,
+        advance_x: f32,
+        offset: [f32; 2],
+    ) -> CustomRectId {
+        assert!(width <= u16::MAX as u32 && height <= u16::MAX as u32);
+        assert!(self.get_font(font).is_some(), "font isn't in this atlas");
+        let index = unsafe {
+            sys::ImFontAtlas_AddCustomRectFontGlyph(
+                self.raw_mut(),
+                font.0 as *mut sys::ImFont,
+                c as sys::ImWchar,
+                width as c_int,
+                height as c_int,
+                advance_x,
+                offset.into(),
+            )
+        };
+        CustomRectId(index)
+    }
+    /// Returns where a custom rectangle was packed in the atlas texture.
+    ///
+    /// Returns `None` if the atlas isn't built, or the rectangle isn't in this atlas.
+    #[doc(alias = "GetCustomRectByIndex", alias = "CalcCustomRectUV")]
+    pub fn custom_rect(&self, id: CustomRectId) -> Option<FontAtlasCustomRect> {
+        if !self.is_built() || id.0 < 0 || id.0 >= self.custom_rects.Size {
+            return None;
+        }
+        unsafe {
+            let raw = self.raw() as *const sys::ImFontAtlas as *mut sys::ImFontAtlas;
+            let rect = sys::ImFontAtlas_GetCustomRectByIndex(raw, id.0);
+            if !sys::ImFontAtlasCustomRect_IsPacked(rect) {
+                return None;
+            }
+            let mut uv_min = sys::ImVec2::zero();
+            let mut uv_max = sys::ImVec2::zero();
+            sys::ImFontAtlas_CalcCustomRectUV(raw, rect, &mut uv_min, &mut uv_max);
+            let rect = &*rect;
+            Some(FontAtlasCustomRect {
+                position: [rect.X as u32, rect.Y as u32],
+                size: [rect.Width as u32, rect.Height as u32],
+                uv_min: uv_min.into(),
+                uv_max: uv_max.into(),
+            })
+        }
+    }
     /// Returns true if the font atlas has been built
     #[doc(alias = "IsBuilt")]
     pub fn is_built(&self) -> bool {
@@ -236,6 +307,64 @@ impl FontAtlas {
             sys::ImFontAtlas_ClearInputData(self.raw_mut());
         }
     }
+    /// Returns where the software mouse cursor of the given type is in the atlas texture.
+    ///
+    /// Returns `None` if the atlas isn't built, or was built with
+    /// [`FontAtlasFlags::NO_MOUSE_CURSORS`].
+    #[doc(alias = "GetMouseCursorTexData")]
+    pub fn mouse_cursor_tex_data(&self, cursor: MouseCursor) -> Option<MouseCursorTexData> {
+        if self.pack_id_mouse_cursors == -1 || !self.is_built() {
+            return None;
+        }
+        let mut offset = sys::ImVec2::zero();
+        let mut size = sys::ImVec2::zero();
+        let mut uv = [sys::ImVec2::zero(); 4];
+        let found = unsafe {
+            sys::ImFontAtlas_GetMouseCursorTexData(
+                self.raw() as *const sys::ImFontAtlas as *mut sys::ImFontAtlas,
+                cursor as sys::ImGuiMouseCursor,
+                &mut offset,
+                &mut size,
+                uv.as_mut_ptr(),
+                uv.as_mut_ptr().add(2),
+            )
+        };
+        if !found {
+            return None;
+        }
+        Some(MouseCursorTexData {
+            offset: offset.into(),
+            size: size.into(),
+            uv_border: [uv[0].into(), uv[1].into()],
+            uv_fill: [uv[2].into(), uv[3].into()],
+        })
+    }
+}
+
+/// Location of a software mouse cursor in the font atlas texture
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MouseCursorTexData {
+    /// Position of the hotspot within the cursor image, in pixels
+    pub offset: [f32; 2],
+    /// Size of the cursor image, in pixels
+    pub size: [f32; 2],
+    /// Minimum and maximum texture coordinates of the cursor border
+    pub uv_border: [[f32; 2]; 2],
+    /// Minimum and maximum texture coordinates of the cursor fill
+    pub uv_fill: [[f32; 2]; 2],
+}
+
+/// Location of a custom rectangle in the font atlas texture
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FontAtlasCustomRect {
+    /// Position of the top left corner, in pixels
+    pub position: [u32; 2],
+    /// Size of the rectangle, in pixels
+    pub size: [u32; 2],
+    /// Texture coordinates of the top left corner
+    pub uv_min: [f32; 2],
+    /// Texture coordinates of the bottom right corner
+    pub uv_max: [f32; 2],
 }
 
 #[test]
@@ -382,6 +511,39 @@ fn sys_font_config_default() -> sys::ImFontConfig {
     }
 }
 
+#[test]
+fn test_mouse_cursor_tex_data() {
+    let (_guard, mut ctx) = crate::test::test_ctx();
+    let atlas = ctx.fonts();
+    assert_eq!(atlas.mouse_cursor_tex_data(MouseCursor::Arrow), None);
+    atlas.build_rgba32_texture();
+    let arrow = atlas.mouse_cursor_tex_data(MouseCursor::Arrow).unwrap();
+    assert!(arrow.size[0] > 0.0 && arrow.size[1] > 0.0);
+    assert!(arrow.uv_fill[1][0] > arrow.uv_fill[0][0]);
+}
+
+#[test]
+fn test_custom_rects() {
+    let (_guard, mut ctx) = crate::test::test_ctx();
+    let atlas = ctx.fonts();
+    let font = atlas.add_font(&[FontSource::DefaultFontData { config: None }]);
+    let regular = atlas.add_custom_rect_regular(8, 4);
+    let glyph = atlas.add_custom_rect_font_glyph(font, '\u{e000}', 13, 13, 14.0, [0.0, 0.0]);
+    assert_ne!(regular, glyph);
+    assert_eq!(atlas.custom_rect(regular), None);
+
+    let texture = atlas.build_rgba32_texture();
+    let (width, height) = (texture.width, texture.height);
+    for (id, size) in [(regular, [8, 4]), (glyph, [13, 13])] {
+        let rect = atlas.custom_rect(id).unwrap();
+        assert_eq!(rect.size, size);
+        assert!(rect.position[0] + size[0] <= width && rect.position[1] + size[1] <= height);
+        assert_eq!(rect.uv_min[0], rect.position[0] as f32 / width as f32);
+        assert!(rect.uv_max[1] > rect.uv_min[1]);
+    }
+    assert_eq!(atlas.custom_rect(CustomRectId(100)), None);
+}
+
 #[test]
 fn test_font_config_default() {
     let sys_font_config = sys_font_config_default();
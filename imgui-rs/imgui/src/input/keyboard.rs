@@ -1,7 +1,20 @@
+use bitflags::bitflags;
+use std::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+
 use crate::sys;
 use crate::Ui;
 
 /// A key identifier
+///
+/// The first [`Key::MAPPED_COUNT`] keys are the ones Dear ImGui itself knows about and looks up
+/// through `Io::key_map`. The remaining keys only exist on the Rust side, so that every key of a
+/// keyboard can be queried the same way regardless of the backend.
+///
+/// Backends following the standard layout write the state of each key to
+/// `io.keys_down[key.index()]` and map the Dear ImGui keys to the same index, see
+/// [`Io::map_keys_to_indices`](crate::Io::map_keys_to_indices).
 #[repr(u32)]
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 #[allow(missing_docs)] // Self-describing
@@ -28,6 +41,101 @@ pub enum Key {
     X = sys::ImGuiKey_X,
     Y = sys::ImGuiKey_Y,
     Z = sys::ImGuiKey_Z,
+    LeftCtrl = sys::ImGuiKey_COUNT,
+    LeftShift = sys::ImGuiKey_COUNT + 1,
+    LeftAlt = sys::ImGuiKey_COUNT + 2,
+    LeftSuper = sys::ImGuiKey_COUNT + 3,
+    RightCtrl = sys::ImGuiKey_COUNT + 4,
+    RightShift = sys::ImGuiKey_COUNT + 5,
+    RightAlt = sys::ImGuiKey_COUNT + 6,
+    RightSuper = sys::ImGuiKey_COUNT + 7,
+    Menu = sys::ImGuiKey_COUNT + 8,
+    Alpha0 = sys::ImGuiKey_COUNT + 9,
+    Alpha1 = sys::ImGuiKey_COUNT + 10,
+    Alpha2 = sys::ImGuiKey_COUNT + 11,
+    Alpha3 = sys::ImGuiKey_COUNT + 12,
+    Alpha4 = sys::ImGuiKey_COUNT + 13,
+    Alpha5 = sys::ImGuiKey_COUNT + 14,
+    Alpha6 = sys::ImGuiKey_COUNT + 15,
+    Alpha7 = sys::ImGuiKey_COUNT + 16,
+    Alpha8 = sys::ImGuiKey_COUNT + 17,
+    Alpha9 = sys::ImGuiKey_COUNT + 18,
+    B = sys::ImGuiKey_COUNT + 19,
+    D = sys::ImGuiKey_COUNT + 20,
+    E = sys::ImGuiKey_COUNT + 21,
+    F = sys::ImGuiKey_COUNT + 22,
+    G = sys::ImGuiKey_COUNT + 23,
+    H = sys::ImGuiKey_COUNT + 24,
+    I = sys::ImGuiKey_COUNT + 25,
+    J = sys::ImGuiKey_COUNT + 26,
+    K = sys::ImGuiKey_COUNT + 27,
+    L = sys::ImGuiKey_COUNT + 28,
+    M = sys::ImGuiKey_COUNT + 29,
+    N = sys::ImGuiKey_COUNT + 30,
+    O = sys::ImGuiKey_COUNT + 31,
+    P = sys::ImGuiKey_COUNT + 32,
+    Q = sys::ImGuiKey_COUNT + 33,
+    R = sys::ImGuiKey_COUNT + 34,
+    S = sys::ImGuiKey_COUNT + 35,
+    T = sys::ImGuiKey_COUNT + 36,
+    U = sys::ImGuiKey_COUNT + 37,
+    W = sys::ImGuiKey_COUNT + 38,
+    F1 = sys::ImGuiKey_COUNT + 39,
+    F2 = sys::ImGuiKey_COUNT + 40,
+    F3 = sys::ImGuiKey_COUNT + 41,
+    F4 = sys::ImGuiKey_COUNT + 42,
+    F5 = sys::ImGuiKey_COUNT + 43,
+    F6 = sys::ImGuiKey_COUNT + 44,
+    F7 = sys::ImGuiKey_COUNT + 45,
+    F8 = sys::ImGuiKey_COUNT + 46,
+    F9 = sys::ImGuiKey_COUNT + 47,
+    F10 = sys::ImGuiKey_COUNT + 48,
+    F11 = sys::ImGuiKey_COUNT + 49,
+    F12 = sys::ImGuiKey_COUNT + 50,
+    F13 = sys::ImGuiKey_COUNT + 51,
+    F14 = sys::ImGuiKey_COUNT + 52,
+    F15 = sys::ImGuiKey_COUNT + 53,
+    F16 = sys::ImGuiKey_COUNT + 54,
+    F17 = sys::ImGuiKey_COUNT + 55,
+    F18 = sys::ImGuiKey_COUNT + 56,
+    F19 = sys::ImGuiKey_COUNT + 57,
+    F20 = sys::ImGuiKey_COUNT + 58,
+    F21 = sys::ImGuiKey_COUNT + 59,
+    F22 = sys::ImGuiKey_COUNT + 60,
+    F23 = sys::ImGuiKey_COUNT + 61,
+    F24 = sys::ImGuiKey_COUNT + 62,
+    Apostrophe = sys::ImGuiKey_COUNT + 63,
+    Comma = sys::ImGuiKey_COUNT + 64,
+    Minus = sys::ImGuiKey_COUNT + 65,
+    Period = sys::ImGuiKey_COUNT + 66,
+    Slash = sys::ImGuiKey_COUNT + 67,
+    Semicolon = sys::ImGuiKey_COUNT + 68,
+    Equal = sys::ImGuiKey_COUNT + 69,
+    LeftBracket = sys::ImGuiKey_COUNT + 70,
+    Backslash = sys::ImGuiKey_COUNT + 71,
+    RightBracket = sys::ImGuiKey_COUNT + 72,
+    GraveAccent = sys::ImGuiKey_COUNT + 73,
+    CapsLock = sys::ImGuiKey_COUNT + 74,
+    ScrollLock = sys::ImGuiKey_COUNT + 75,
+    NumLock = sys::ImGuiKey_COUNT + 76,
+    PrintScreen = sys::ImGuiKey_COUNT + 77,
+    Pause = sys::ImGuiKey_COUNT + 78,
+    KeyPad0 = sys::ImGuiKey_COUNT + 79,
+    KeyPad1 = sys::ImGuiKey_COUNT + 80,
+    KeyPad2 = sys::ImGuiKey_COUNT + 81,
+    KeyPad3 = sys::ImGuiKey_COUNT + 82,
+    KeyPad4 = sys::ImGuiKey_COUNT + 83,
+    KeyPad5 = sys::ImGuiKey_COUNT + 84,
+    KeyPad6 = sys::ImGuiKey_COUNT + 85,
+    KeyPad7 = sys::ImGuiKey_COUNT + 86,
+    KeyPad8 = sys::ImGuiKey_COUNT + 87,
+    KeyPad9 = sys::ImGuiKey_COUNT + 88,
+    KeyPadDecimal = sys::ImGuiKey_COUNT + 89,
+    KeyPadDivide = sys::ImGuiKey_COUNT + 90,
+    KeyPadMultiply = sys::ImGuiKey_COUNT + 91,
+    KeyPadSubtract = sys::ImGuiKey_COUNT + 92,
+    KeyPadAdd = sys::ImGuiKey_COUNT + 93,
+    KeyPadEqual = sys::ImGuiKey_COUNT + 94,
 }
 
 impl Key {
@@ -55,9 +163,258 @@ impl Key {
         Key::X,
         Key::Y,
         Key::Z,
+        Key::LeftCtrl,
+        Key::LeftShift,
+        Key::LeftAlt,
+        Key::LeftSuper,
+        Key::RightCtrl,
+        Key::RightShift,
+        Key::RightAlt,
+        Key::RightSuper,
+        Key::Menu,
+        Key::Alpha0,
+        Key::Alpha1,
+        Key::Alpha2,
+        Key::Alpha3,
+        Key::Alpha4,
+        Key::Alpha5,
+        Key::Alpha6,
+        Key::Alpha7,
+        Key::Alpha8,
+        Key::Alpha9,
+        Key::B,
+        Key::D,
+        Key::E,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::I,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::M,
+        Key::N,
+        Key::O,
+        Key::P,
+        Key::Q,
+        Key::R,
+        Key::S,
+        Key::T,
+        Key::U,
+        Key::W,
+        Key::F1,
+        Key::F2,
+        Key::F3,
+        Key::F4,
+        Key::F5,
+        Key::F6,
+        Key::F7,
+        Key::F8,
+        Key::F9,
+        Key::F10,
+        Key::F11,
+        Key::F12,
+        Key::F13,
+        Key::F14,
+        Key::F15,
+        Key::F16,
+        Key::F17,
+        Key::F18,
+        Key::F19,
+        Key::F20,
+        Key::F21,
+        Key::F22,
+        Key::F23,
+        Key::F24,
+        Key::Apostrophe,
+        Key::Comma,
+        Key::Minus,
+        Key::Period,
+        Key::Slash,
+        Key::Semicolon,
+        Key::Equal,
+        Key::LeftBracket,
+        Key::Backslash,
+        Key::RightBracket,
+        Key::GraveAccent,
+        Key::CapsLock,
+        Key::ScrollLock,
+        Key::NumLock,
+        Key::PrintScreen,
+        Key::Pause,
+        Key::KeyPad0,
+        Key::KeyPad1,
+        Key::KeyPad2,
+        Key::KeyPad3,
+        Key::KeyPad4,
+        Key::KeyPad5,
+        Key::KeyPad6,
+        Key::KeyPad7,
+        Key::KeyPad8,
+        Key::KeyPad9,
+        Key::KeyPadDecimal,
+        Key::KeyPadDivide,
+        Key::KeyPadMultiply,
+        Key::KeyPadSubtract,
+        Key::KeyPadAdd,
+        Key::KeyPadEqual,
     ];
     /// Total count of `Key` variants
-    pub const COUNT: usize = sys::ImGuiKey_COUNT as usize;
+    pub const COUNT: usize = sys::ImGuiKey_COUNT as usize + 95;
+    /// Count of the keys known to Dear ImGui, which have an entry in `Io::key_map`
+    pub const MAPPED_COUNT: usize = sys::ImGuiKey_COUNT as usize;
+
+    /// Returns true if Dear ImGui knows about this key, and it has an entry in `Io::key_map`
+    #[inline]
+    pub const fn is_mapped(self) -> bool {
+        (self as usize) < Key::MAPPED_COUNT
+    }
+
+    /// The index of this key in `Io::keys_down` when using the standard layout
+    #[inline]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The modifier flag this key sets when held, empty for non-modifier keys
+    pub const fn modifier(self) -> KeyMods {
+        match self {
+            Key::LeftCtrl | Key::RightCtrl => KeyMods::CTRL,
+            Key::LeftShift | Key::RightShift => KeyMods::SHIFT,
+            Key::LeftAlt | Key::RightAlt => KeyMods::ALT,
+            Key::LeftSuper | Key::RightSuper => KeyMods::SUPER,
+            _ => KeyMods::empty(),
+        }
+    }
+
+    /// A short human readable name of the key
+    pub const fn name(self) -> &'static str {
+        match self {
+            Key::Tab => "Tab",
+            Key::LeftArrow => "Left",
+            Key::RightArrow => "Right",
+            Key::UpArrow => "Up",
+            Key::DownArrow => "Down",
+            Key::PageUp => "PageUp",
+            Key::PageDown => "PageDown",
+            Key::Home => "Home",
+            Key::End => "End",
+            Key::Insert => "Insert",
+            Key::Delete => "Delete",
+            Key::Backspace => "Backspace",
+            Key::Space => "Space",
+            Key::Enter => "Enter",
+            Key::Escape => "Escape",
+            Key::KeyPadEnter => "KeypadEnter",
+            Key::A => "A",
+            Key::C => "C",
+            Key::V => "V",
+            Key::X => "X",
+            Key::Y => "Y",
+            Key::Z => "Z",
+            Key::LeftCtrl => "LeftCtrl",
+            Key::LeftShift => "LeftShift",
+            Key::LeftAlt => "LeftAlt",
+            Key::LeftSuper => "LeftSuper",
+            Key::RightCtrl => "RightCtrl",
+            Key::RightShift => "RightShift",
+            Key::RightAlt => "RightAlt",
+            Key::RightSuper => "RightSuper",
+            Key::Menu => "Menu",
+            Key::Alpha0 => "0",
+            Key::Alpha1 => "1",
+            Key::Alpha2 => "2",
+            Key::Alpha3 => "3",
+            Key::Alpha4 => "4",
+            Key::Alpha5 => "5",
+            Key::Alpha6 => "6",
+            Key::Alpha7 => "7",
+            Key::Alpha8 => "8",
+            Key::Alpha9 => "9",
+            Key::B => "B",
+            Key::D => "D",
+            Key::E => "E",
+            Key::F => "F",
+            Key::G => "G",
+            Key::H => "H",
+            Key::I => "I",
+            Key::J => "J",
+            Key::K => "K",
+            Key::L => "L",
+            Key::M => "M",
+            Key::N => "N",
+            Key::O => "O",
+            Key::P => "P",
+            Key::Q => "Q",
+            Key::R => "R",
+            Key::S => "S",
+            Key::T => "T",
+            Key::U => "U",
+            Key::W => "W",
+            Key::F1 => "F1",
+            Key::F2 => "F2",
+            Key::F3 => "F3",
+            Key::F4 => "F4",
+            Key::F5 => "F5",
+            Key::F6 => "F6",
+            Key::F7 => "F7",
+            Key::F8 => "F8",
+            Key::F9 => "F9",
+            Key::F10 => "F10",
+            Key::F11 => "F11",
+            Key::F12 => "F12",
+            Key::F13 => "F13",
+            Key::F14 => "F14",
+            Key::F15 => "F15",
+            Key::F16 => "F16",
+            Key::F17 => "F17",
+            Key::F18 => "F18",
+            Key::F19 => "F19",
+            Key::F20 => "F20",
+            Key::F21 => "F21",
+            Key::F22 => "F22",
+            Key::F23 => "F23",
+            Key::F24 => "F24",
+            Key::Apostrophe => "'",
+            Key::Comma => ",",
+            Key::Minus => "-",
+            Key::Period => ".",
+            Key::Slash => "/",
+            Key::Semicolon => ";",
+            Key::Equal => "=",
+            Key::LeftBracket => "[",
+            Key::Backslash => "\\",
+            Key::RightBracket => "]",
+            Key::GraveAccent => "`",
+            Key::CapsLock => "CapsLock",
+            Key::ScrollLock => "ScrollLock",
+            Key::NumLock => "NumLock",
+            Key::PrintScreen => "PrintScreen",
+            Key::Pause => "Pause",
+            Key::KeyPad0 => "Keypad0",
+            Key::KeyPad1 => "Keypad1",
+            Key::KeyPad2 => "Keypad2",
+            Key::KeyPad3 => "Keypad3",
+            Key::KeyPad4 => "Keypad4",
+            Key::KeyPad5 => "Keypad5",
+            Key::KeyPad6 => "Keypad6",
+            Key::KeyPad7 => "Keypad7",
+            Key::KeyPad8 => "Keypad8",
+            Key::KeyPad9 => "Keypad9",
+            Key::KeyPadDecimal => "Keypad.",
+            Key::KeyPadDivide => "Keypad/",
+            Key::KeyPadMultiply => "Keypad*",
+            Key::KeyPadSubtract => "Keypad-",
+            Key::KeyPadAdd => "Keypad+",
+            Key::KeyPadEqual => "Keypad=",
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 #[test]
@@ -67,6 +424,211 @@ fn test_key_variants() {
     }
 }
 
+#[test]
+fn test_key_index_fits_keys_down() {
+    for &key in Key::VARIANTS.iter() {
+        assert!(key.index() < 512);
+    }
+}
+
+bitflags! {
+    /// Keyboard modifiers
+    pub struct KeyMods: u32 {
+        /// Control (or Command on macOS when swapped by the backend)
+        const CTRL = 1;
+        /// Shift
+        const SHIFT = 1 << 1;
+        /// Alt (Option on macOS)
+        const ALT = 1 << 2;
+        /// Super (Windows or Command key)
+        const SUPER = 1 << 3;
+    }
+}
+
+impl fmt::Display for KeyMods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (KeyMods::CTRL, "Ctrl"),
+            (KeyMods::SHIFT, "Shift"),
+            (KeyMods::ALT, "Alt"),
+            (KeyMods::SUPER, "Super"),
+        ];
+        let mut first = true;
+        for (flag, name) in names.iter() {
+            if self.contains(*flag) {
+                if !first {
+                    f.write_str("+")?;
+                }
+                f.write_str(name)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Add for KeyMods {
+    type Output = KeyMods;
+
+    /// Combines modifiers, so chords read like `KeyMods::CTRL + KeyMods::SHIFT + Key::S`
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: KeyMods) -> KeyMods {
+        self | rhs
+    }
+}
+
+impl Add<Key> for KeyMods {
+    type Output = KeyChord;
+
+    fn add(self, key: Key) -> KeyChord {
+        KeyChord::new(self, key)
+    }
+}
+
+/// A key pressed together with a set of modifiers, e.g. `KeyMods::CTRL + KeyMods::SHIFT + Key::S`
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct KeyChord {
+    /// Modifiers that have to be held
+    pub mods: KeyMods,
+    /// The key that has to be pressed
+    pub key: Key,
+}
+
+impl KeyChord {
+    /// Creates a chord from modifiers and a key
+    #[inline]
+    pub const fn new(mods: KeyMods, key: Key) -> Self {
+        KeyChord { mods, key }
+    }
+
+    /// Returns true if exactly these modifiers are held.
+    ///
+    /// A modifier key used as the chord key doesn't count as held.
+    #[inline]
+    pub fn mods_match(&self, held: KeyMods) -> bool {
+        let own = self.key.modifier();
+        held - own == self.mods - own
+    }
+}
+
+impl From<Key> for KeyChord {
+    #[inline]
+    fn from(key: Key) -> Self {
+        KeyChord::new(KeyMods::empty(), key)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.mods.is_empty() {
+            write!(f, "{}+", self.mods)?;
+        }
+        f.write_str(self.key.name())
+    }
+}
+
+/// Error returned when parsing a [`KeyChord`] from a string fails
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseKeyChordError(String);
+
+impl fmt::Display for ParseKeyChordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid key chord '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseKeyChordError {}
+
+impl FromStr for KeyChord {
+    type Err = ParseKeyChordError;
+
+    /// Parses the format produced by `Display`, e.g. `Ctrl+Shift+S`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let modifiers = [
+            ("Ctrl+", KeyMods::CTRL),
+            ("Shift+", KeyMods::SHIFT),
+            ("Alt+", KeyMods::ALT),
+            ("Super+", KeyMods::SUPER),
+        ];
+
+        // Key names may contain '+' themselves (`Keypad+`), so only strip known prefixes
+        let mut mods = KeyMods::empty();
+        let mut rest = text.trim();
+        while let Some((prefix, flag)) = modifiers
+            .iter()
+            .find(|(prefix, _)| rest.starts_with(prefix))
+        {
+            mods |= *flag;
+            rest = &rest[prefix.len()..];
+        }
+
+        Key::VARIANTS
+            .iter()
+            .find(|key| key.name() == rest)
+            .map(|&key| KeyChord::new(mods, key))
+            .ok_or_else(|| ParseKeyChordError(text.to_string()))
+    }
+}
+
+#[test]
+fn test_key_chord_parse() {
+    for &key in Key::VARIANTS.iter() {
+        let chord = KeyMods::CTRL + KeyMods::ALT + key;
+        assert_eq!(chord.to_string().parse(), Ok(chord));
+        assert_eq!(key.name().parse(), Ok(KeyChord::from(key)));
+    }
+    assert_eq!("Shift+Keypad+".parse(), Ok(KeyMods::SHIFT + Key::KeyPadAdd));
+    assert!("Ctrl+".parse::<KeyChord>().is_err());
+    assert!("Hyper+S".parse::<KeyChord>().is_err());
+}
+
+#[test]
+fn test_key_chord() {
+    let chord = KeyMods::CTRL + KeyMods::SHIFT + Key::S;
+    assert_eq!(chord, KeyChord::new(KeyMods::CTRL | KeyMods::SHIFT, Key::S));
+    assert_eq!(chord.to_string(), "Ctrl+Shift+S");
+    assert_eq!(KeyChord::from(Key::F5).to_string(), "F5");
+    assert!(chord.mods_match(KeyMods::CTRL | KeyMods::SHIFT));
+    assert!(!chord.mods_match(KeyMods::CTRL));
+    assert!(!chord.mods_match(KeyMods::all()));
+
+    let modifier = KeyChord::new(KeyMods::CTRL, Key::LeftShift);
+    assert!(modifier.mods_match(KeyMods::CTRL | KeyMods::SHIFT));
+}
+
+#[test]
+fn test_unmapped_key_state() {
+    let (_guard, mut ctx) = crate::test::test_ctx_initialized();
+    ctx.io_mut().map_keys_to_indices();
+    ctx.io_mut().keys_down[Key::F5.index()] = true;
+    ctx.io_mut().key_ctrl = true;
+    let ui = ctx.frame();
+    assert!(ui.is_key_down(Key::F5));
+    assert!(ui.is_key_chord_pressed(KeyMods::CTRL + Key::F5));
+    assert!(!ui.is_key_chord_pressed(Key::F5));
+    assert!(!ui.is_key_down(Key::Tab));
+}
+
+#[test]
+fn test_ime_input_pos_follows_text_field() {
+    let (_guard, mut ctx) = crate::test::test_ctx_initialized();
+    let mut text = String::new();
+    let mut pos = [0.0, 0.0];
+    for _ in 0..3 {
+        ctx.io_mut().delta_time = 1.0 / 60.0;
+        let ui = ctx.frame();
+        ui.window("ime")
+            .position([100.0, 50.0], crate::Condition::Always)
+            .build(|| {
+                ui.set_keyboard_focus_here();
+                ui.input_text("##text", &mut text).build();
+            });
+        pos = ui.ime_input_pos();
+        ctx.render();
+    }
+    assert!(pos[0] > 100.0 && pos[1] > 50.0, "{:?}", pos);
+}
+
 /// Target widget selection for keyboard focus
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 pub enum FocusedWidget {
@@ -95,11 +657,16 @@ impl FocusedWidget {
 impl Ui {
     /// Returns the key index of the given key identifier.
     ///
-    /// Equivalent to indexing the Io struct `key_map` field: `ui.io().key_map[key]`
+    /// Equivalent to indexing the Io struct `key_map` field: `ui.io().key_map[key]`. Keys
+    /// Dear ImGui doesn't know about use their standard layout index.
     #[inline]
     #[doc(alias = "GetKeyIndex")]
     fn key_index(&self, key: Key) -> i32 {
-        unsafe { sys::igGetKeyIndex(key as i32) }
+        if key.is_mapped() {
+            unsafe { sys::igGetKeyIndex(key as i32) }
+        } else {
+            key.index() as i32
+        }
     }
     /// Returns true if the key is being held.
     ///
@@ -129,6 +696,14 @@ impl Ui {
         self.is_key_index_pressed(key_index)
     }
 
+    /// Same as [`is_key_pressed`](Self::is_key_pressed), spelled out for call sites where the
+    /// repeat behaviour matters.
+    #[inline]
+    #[doc(alias = "IsKeyPressed")]
+    pub fn is_key_pressed_with_repeat(&self, key: Key) -> bool {
+        self.is_key_pressed(key)
+    }
+
     /// Same as [`is_key_pressed`](Self::is_key_pressed) but takes a key index.
     ///
     /// The meaning of index is defined by your backend
@@ -178,6 +753,35 @@ impl Ui {
         unsafe { sys::igIsKeyReleased(key_index) }
     }
 
+    /// Returns the modifiers currently held
+    #[inline]
+    pub fn key_mods(&self) -> KeyMods {
+        let io = self.io();
+        let mut mods = KeyMods::empty();
+        mods.set(KeyMods::CTRL, io.key_ctrl);
+        mods.set(KeyMods::SHIFT, io.key_shift);
+        mods.set(KeyMods::ALT, io.key_alt);
+        mods.set(KeyMods::SUPER, io.key_super);
+        mods
+    }
+
+    /// Returns true if the chord key was pressed with exactly the chord modifiers held.
+    ///
+    /// Is **not** affected by key repeat settings.
+    #[inline]
+    pub fn is_key_chord_pressed(&self, chord: impl Into<KeyChord>) -> bool {
+        let chord = chord.into();
+        chord.mods_match(self.key_mods()) && self.is_key_pressed_no_repeat(chord.key)
+    }
+
+    /// Same as [`is_key_chord_pressed`](Self::is_key_chord_pressed) but affected by key repeat
+    /// settings (`io.key_repeat_delay`, `io.key_repeat_rate`)
+    #[inline]
+    pub fn is_key_chord_pressed_with_repeat(&self, chord: impl Into<KeyChord>) -> bool {
+        let chord = chord.into();
+        chord.mods_match(self.key_mods()) && self.is_key_pressed(chord.key)
+    }
+
     /// Returns a count of key presses using the given repeat rate/delay settings.
     ///
     /// Usually returns 0 or 1, but might be >1 if `rate` is small enough that `io.delta_time` >
@@ -214,4 +818,23 @@ impl Ui {
             sys::igSetKeyboardFocusHere(target_widget.as_offset());
         }
     }
+
+    /// Returns the screen position of the text cursor of the active text field, where the OS
+    /// input method editor should show its candidate window. The position is the top left of the
+    /// line being edited, and is only meaningful while `io.want_text_input` is set.
+    ///
+    /// Without the `docking` feature, this is the position reported at the end of the previous
+    /// frame.
+    pub fn ime_input_pos(&self) -> [f32; 2] {
+        #[cfg(feature = "docking")]
+        unsafe {
+            let pos = (*sys::igGetCurrentContext()).PlatformImePos;
+            [pos.x, pos.y]
+        }
+        #[cfg(not(feature = "docking"))]
+        unsafe {
+            let handle = self.io().ime_window_handle as *const std::cell::Cell<[f32; 2]>;
+            handle.as_ref().map_or([1.0, 1.0], |pos| pos.get())
+        }
+    }
 }
@@ -13,6 +13,10 @@ pub struct ImVector<T> {
 impl<T> ImVector<T> {
     #[inline]
     pub fn as_slice(&self) -> &[T] {
+        // empty vectors haven't allocated yet
+        if self.data.is_null() {
+            return &[];
+        }
         unsafe { slice::from_raw_parts(self.data, self.size as usize) }
     }
 }
@@ -55,6 +55,11 @@ bitflags! {
 
         #[cfg(feature = "docking")]
         const DOCKING_ENABLE = sys::ImGuiConfigFlags_DockingEnable;
+        /// Lets imgui windows leave the main window and become OS windows.
+        ///
+        /// Needs platform and renderer backends that support viewports.
+        #[cfg(feature = "docking")]
+        const VIEWPORTS_ENABLE = sys::ImGuiConfigFlags_ViewportsEnable;
     }
 }
 
@@ -74,6 +79,16 @@ bitflags! {
         ///
         /// This enables output of large meshes (64K+ vertices) while still using 16-bits indices.
         const RENDERER_HAS_VTX_OFFSET = sys::ImGuiBackendFlags_RendererHasVtxOffset;
+        /// Backend platform can create OS windows for viewports
+        #[cfg(feature = "docking")]
+        const PLATFORM_HAS_VIEWPORTS = sys::ImGuiBackendFlags_PlatformHasViewports;
+        /// Backend platform sets `io.mouse_hovered_viewport`, ignoring viewports with the
+        /// `NO_INPUTS` flag
+        #[cfg(feature = "docking")]
+        const HAS_MOUSE_HOVERED_VIEWPORT = sys::ImGuiBackendFlags_HasMouseHoveredViewport;
+        /// Backend renderer can draw into the OS windows of viewports
+        #[cfg(feature = "docking")]
+        const RENDERER_HAS_VIEWPORTS = sys::ImGuiBackendFlags_RendererHasViewports;
     }
 }
 
@@ -227,7 +242,7 @@ pub struct Io {
 
     pub(crate) backend_platform_name: *const c_char,
     pub(crate) backend_renderer_name: *const c_char,
-    backend_platform_user_data: *mut c_void,
+    pub(crate) backend_platform_user_data: *mut c_void,
     backend_renderer_user_data: *mut c_void,
     backend_language_user_data: *mut c_void,
     pub(crate) get_clipboard_text_fn:
@@ -236,10 +251,10 @@ pub struct Io {
         Option<unsafe extern "C" fn(user_data: *mut c_void, text: *const c_char)>,
     pub(crate) clipboard_user_data: *mut c_void,
     #[cfg(not(feature = "docking"))]
-    ime_set_input_screen_pos_fn:
+    pub(crate) ime_set_input_screen_pos_fn:
         Option<unsafe extern "C" fn(x: std::os::raw::c_int, y: std::os::raw::c_int)>,
     #[cfg(not(feature = "docking"))]
-    ime_window_handle: *mut c_void,
+    pub(crate) ime_window_handle: *mut c_void,
     /// Mouse position, in pixels.
     ///
     /// Set to [f32::MAX, f32::MAX] if mouse is unavailable (on another screen, etc.).
@@ -391,15 +406,61 @@ impl Io {
     pub fn update_delta_time(&mut self, delta: Duration) {
         self.delta_time = delta.as_secs_f32().max(f32::MIN_POSITIVE);
     }
+    /// Holds a mouse button down without a click, as if it was pressed before the frame began.
+    ///
+    /// Used for drags that started outside the application, like files dragged from the file
+    /// manager, so the widget under the mouse isn't clicked when they arrive.
+    pub fn hold_mouse_button(&mut self, button: MouseButton) {
+        self.mouse_down[button as usize] = true;
+        let duration = &mut self.mouse_down_duration[button as usize];
+        if *duration < 0.0 {
+            *duration = 0.0;
+        }
+    }
+    /// Size in pixels of the font windows start with, `font_global_scale` included.
+    ///
+    /// This is the default font if one is set, the first font of the atlas otherwise. Returns 0.0
+    /// while the atlas has no fonts.
+    pub fn default_font_size(&self) -> f32 {
+        let font = if self.font_default.is_null() {
+            match unsafe { (*self.fonts).fonts().first() } {
+                Some(id) => id.0,
+                None => return 0.0,
+            }
+        } else {
+            self.font_default as *const Font
+        };
+        let font = unsafe { &*font };
+        (font.font_size * font.scale * self.font_global_scale).max(1.0)
+    }
+    /// Maps every key Dear ImGui knows about to its standard layout index.
+    ///
+    /// Backends calling this write the state of each [`Key`] to `keys_down[key.index()]`.
+    pub fn map_keys_to_indices(&mut self) {
+        for &key in Key::VARIANTS.iter().filter(|key| key.is_mapped()) {
+            self[key] = key.index() as u32;
+        }
+    }
 }
 
+/// Value of `key_map` entries for keys without a mapping, -1 to Dear ImGui
+static UNMAPPED_KEY: u32 = u32::MAX;
+
+/// Indexes `key_map`.
+///
+/// Keys Dear ImGui doesn't know about have no entry, they read as unmapped (`u32::MAX`), see
+/// [`Key::is_mapped`].
 impl Index<Key> for Io {
     type Output = u32;
     fn index(&self, index: Key) -> &u32 {
-        &self.key_map[index as usize]
+        self.key_map.get(index as usize).unwrap_or(&UNMAPPED_KEY)
     }
 }
 
+/// Indexes `key_map`.
+///
+/// Panics for keys Dear ImGui doesn't know about, as there's no entry to write, see
+/// [`Key::is_mapped`].
 impl IndexMut<Key> for Io {
     fn index_mut(&mut self, index: Key) -> &mut u32 {
         &mut self.key_map[index as usize]
@@ -432,6 +493,29 @@ impl IndexMut<MouseButton> for Io {
     }
 }
 
+#[test]
+fn test_unmapped_keys_read_as_unmapped() {
+    let (_guard, mut ctx) = crate::test::test_ctx();
+    let io = ctx.io_mut();
+    io.map_keys_to_indices();
+    for &key in Key::VARIANTS.iter() {
+        if key.is_mapped() {
+            assert_eq!(io[key], key.index() as u32);
+        } else {
+            assert_eq!(io[key], u32::MAX);
+        }
+    }
+}
+
+#[test]
+fn test_default_font_size() {
+    let (_guard, mut ctx) = crate::test::test_ctx_initialized();
+    ctx.io_mut().font_global_scale = 2.0;
+    let ui = ctx.frame();
+    assert_eq!(ui.io().default_font_size(), ui.current_font_size());
+    assert_eq!(ui.io().default_font_size(), 26.0);
+}
+
 #[test]
 #[cfg(test)]
 fn test_io_memory_layout() {
@@ -10,7 +10,9 @@ use std::os::raw::c_char;
 pub use self::clipboard::*;
 pub use self::color::ImColor32;
 pub use self::context::*;
-pub use self::drag_drop::{DragDropFlags, DragDropSource, DragDropTarget};
+pub use self::drag_drop::{
+    DragDropFiles, DragDropFlags, DragDropPayloadOwned, DragDropSource, DragDropTarget,
+};
 pub use self::draw_list::{ChannelsSplit, DrawListMut};
 pub use self::fonts::atlas::*;
 pub use self::fonts::font::*;
@@ -35,6 +37,8 @@ pub use self::style::*;
 pub use self::tables::*;
 pub use self::text_filter::*;
 pub use self::utils::*;
+#[cfg(feature = "docking")]
+pub use self::viewports::*;
 pub use self::widget::color_editors::*;
 pub use self::widget::combo_box::*;
 pub use self::widget::drag::*;
@@ -84,6 +88,8 @@ mod tables;
 mod test;
 pub mod text_filter;
 mod utils;
+#[cfg(feature = "docking")]
+mod viewports;
 mod widget;
 mod window;
 
@@ -123,6 +129,8 @@ impl Context {
 pub struct Ui {
     /// our scratch sheet
     buffer: cell::UnsafeCell<string::UiBuffer>,
+    /// payload of the current drag, when it's kept on the Rust side
+    drag_drop_payloads: cell::RefCell<drag_drop::OwnedPayloads>,
 }
 
 impl Ui {
@@ -227,7 +227,7 @@ impl<'a> Iterator for DrawCmdIterator<'a> {
     }
 }
 
-/// A vertex index
+/// A vertex index, `u16` or `u32` with the `draw-idx-32` feature
 pub type DrawIdx = sys::ImDrawIdx;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -77,7 +77,10 @@ impl<T> Textures<T> {
         TextureId::from(id)
     }
 
+    /// Puts a texture under an id. Ids from `insert` skip it from now on, so a texture can be
+    /// kept under the same id as in another `Textures`.
     pub fn replace(&mut self, id: TextureId, texture: T) -> Option<T> {
+        self.next = self.next.max(id.0 + 1);
         self.textures.insert(id.0, texture)
     }
 
@@ -93,3 +96,14 @@ impl<T> Textures<T> {
         self.textures.get_mut(&id.0)
     }
 }
+
+#[test]
+fn test_textures_replace_reserves_id() {
+    let mut textures = Textures::new();
+    assert_eq!(textures.insert("a"), TextureId::new(0));
+    assert_eq!(textures.replace(TextureId::new(3), "shared"), None);
+    assert_eq!(textures.insert("b"), TextureId::new(4));
+    assert_eq!(textures.replace(TextureId::new(0), "c"), Some("a"));
+    assert_eq!(textures.insert("d"), TextureId::new(5));
+    assert_eq!(textures.get(TextureId::new(3)), Some(&"shared"));
+}
@@ -0,0 +1,539 @@
+//! Platform windows for imgui windows dragged outside the main window.
+//!
+//! With [`ConfigFlags::VIEWPORTS_ENABLE`](crate::ConfigFlags::VIEWPORTS_ENABLE), imgui asks the
+//! platform backend to create an OS window for every viewport that leaves the main one, and the
+//! renderer backend to draw into it. Backends implement [`PlatformViewportBackend`] and
+//! [`RendererViewportBackend`], and are installed with [`Context::set_platform_backend`] and
+//! [`Context::set_renderer_backend`].
+
+use bitflags::bitflags;
+use std::ffi::{c_void, CStr};
+use std::fmt;
+use std::os::raw::c_char;
+use std::slice;
+
+use crate::internal::{ImVector, RawCast};
+use crate::{sys, Context, DrawData};
+
+bitflags! {
+    /// Viewport flags
+    #[repr(transparent)]
+    pub struct ViewportFlags: u32 {
+        /// Represents a platform window
+        const IS_PLATFORM_WINDOW = sys::ImGuiViewportFlags_IsPlatformWindow;
+        /// Represents a platform monitor
+        const IS_PLATFORM_MONITOR = sys::ImGuiViewportFlags_IsPlatformMonitor;
+        /// The platform window is created and managed by the application
+        const OWNED_BY_APP = sys::ImGuiViewportFlags_OwnedByApp;
+        /// The platform window has no title bar and borders
+        const NO_DECORATION = sys::ImGuiViewportFlags_NoDecoration;
+        /// The platform window doesn't appear in the task bar
+        const NO_TASK_BAR_ICON = sys::ImGuiViewportFlags_NoTaskBarIcon;
+        /// The platform window isn't focused when it appears
+        const NO_FOCUS_ON_APPEARING = sys::ImGuiViewportFlags_NoFocusOnAppearing;
+        /// The platform window isn't focused when clicked
+        const NO_FOCUS_ON_CLICK = sys::ImGuiViewportFlags_NoFocusOnClick;
+        /// The platform window lets the mouse go through to what's below
+        const NO_INPUTS = sys::ImGuiViewportFlags_NoInputs;
+        /// The renderer doesn't have to clear the window before drawing
+        const NO_RENDERER_CLEAR = sys::ImGuiViewportFlags_NoRendererClear;
+        /// The platform window stays above the others
+        const TOP_MOST = sys::ImGuiViewportFlags_TopMost;
+        /// The platform window is minimized, imgui skips drawing it
+        const MINIMIZED = sys::ImGuiViewportFlags_Minimized;
+        /// The viewport is never merged back into another one
+        const NO_AUTO_MERGE = sys::ImGuiViewportFlags_NoAutoMerge;
+        /// The viewport can host windows other than its owner, the main viewport can
+        const CAN_HOST_OTHER_WINDOWS = sys::ImGuiViewportFlags_CanHostOtherWindows;
+    }
+}
+
+/// A platform window, or the main window, that imgui draws into
+#[repr(C)]
+pub struct Viewport {
+    pub id: sys::ImGuiID,
+    pub flags: ViewportFlags,
+    /// Position of the window on the desktop, in logical pixels
+    pub pos: [f32; 2],
+    /// Size of the window, in logical pixels
+    pub size: [f32; 2],
+    /// Position of the area not covered by menu bars and status bars
+    pub work_pos: [f32; 2],
+    /// Size of the area not covered by menu bars and status bars
+    pub work_size: [f32; 2],
+    pub dpi_scale: f32,
+    pub parent_viewport_id: sys::ImGuiID,
+    draw_data: *mut DrawData,
+    /// Free for the renderer backend to use
+    pub renderer_user_data: *mut c_void,
+    /// Free for the platform backend to use
+    pub platform_user_data: *mut c_void,
+    /// Window handle of the platform backend
+    pub platform_handle: *mut c_void,
+    /// Native window handle of the platform backend
+    pub platform_handle_raw: *mut c_void,
+    /// Set by the platform backend when the OS moved the window
+    pub platform_request_move: bool,
+    /// Set by the platform backend when the OS resized the window
+    pub platform_request_resize: bool,
+    /// Set by the platform backend when the OS asked to close the window
+    pub platform_request_close: bool,
+}
+
+unsafe impl RawCast<sys::ImGuiViewport> for Viewport {}
+
+impl Viewport {
+    /// Draw data of the last rendered frame, `None` when the viewport wasn't drawn
+    pub fn draw_data(&self) -> Option<&DrawData> {
+        unsafe { self.draw_data.as_ref() }
+    }
+
+    /// Draw data of the last rendered frame, for example to set the framebuffer scale of the
+    /// window it goes to
+    pub fn draw_data_mut(&mut self) -> Option<&mut DrawData> {
+        unsafe { self.draw_data.as_mut() }
+    }
+
+    /// True for the viewport of the main window
+    pub fn is_main(&self) -> bool {
+        unsafe { (*sys::igGetMainViewport()).ID == self.id }
+    }
+}
+
+impl fmt::Debug for Viewport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Viewport")
+            .field("id", &self.id)
+            .field("flags", &self.flags)
+            .field("pos", &self.pos)
+            .field("size", &self.size)
+            .field("dpi_scale", &self.dpi_scale)
+            .field("parent_viewport_id", &self.parent_viewport_id)
+            .finish()
+    }
+}
+
+/// A monitor windows can be placed on, in logical pixels
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PlatformMonitor {
+    pub main_pos: [f32; 2],
+    pub main_size: [f32; 2],
+    /// Position of the area not covered by task bars
+    pub work_pos: [f32; 2],
+    /// Size of the area not covered by task bars
+    pub work_size: [f32; 2],
+    pub dpi_scale: f32,
+}
+
+/// Creates and manages the OS windows of viewports.
+///
+/// All positions and sizes are in logical pixels, in the same space as `io.mouse_pos`.
+pub trait PlatformViewportBackend: 'static {
+    /// Called for every viewport but the main one
+    fn create_window(&mut self, viewport: &mut Viewport);
+    /// Also called for the main viewport, whose window belongs to the application
+    fn destroy_window(&mut self, viewport: &mut Viewport);
+    fn show_window(&mut self, viewport: &mut Viewport);
+    fn set_window_pos(&mut self, viewport: &mut Viewport, pos: [f32; 2]);
+    fn get_window_pos(&mut self, viewport: &mut Viewport) -> [f32; 2];
+    fn set_window_size(&mut self, viewport: &mut Viewport, size: [f32; 2]);
+    fn get_window_size(&mut self, viewport: &mut Viewport) -> [f32; 2];
+    fn set_window_focus(&mut self, viewport: &mut Viewport);
+    fn get_window_focus(&mut self, viewport: &mut Viewport) -> bool;
+    fn get_window_minimized(&mut self, viewport: &mut Viewport) -> bool;
+    fn set_window_title(&mut self, viewport: &mut Viewport, title: &str);
+    fn set_window_alpha(&mut self, viewport: &mut Viewport, alpha: f32);
+    /// Called once per frame for every platform window, after the others
+    fn update_window(&mut self, viewport: &mut Viewport);
+    fn get_window_dpi_scale(&mut self, viewport: &mut Viewport) -> f32;
+}
+
+/// Draws viewports into the OS windows created by the platform backend
+pub trait RendererViewportBackend: 'static {
+    fn create_window(&mut self, viewport: &mut Viewport);
+    /// Also called for the main viewport
+    fn destroy_window(&mut self, viewport: &mut Viewport);
+    fn set_window_size(&mut self, viewport: &mut Viewport, size: [f32; 2]);
+    fn render_window(&mut self, viewport: &mut Viewport);
+    fn swap_buffers(&mut self, viewport: &mut Viewport);
+}
+
+/// The backends of a context, found by the callbacks through `io.backend_platform_user_data`
+#[derive(Default)]
+pub(crate) struct ViewportBackends {
+    platform: Option<Box<dyn PlatformViewportBackend>>,
+    renderer: Option<Box<dyn RendererViewportBackend>>,
+}
+
+impl fmt::Debug for ViewportBackends {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewportBackends")
+            .field("platform", &self.platform.is_some())
+            .field("renderer", &self.renderer.is_some())
+            .finish()
+    }
+}
+
+unsafe fn backends<'a>() -> &'a mut ViewportBackends {
+    &mut *((*sys::igGetIO()).BackendPlatformUserData as *mut ViewportBackends)
+}
+
+unsafe fn platform<'a>() -> &'a mut dyn PlatformViewportBackend {
+    backends()
+        .platform
+        .as_deref_mut()
+        .expect("no platform viewport backend installed")
+}
+
+unsafe fn renderer<'a>() -> &'a mut dyn RendererViewportBackend {
+    backends()
+        .renderer
+        .as_deref_mut()
+        .expect("no renderer viewport backend installed")
+}
+
+unsafe fn viewport<'a>(vp: *mut sys::ImGuiViewport) -> &'a mut Viewport {
+    Viewport::from_raw_mut(&mut *vp)
+}
+
+unsafe extern "C" fn platform_create_window(vp: *mut sys::ImGuiViewport) {
+    platform().create_window(viewport(vp));
+}
+unsafe extern "C" fn platform_destroy_window(vp: *mut sys::ImGuiViewport) {
+    platform().destroy_window(viewport(vp));
+}
+unsafe extern "C" fn platform_show_window(vp: *mut sys::ImGuiViewport) {
+    platform().show_window(viewport(vp));
+}
+unsafe extern "C" fn platform_set_window_pos(vp: *mut sys::ImGuiViewport, pos: sys::ImVec2) {
+    platform().set_window_pos(viewport(vp), pos.into());
+}
+unsafe extern "C" fn platform_get_window_pos(vp: *mut sys::ImGuiViewport, out: *mut sys::ImVec2) {
+    *out = platform().get_window_pos(viewport(vp)).into();
+}
+unsafe extern "C" fn platform_set_window_size(vp: *mut sys::ImGuiViewport, size: sys::ImVec2) {
+    platform().set_window_size(viewport(vp), size.into());
+}
+unsafe extern "C" fn platform_get_window_size(vp: *mut sys::ImGuiViewport, out: *mut sys::ImVec2) {
+    *out = platform().get_window_size(viewport(vp)).into();
+}
+unsafe extern "C" fn platform_set_window_focus(vp: *mut sys::ImGuiViewport) {
+    platform().set_window_focus(viewport(vp));
+}
+unsafe extern "C" fn platform_get_window_focus(vp: *mut sys::ImGuiViewport) -> bool {
+    platform().get_window_focus(viewport(vp))
+}
+unsafe extern "C" fn platform_get_window_minimized(vp: *mut sys::ImGuiViewport) -> bool {
+    platform().get_window_minimized(viewport(vp))
+}
+unsafe extern "C" fn platform_set_window_title(vp: *mut sys::ImGuiViewport, title: *const c_char) {
+    let title = CStr::from_ptr(title).to_string_lossy();
+    platform().set_window_title(viewport(vp), &title);
+}
+unsafe extern "C" fn platform_set_window_alpha(vp: *mut sys::ImGuiViewport, alpha: f32) {
+    platform().set_window_alpha(viewport(vp), alpha);
+}
+unsafe extern "C" fn platform_update_window(vp: *mut sys::ImGuiViewport) {
+    platform().update_window(viewport(vp));
+}
+unsafe extern "C" fn platform_get_window_dpi_scale(vp: *mut sys::ImGuiViewport) -> f32 {
+    platform().get_window_dpi_scale(viewport(vp))
+}
+
+unsafe extern "C" fn renderer_create_window(vp: *mut sys::ImGuiViewport) {
+    renderer().create_window(viewport(vp));
+}
+unsafe extern "C" fn renderer_destroy_window(vp: *mut sys::ImGuiViewport) {
+    renderer().destroy_window(viewport(vp));
+}
+unsafe extern "C" fn renderer_set_window_size(vp: *mut sys::ImGuiViewport, size: sys::ImVec2) {
+    renderer().set_window_size(viewport(vp), size.into());
+}
+unsafe extern "C" fn renderer_render_window(vp: *mut sys::ImGuiViewport, _: *mut c_void) {
+    renderer().render_window(viewport(vp));
+}
+unsafe extern "C" fn renderer_swap_buffers(vp: *mut sys::ImGuiViewport, _: *mut c_void) {
+    renderer().swap_buffers(viewport(vp));
+}
+
+impl Context {
+    fn viewport_backends(&mut self) -> &mut ViewportBackends {
+        let backends = self.viewport_backends.get();
+        self.io_mut().backend_platform_user_data = backends as *mut c_void;
+        unsafe { &mut *backends }
+    }
+
+    /// Installs the backend that creates the OS windows of viewports, and flags the platform as
+    /// supporting them.
+    ///
+    /// The platform also has to set the monitors and the `platform_handle` of the main viewport.
+    pub fn set_platform_backend<T: PlatformViewportBackend>(&mut self, backend: T) {
+        self.viewport_backends().platform = Some(Box::new(backend));
+        self.io_mut().backend_flags |= crate::BackendFlags::PLATFORM_HAS_VIEWPORTS;
+
+        let platform_io = unsafe { &mut *sys::igGetPlatformIO() };
+        platform_io.Platform_CreateWindow = Some(platform_create_window);
+        platform_io.Platform_DestroyWindow = Some(platform_destroy_window);
+        platform_io.Platform_ShowWindow = Some(platform_show_window);
+        platform_io.Platform_SetWindowPos = Some(platform_set_window_pos);
+        platform_io.Platform_SetWindowSize = Some(platform_set_window_size);
+        platform_io.Platform_SetWindowFocus = Some(platform_set_window_focus);
+        platform_io.Platform_GetWindowFocus = Some(platform_get_window_focus);
+        platform_io.Platform_GetWindowMinimized = Some(platform_get_window_minimized);
+        platform_io.Platform_SetWindowTitle = Some(platform_set_window_title);
+        platform_io.Platform_SetWindowAlpha = Some(platform_set_window_alpha);
+        platform_io.Platform_UpdateWindow = Some(platform_update_window);
+        platform_io.Platform_GetWindowDpiScale = Some(platform_get_window_dpi_scale);
+        unsafe {
+            sys::ImGuiPlatformIO_Set_Platform_GetWindowPos(
+                platform_io,
+                Some(platform_get_window_pos),
+            );
+            sys::ImGuiPlatformIO_Set_Platform_GetWindowSize(
+                platform_io,
+                Some(platform_get_window_size),
+            );
+        }
+    }
+
+    /// Installs the backend that draws viewports into their OS windows, and flags the renderer
+    /// as supporting them
+    pub fn set_renderer_backend<T: RendererViewportBackend>(&mut self, backend: T) {
+        self.viewport_backends().renderer = Some(Box::new(backend));
+        self.io_mut().backend_flags |= crate::BackendFlags::RENDERER_HAS_VIEWPORTS;
+
+        let platform_io = unsafe { &mut *sys::igGetPlatformIO() };
+        platform_io.Renderer_CreateWindow = Some(renderer_create_window);
+        platform_io.Renderer_DestroyWindow = Some(renderer_destroy_window);
+        platform_io.Renderer_SetWindowSize = Some(renderer_set_window_size);
+        platform_io.Renderer_RenderWindow = Some(renderer_render_window);
+        platform_io.Renderer_SwapBuffers = Some(renderer_swap_buffers);
+    }
+
+    /// Sets the monitors windows can be placed on. At least one is needed once viewports are
+    /// enabled.
+    #[doc(alias = "Monitors")]
+    pub fn set_monitors(&mut self, monitors: &[PlatformMonitor]) {
+        let platform_io = unsafe { &mut *sys::igGetPlatformIO() };
+        let vector = &mut platform_io.Monitors;
+        unsafe {
+            // Dear ImGui frees the list with its own allocator, so it has to be allocated there
+            if !vector.Data.is_null() {
+                sys::igMemFree(vector.Data as *mut c_void);
+            }
+            let data = sys::igMemAlloc(std::mem::size_of_val(monitors)) as *mut PlatformMonitor;
+            data.copy_from_nonoverlapping(monitors.as_ptr(), monitors.len());
+            vector.Data = data as *mut sys::ImGuiPlatformMonitor;
+        }
+        vector.Size = monitors.len() as i32;
+        vector.Capacity = monitors.len() as i32;
+    }
+
+    /// The monitors set by the platform backend
+    pub fn monitors(&self) -> &[PlatformMonitor] {
+        unsafe {
+            let vector = &(*sys::igGetPlatformIO()).Monitors;
+            if vector.Data.is_null() {
+                return &[];
+            }
+            slice::from_raw_parts(vector.Data as *const PlatformMonitor, vector.Size as usize)
+        }
+    }
+
+    /// The viewport of the main window
+    #[doc(alias = "GetMainViewport")]
+    pub fn main_viewport(&self) -> &Viewport {
+        unsafe { Viewport::from_raw(&*sys::igGetMainViewport()) }
+    }
+
+    /// The viewport of the main window
+    #[doc(alias = "GetMainViewport")]
+    pub fn main_viewport_mut(&mut self) -> &mut Viewport {
+        unsafe { Viewport::from_raw_mut(&mut *sys::igGetMainViewport()) }
+    }
+
+    /// All viewports, starting with the main one
+    pub fn viewports(&self) -> impl Iterator<Item = &Viewport> {
+        let viewports = unsafe {
+            &*(&(*sys::igGetPlatformIO()).Viewports as *const sys::ImVector_ImGuiViewportPtr
+                as *const ImVector<*mut Viewport>)
+        };
+        viewports.as_slice().iter().map(|&vp| unsafe { &*vp })
+    }
+
+    /// All viewports, starting with the main one
+    pub fn viewports_mut(&mut self) -> impl Iterator<Item = &mut Viewport> {
+        let viewports = unsafe {
+            &*(&(*sys::igGetPlatformIO()).Viewports as *const sys::ImVector_ImGuiViewportPtr
+                as *const ImVector<*mut Viewport>)
+        };
+        viewports.as_slice().iter().map(|&vp| unsafe { &mut *vp })
+    }
+
+    /// Finds a viewport by id
+    #[doc(alias = "FindViewportByID")]
+    pub fn viewport_by_id(&self, id: sys::ImGuiID) -> Option<&Viewport> {
+        self.viewports().find(|viewport| viewport.id == id)
+    }
+
+    /// Creates, moves and destroys the OS windows of viewports. Has to be called every frame
+    /// after [`render`](Self::render) while viewports are enabled.
+    #[doc(alias = "UpdatePlatformWindows")]
+    pub fn update_platform_windows(&mut self) {
+        unsafe { sys::igUpdatePlatformWindows() }
+    }
+
+    /// Draws every viewport other than the main one through the renderer backend
+    #[doc(alias = "RenderPlatformWindowsDefault")]
+    pub fn render_platform_windows_default(&mut self) {
+        unsafe { sys::igRenderPlatformWindowsDefault(std::ptr::null_mut(), std::ptr::null_mut()) }
+    }
+
+    /// Destroys the OS windows of all viewports other than the main one
+    #[doc(alias = "DestroyPlatformWindows")]
+    pub fn destroy_platform_windows(&mut self) {
+        unsafe { sys::igDestroyPlatformWindows() }
+    }
+}
+
+#[test]
+#[cfg(test)]
+fn test_viewport_memory_layout() {
+    use std::mem;
+    assert_eq!(
+        mem::size_of::<Viewport>(),
+        mem::size_of::<sys::ImGuiViewport>()
+    );
+    assert_eq!(
+        mem::align_of::<Viewport>(),
+        mem::align_of::<sys::ImGuiViewport>()
+    );
+    assert_eq!(
+        mem::size_of::<PlatformMonitor>(),
+        mem::size_of::<sys::ImGuiPlatformMonitor>()
+    );
+    use sys::ImGuiViewport;
+    macro_rules! assert_field_offset {
+        ($l:ident, $r:ident) => {
+            assert_eq!(
+                memoffset::offset_of!(Viewport, $l),
+                memoffset::offset_of!(ImGuiViewport, $r)
+            );
+        };
+    }
+    assert_field_offset!(id, ID);
+    assert_field_offset!(flags, Flags);
+    assert_field_offset!(pos, Pos);
+    assert_field_offset!(size, Size);
+    assert_field_offset!(work_pos, WorkPos);
+    assert_field_offset!(work_size, WorkSize);
+    assert_field_offset!(dpi_scale, DpiScale);
+    assert_field_offset!(parent_viewport_id, ParentViewportId);
+    assert_field_offset!(draw_data, DrawData);
+    assert_field_offset!(renderer_user_data, RendererUserData);
+    assert_field_offset!(platform_user_data, PlatformUserData);
+    assert_field_offset!(platform_handle, PlatformHandle);
+    assert_field_offset!(platform_handle_raw, PlatformHandleRaw);
+    assert_field_offset!(platform_request_move, PlatformRequestMove);
+    assert_field_offset!(platform_request_resize, PlatformRequestResize);
+    assert_field_offset!(platform_request_close, PlatformRequestClose);
+}
+
+#[test]
+fn test_viewport_backends() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Default)]
+    struct Log {
+        created: Vec<sys::ImGuiID>,
+        destroyed: Vec<sys::ImGuiID>,
+        rendered: Vec<sys::ImGuiID>,
+        pos: [f32; 2],
+    }
+
+    struct Platform(Rc<RefCell<Log>>);
+    impl PlatformViewportBackend for Platform {
+        fn create_window(&mut self, viewport: &mut Viewport) {
+            self.0.borrow_mut().created.push(viewport.id);
+        }
+        fn destroy_window(&mut self, viewport: &mut Viewport) {
+            self.0.borrow_mut().destroyed.push(viewport.id);
+        }
+        fn show_window(&mut self, _: &mut Viewport) {}
+        fn set_window_pos(&mut self, _: &mut Viewport, pos: [f32; 2]) {
+            self.0.borrow_mut().pos = pos;
+        }
+        fn get_window_pos(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+            viewport.pos
+        }
+        fn set_window_size(&mut self, _: &mut Viewport, _: [f32; 2]) {}
+        fn get_window_size(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+            viewport.size
+        }
+        fn set_window_focus(&mut self, _: &mut Viewport) {}
+        fn get_window_focus(&mut self, _: &mut Viewport) -> bool {
+            false
+        }
+        fn get_window_minimized(&mut self, _: &mut Viewport) -> bool {
+            false
+        }
+        fn set_window_title(&mut self, _: &mut Viewport, _: &str) {}
+        fn set_window_alpha(&mut self, _: &mut Viewport, _: f32) {}
+        fn update_window(&mut self, _: &mut Viewport) {}
+        fn get_window_dpi_scale(&mut self, _: &mut Viewport) -> f32 {
+            1.0
+        }
+    }
+
+    struct Renderer(Rc<RefCell<Log>>);
+    impl RendererViewportBackend for Renderer {
+        fn create_window(&mut self, _: &mut Viewport) {}
+        fn destroy_window(&mut self, _: &mut Viewport) {}
+        fn set_window_size(&mut self, _: &mut Viewport, _: [f32; 2]) {}
+        fn render_window(&mut self, viewport: &mut Viewport) {
+            assert!(viewport.draw_data().is_some());
+            self.0.borrow_mut().rendered.push(viewport.id);
+        }
+        fn swap_buffers(&mut self, _: &mut Viewport) {}
+    }
+
+    let (_guard, mut ctx) = crate::test::test_ctx_initialized();
+    let log = Rc::new(RefCell::new(Log::default()));
+    ctx.set_platform_backend(Platform(log.clone()));
+    ctx.set_renderer_backend(Renderer(log.clone()));
+    ctx.set_monitors(&[PlatformMonitor {
+        main_size: [4000.0, 2000.0],
+        work_size: [4000.0, 2000.0],
+        dpi_scale: 1.0,
+        ..Default::default()
+    }]);
+    assert_eq!(ctx.monitors().len(), 1);
+    // any non-null handle will do, imgui only checks that the platform set one
+    let mut handle = 0u8;
+    ctx.main_viewport_mut().platform_handle = &mut handle as *mut u8 as *mut c_void;
+    ctx.io_mut().config_flags |= crate::ConfigFlags::VIEWPORTS_ENABLE;
+
+    for _ in 0..3 {
+        let ui = ctx.frame();
+        ui.window("Outside")
+            .position([2000.0, 100.0], crate::Condition::Always)
+            .size([200.0, 100.0], crate::Condition::Always)
+            .build(|| ui.text("Hello"));
+        ctx.render();
+        ctx.update_platform_windows();
+        ctx.render_platform_windows_default();
+    }
+
+    let created = log.borrow().created.clone();
+    assert_eq!(created.len(), 1);
+    assert!(ctx.viewports().any(|viewport| viewport.id == created[0]));
+    assert!(ctx.viewports().next().unwrap().is_main());
+    assert_eq!(log.borrow().pos, [2000.0, 100.0]);
+    assert!(log.borrow().rendered.contains(&created[0]));
+
+    // The main viewport is included, its window belongs to the application
+    ctx.destroy_platform_windows();
+    assert!(log.borrow().destroyed.contains(&created[0]));
+}
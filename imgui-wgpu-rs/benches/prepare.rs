@@ -0,0 +1,110 @@
+//! Times `Renderer::prepare` uploading large synthetic draw data, with `cargo bench --bench prepare`.
+//!
+//! Only the upload is timed, the frames are built by imgui beforehand. Set `WGPU_BACKEND` to
+//! pick the backend, the benchmark is skipped when there's no adapter.
+
+use imgui::{Condition, Context, DrawData};
+use imgui_wgpu::{Renderer, RendererConfig};
+use pollster::block_on;
+use std::time::{Duration, Instant};
+
+const FRAMES: u32 = 200;
+
+/// Draws `lines` lines in each of `windows` windows, keeping every draw list under 65536
+/// vertices so 16-bit indices are enough.
+fn frame(imgui: &mut Context, windows: usize, lines: usize) -> &DrawData {
+    let ui = imgui.new_frame();
+    for window in 0..windows {
+        ui.window(format!("window {}", window))
+            .position(
+                [(window % 8) as f32 * 240.0, (window / 8) as f32 * 180.0],
+                Condition::Always,
+            )
+            .size([240.0, 180.0], Condition::Always)
+            .build(|| {
+                let draw_list = ui.get_window_draw_list();
+                let [x, y] = ui.cursor_screen_pos();
+                for line in 0..lines {
+                    let offset = (line % 160) as f32;
+                    draw_list
+                        .add_line(
+                            [x, y + offset],
+                            [x + 200.0, y + 160.0 - offset],
+                            [1.0, 1.0, 1.0],
+                        )
+                        .build();
+                }
+            });
+    }
+    imgui.render()
+}
+
+/// Returns the time per frame of preparing frames of the given sizes in turn
+fn bench(
+    imgui: &mut Context,
+    renderer: &Renderer,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    sizes: &[(usize, usize)],
+) -> Duration {
+    let mut render_data = None;
+    let mut total = Duration::ZERO;
+    for index in 0..FRAMES {
+        let (windows, lines) = sizes[index as usize % sizes.len()];
+        let draw_data = frame(imgui, windows, lines);
+
+        let start = Instant::now();
+        render_data = Some(renderer.prepare(draw_data, render_data.take(), queue, device));
+        queue.submit(None);
+        total += start.elapsed();
+
+        // Keeps the GPU from falling behind, which would be timed as part of later frames
+        device.poll(wgpu::Maintain::Wait);
+    }
+    total / FRAMES
+}
+
+fn main() {
+    let backends = wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all);
+    let instance = wgpu::Instance::new(backends);
+    let adapter = match block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+    {
+        Some(adapter) => adapter,
+        None => {
+            eprintln!("No adapter found, skipping the benchmark");
+            return;
+        }
+    };
+    let (device, queue) =
+        block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+    println!("Adapter: {:?}", adapter.get_info());
+
+    let mut imgui = Context::create();
+    imgui.set_ini_filename(None);
+    imgui.io_mut().display_size = [1920.0, 1080.0];
+    let renderer = Renderer::new(
+        &mut imgui,
+        &device,
+        &queue,
+        RendererConfig {
+            texture_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            ..Default::default()
+        },
+    );
+
+    let small = (8, 500);
+    let large = (48, 12_000);
+    let cases = [
+        ("small frames", vec![small]),
+        ("large frames", vec![large]),
+        ("alternating sizes", vec![small, large]),
+    ];
+    for (name, sizes) in cases {
+        let time = bench(&mut imgui, &renderer, &device, &queue, &sizes);
+        println!(
+            "{:<20} {:>10.3} ms/frame",
+            name,
+            time.as_secs_f64() * 1000.0
+        );
+    }
+}
@@ -0,0 +1,453 @@
+//! Packing many small images into a single texture, so drawing them doesn't switch bind groups,
+//! and merging them into the font atlas to use them inline in text.
+
+use crate::{rgba8_format_for, Renderer, Texture, TextureConfig};
+use imgui::{Context, CustomRectId, FontAtlas, FontId, Image, TextureId, Ui};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use wgpu::{Device, Extent3d, Queue};
+
+/// Error building a sprite atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtlasError {
+    /// The sprites don't fit in a texture of the largest size the device supports
+    TooLarge { max_size: u32 },
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::TooLarge { max_size } => {
+                write!(f, "sprites don't fit in a {0}x{0} texture", max_size)
+            }
+        }
+    }
+}
+
+impl Error for AtlasError {}
+
+/// An image to pack, in 8 bit RGBA
+#[derive(Clone)]
+struct SpriteImage {
+    name: String,
+    size: [u32; 2],
+    data: Vec<u8>,
+}
+
+/// Collects named RGBA images to pack into a [`SpriteAtlas`] or merge into the font atlas.
+#[derive(Clone)]
+pub struct SpriteAtlasBuilder {
+    sprites: Vec<SpriteImage>,
+    /// Transparent pixels left around each sprite, so filtering doesn't bleed between them
+    pub padding: u32,
+}
+
+impl Default for SpriteAtlasBuilder {
+    fn default() -> Self {
+        Self {
+            sprites: Vec::new(),
+            padding: 1,
+        }
+    }
+}
+
+impl SpriteAtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an image of 8 bit RGBA pixels. Adding a name again replaces its image.
+    pub fn add(&mut self, name: impl Into<String>, width: u32, height: u32, data: Vec<u8>) {
+        assert_eq!(
+            data.len(),
+            width as usize * height as usize * 4,
+            "sprite data doesn't match its size"
+        );
+        let sprite = SpriteImage {
+            name: name.into(),
+            size: [width, height],
+            data,
+        };
+        match self.sprites.iter_mut().find(|old| old.name == sprite.name) {
+            Some(old) => *old = sprite,
+            None => self.sprites.push(sprite),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Packs the images into a texture added to the renderer.
+    pub fn build(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        renderer: &mut Renderer,
+    ) -> Result<SpriteAtlas, AtlasError> {
+        let max_size = device.limits().max_texture_dimension_2d;
+        let sizes: Vec<[u32; 2]> = self.sprites.iter().map(|sprite| sprite.size).collect();
+        let (size, positions) =
+            pack(&sizes, self.padding, max_size).ok_or(AtlasError::TooLarge { max_size })?;
+
+        let mut data = vec![0; size[0] as usize * size[1] as usize * 4];
+        for (sprite, &position) in self.sprites.iter().zip(&positions) {
+            blit(&mut data, size[0], &sprite.data, sprite.size, position);
+        }
+
+        let texture = Texture::new(
+            device,
+            renderer,
+            TextureConfig {
+                size: Extent3d {
+                    width: size[0],
+                    height: size[1],
+                    depth_or_array_layers: 1,
+                },
+                label: Some("imgui-wgpu sprite atlas"),
+                format: Some(rgba8_format_for(renderer.texture_format())),
+                ..Default::default()
+            },
+        );
+        texture.write(queue, &data, size[0], size[1]);
+        let texture_id = renderer.textures.insert(texture);
+
+        let scale = [1.0 / size[0] as f32, 1.0 / size[1] as f32];
+        let sprites = self
+            .sprites
+            .iter()
+            .zip(positions)
+            .map(|(sprite, [x, y])| {
+                let [width, height] = sprite.size;
+                let sprite_rect = Sprite {
+                    size: [width as f32, height as f32],
+                    uv0: [x as f32 * scale[0], y as f32 * scale[1]],
+                    uv1: [
+                        (x + width) as f32 * scale[0],
+                        (y + height) as f32 * scale[1],
+                    ],
+                };
+                (sprite.name.clone(), sprite_rect)
+            })
+            .collect();
+        Ok(SpriteAtlas {
+            texture_id,
+            size,
+            sprites,
+        })
+    }
+
+    /// Adds the images as glyphs of a font, the default one if `None`, from codepoint `first`
+    /// on in the order they were added. They're centered on the line and drawn in their own
+    /// colors.
+    ///
+    /// The renderer keeps the pixels to draw them into the font atlas whenever it's built, and
+    /// the font texture is reloaded.
+    pub fn merge_into_fonts(
+        &self,
+        imgui: &mut Context,
+        font: Option<FontId>,
+        first: char,
+        renderer: &mut Renderer,
+        device: &Device,
+        queue: &Queue,
+    ) -> FontIcons {
+        let mut chars = HashMap::new();
+        {
+            let fonts = imgui.fonts();
+            let font = font.unwrap_or_else(|| default_font(fonts));
+            let font_size = fonts.get_font(font).map_or(13.0, |font| font.font_size);
+            let first = first as u32;
+            for (index, sprite) in self.sprites.iter().enumerate() {
+                let c = char::from_u32(first + index as u32).expect("icon codepoint isn't a char");
+                let [width, height] = sprite.size;
+                let rect = fonts.add_custom_rect_font_glyph(
+                    font,
+                    c,
+                    width,
+                    height,
+                    width as f32 + 1.0,
+                    [0.0, ((font_size - height as f32) / 2.0).round()],
+                );
+                renderer.font_icons.push(FontIconImage {
+                    rect,
+                    size: sprite.size,
+                    data: sprite.data.clone(),
+                });
+                chars.insert(sprite.name.clone(), c);
+            }
+            // Repack the atlas with the new glyphs
+            fonts.clear_tex_data();
+        }
+        renderer.reload_font_texture(imgui, device, queue);
+        FontIcons { chars }
+    }
+}
+
+/// The font of a context used when none is pushed
+fn default_font(fonts: &mut FontAtlas) -> FontId {
+    if fonts.fonts().is_empty() {
+        fonts.add_font(&[imgui::FontSource::DefaultFontData { config: None }])
+    } else {
+        fonts.fonts()[0]
+    }
+}
+
+/// Where a sprite is in its atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    /// Size of the image in pixels
+    pub size: [f32; 2],
+    /// Texture coordinates of the top left corner
+    pub uv0: [f32; 2],
+    /// Texture coordinates of the bottom right corner
+    pub uv1: [f32; 2],
+}
+
+/// Named images packed into one texture of the renderer.
+#[derive(Debug, Clone)]
+pub struct SpriteAtlas {
+    texture_id: TextureId,
+    size: [u32; 2],
+    sprites: HashMap<String, Sprite>,
+}
+
+impl SpriteAtlas {
+    pub fn texture_id(&self) -> TextureId {
+        self.texture_id
+    }
+
+    /// Size of the texture in pixels
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    pub fn get(&self, name: &str) -> Option<Sprite> {
+        self.sprites.get(name).copied()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sprites.keys().map(String::as_str)
+    }
+}
+
+/// Creates [`Image`]s showing a sprite of an atlas.
+pub trait AtlasImage: Sized {
+    /// Shows a sprite at its own size, `None` if the atlas has no sprite of that name.
+    fn from_atlas(atlas: &SpriteAtlas, name: &str) -> Option<Self>;
+}
+
+impl AtlasImage for Image {
+    fn from_atlas(atlas: &SpriteAtlas, name: &str) -> Option<Self> {
+        let sprite = atlas.get(name)?;
+        Some(
+            Image::new(atlas.texture_id, sprite.size)
+                .uv0(sprite.uv0)
+                .uv1(sprite.uv1),
+        )
+    }
+}
+
+/// Draws sprites of an atlas as icons.
+pub trait IconUi {
+    /// Draws a sprite at its own size, returning whether the atlas has it.
+    fn icon(&self, atlas: &SpriteAtlas, name: &str) -> bool;
+}
+
+impl IconUi for Ui {
+    fn icon(&self, atlas: &SpriteAtlas, name: &str) -> bool {
+        match Image::from_atlas(atlas, name) {
+            Some(image) => {
+                image.build(self);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Characters of the icons merged into a font, to write them in text.
+#[derive(Debug, Clone, Default)]
+pub struct FontIcons {
+    chars: HashMap<String, char>,
+}
+
+impl FontIcons {
+    pub fn get(&self, name: &str) -> Option<char> {
+        self.chars.get(name).copied()
+    }
+}
+
+/// Pixels of an icon glyph, drawn into the font atlas after it's built
+pub(crate) struct FontIconImage {
+    rect: CustomRectId,
+    size: [u32; 2],
+    data: Vec<u8>,
+}
+
+/// Draws the icons into the RGBA pixels of a built font atlas.
+pub(crate) fn draw_font_icons(
+    fonts: &FontAtlas,
+    icons: &[FontIconImage],
+    data: &mut [u8],
+    width: u32,
+) {
+    for icon in icons {
+        if let Some(rect) = fonts.custom_rect(icon.rect) {
+            blit(data, width, &icon.data, icon.size, rect.position);
+        }
+    }
+}
+
+/// Copies RGBA pixels into a larger image.
+fn blit(dst: &mut [u8], dst_width: u32, src: &[u8], size: [u32; 2], position: [u32; 2]) {
+    let row = size[0] as usize * 4;
+    if row == 0 {
+        return;
+    }
+    for (y, src_row) in src.chunks_exact(row).enumerate() {
+        let start = ((position[1] as usize + y) * dst_width as usize + position[0] as usize) * 4;
+        dst[start..start + row].copy_from_slice(src_row);
+    }
+}
+
+/// Places rectangles on shelves, the tallest first, in the narrowest power of two width that
+/// holds them without going over `max_size` in height. Returns the size of the atlas and the
+/// position of each rectangle.
+fn pack(sizes: &[[u32; 2]], padding: u32, max_size: u32) -> Option<([u32; 2], Vec<[u32; 2]>)> {
+    let area: u64 = sizes
+        .iter()
+        .map(|&[width, height]| (width + padding) as u64 * (height + padding) as u64)
+        .sum();
+    let widest = sizes.iter().map(|size| size[0]).max().unwrap_or(0) + padding * 2;
+    let mut width = widest
+        .max((area as f64).sqrt().ceil() as u32)
+        .max(1)
+        .next_power_of_two();
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(sizes[index][1]));
+
+    while width <= max_size {
+        let mut positions = vec![[0, 0]; sizes.len()];
+        let (mut x, mut y, mut shelf_height) = (padding, padding, 0);
+        for &index in &order {
+            let [sprite_width, sprite_height] = sizes[index];
+            if x + sprite_width + padding > width {
+                x = padding;
+                y += shelf_height + padding;
+                shelf_height = 0;
+            }
+            positions[index] = [x, y];
+            x += sprite_width + padding;
+            shelf_height = shelf_height.max(sprite_height);
+        }
+        let height = (y + shelf_height + padding).max(1);
+        if height <= max_size {
+            return Some(([width, height], positions));
+        }
+        width *= 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::TextureFormat;
+
+    fn overlaps(a: ([u32; 2], [u32; 2]), b: ([u32; 2], [u32; 2])) -> bool {
+        let ((a_position, a_size), (b_position, b_size)) = (a, b);
+        (0..2).all(|axis| {
+            a_position[axis] < b_position[axis] + b_size[axis]
+                && b_position[axis] < a_position[axis] + a_size[axis]
+        })
+    }
+
+    #[test]
+    fn packed_sprites_stay_apart_and_inside() {
+        let sizes: Vec<[u32; 2]> = (1..40).map(|i| [i % 7 * 3 + 1, i % 5 * 4 + 2]).collect();
+        let (size, positions) = pack(&sizes, 1, 4096).unwrap();
+        assert!(size[0].is_power_of_two());
+
+        for (index, (&position, &sprite_size)) in positions.iter().zip(&sizes).enumerate() {
+            assert!(position[0] >= 1 && position[0] + sprite_size[0] < size[0]);
+            assert!(position[1] >= 1 && position[1] + sprite_size[1] < size[1]);
+            for (&other_position, &other_size) in positions.iter().zip(&sizes).skip(index + 1) {
+                // with the padding included, sprites still don't touch
+                let padded = [sprite_size[0] + 1, sprite_size[1] + 1];
+                assert!(!overlaps((position, padded), (other_position, other_size)));
+            }
+        }
+    }
+
+    #[test]
+    fn packing_respects_the_size_limit() {
+        let sizes = vec![[10, 30]; 4];
+        let (size, _) = pack(&sizes, 0, 64).unwrap();
+        assert_eq!(size, [64, 30]);
+        assert_eq!(pack(&sizes, 0, 32), None);
+        assert_eq!(pack(&[], 1, 64), Some(([2, 2], vec![])));
+    }
+
+    #[test]
+    fn sprites_are_copied_by_row() {
+        let mut atlas = vec![0; 4 * 3 * 4];
+        let sprite: Vec<u8> = (1..=2 * 2 * 4).collect();
+        blit(&mut atlas, 4, &sprite, [2, 2], [1, 1]);
+        assert_eq!(&atlas[4 * 4..4 * 4 + 4], &[0; 4]);
+        assert_eq!(&atlas[(4 + 1) * 4..(4 + 3) * 4], &sprite[..8]);
+        assert_eq!(&atlas[(8 + 1) * 4..(8 + 3) * 4], &sprite[8..]);
+    }
+
+    #[test]
+    fn icons_are_drawn_where_the_font_atlas_packed_them() {
+        let mut imgui = Context::create();
+        let fonts = imgui.fonts();
+        let font = default_font(fonts);
+        let rect = fonts.add_custom_rect_font_glyph(font, '\u{e000}', 2, 1, 3.0, [0.0, 0.0]);
+        let icons = [FontIconImage {
+            rect,
+            size: [2, 1],
+            data: vec![255, 0, 0, 255, 0, 255, 0, 255],
+        }];
+
+        let texture = fonts.build_rgba32_texture();
+        let width = texture.width;
+        let mut data = texture.data.to_vec();
+        draw_font_icons(fonts, &icons, &mut data, width);
+
+        let [x, y] = fonts.custom_rect(rect).unwrap().position;
+        let start = ((y * width + x) * 4) as usize;
+        assert_eq!(&data[start..start + 8], &icons[0].data[..]);
+    }
+
+    #[test]
+    fn names_are_replaced() {
+        let mut builder = SpriteAtlasBuilder::new();
+        builder.add("folder", 1, 1, vec![0; 4]);
+        builder.add("file", 1, 1, vec![0; 4]);
+        builder.add("folder", 2, 1, vec![0; 8]);
+        assert_eq!(builder.len(), 2);
+        assert_eq!(builder.sprites[0].size, [2, 1]);
+    }
+
+    #[test]
+    fn atlases_are_rgba_on_bgra_targets() {
+        // the sprites are RGBA bytes, a BGRA atlas would swap red and blue
+        let formats = [
+            (TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgba8UnormSrgb),
+            (TextureFormat::Bgra8Unorm, TextureFormat::Rgba8Unorm),
+            (TextureFormat::Rgba8Unorm, TextureFormat::Rgba8Unorm),
+            (TextureFormat::Rgba16Float, TextureFormat::Rgba8UnormSrgb),
+        ];
+        for (target, atlas) in formats {
+            assert_eq!(rgba8_format_for(target), atlas, "{:?}", target);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 struct Uniforms {
     u_Matrix: mat4x4<f32>,
+    // x: scale of paper white, y: peak brightness colors are rolled off toward, 0 for none
+    u_Hdr: vec4<f32>,
 };
 
 struct VertexInput {
@@ -57,3 +59,23 @@ fn fs_main_srgb(in: VertexOutput) -> FragmentOutput {
 
     return FragmentOutput(color * textureSample(u_Texture, u_Sampler, in.v_UV));
 }
+
+// Rolls colors off smoothly toward the peak above 80% of it, keeping their hue
+fn tonemap(color: vec3<f32>, peak: f32) -> vec3<f32> {
+    let brightest = max(color.r, max(color.g, color.b));
+    let knee = peak * 0.8;
+    if (peak <= 0.0 || brightest <= knee) {
+        return color;
+    }
+    let range = peak - knee;
+    let mapped = knee + range * (1.0 - exp(-(brightest - knee) / range));
+    return color * (mapped / brightest);
+}
+
+@fragment
+fn fs_main_hdr(in: VertexOutput) -> FragmentOutput {
+    let color = srgb_to_linear(in.v_Color) * textureSample(u_Texture, u_Sampler, in.v_UV);
+    let rgb = tonemap(color.rgb * uniforms.u_Hdr.x, uniforms.u_Hdr.y);
+
+    return FragmentOutput(vec4<f32>(rgb, color.a));
+}
@@ -6,12 +6,112 @@ use std::fmt;
 use std::mem::size_of;
 use std::sync::Arc;
 use std::{error::Error, num::NonZeroU32};
-use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::*;
 
+mod atlas;
+#[cfg(feature = "image")]
+mod loader;
+mod mipmap;
+use atlas::FontIconImage;
+pub use atlas::{
+    AtlasError, AtlasImage, FontIcons, IconUi, Sprite, SpriteAtlas, SpriteAtlasBuilder,
+};
+#[cfg(feature = "image")]
+pub use loader::{ColorSpace, LoadError, LoadOptions, TextureLoader};
+pub use mipmap::full_mip_level_count;
+use mipmap::MipmapBlitter;
+mod upload;
+use upload::FrameBuffer;
+
 static VS_ENTRY_POINT: &str = "vs_main";
 static FS_ENTRY_POINT_LINEAR: &str = "fs_main_linear";
 static FS_ENTRY_POINT_SRGB: &str = "fs_main_srgb";
+static FS_ENTRY_POINT_HDR: &str = "fs_main_hdr";
+
+/// Format of the indices imgui was compiled with, 32 bits with its `draw-idx-32` feature
+const INDEX_FORMAT: IndexFormat = match size_of::<DrawIdx>() {
+    2 => IndexFormat::Uint16,
+    _ => IndexFormat::Uint32,
+};
+
+/// Brightness of a color of 1.0 in a float target, as in scRGB.
+pub const SCRGB_REFERENCE_NITS: f32 = 80.0;
+
+/// Returns whether colors written to a target of this format have to be linear.
+///
+/// sRGB formats encode linear colors on write, and float formats store linear colors as they are.
+/// Everything else stores the sRGB colors imgui works with without conversion.
+pub fn format_expects_linear(format: TextureFormat) -> bool {
+    is_float_format(format) || format.describe().srgb
+}
+
+/// Returns the format of textures filled with RGBA bytes, such as the font and sprite atlases,
+/// drawn to a target of this format. Its channels have to be in RGBA order whatever the target
+/// is, and it's sRGB where the target expects linear colors.
+pub(crate) fn rgba8_format_for(target: TextureFormat) -> TextureFormat {
+    if format_expects_linear(target) {
+        TextureFormat::Rgba8UnormSrgb
+    } else {
+        TextureFormat::Rgba8Unorm
+    }
+}
+
+/// Returns whether a format stores colors as floats, which can go beyond 1.0 for HDR.
+fn is_float_format(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::R16Float
+            | TextureFormat::Rg16Float
+            | TextureFormat::Rgba16Float
+            | TextureFormat::R32Float
+            | TextureFormat::Rg32Float
+            | TextureFormat::Rgba32Float
+            | TextureFormat::Rg11b10Float
+            | TextureFormat::Rgb9e5Ufloat
+            | TextureFormat::Bc6hRgbUfloat
+            | TextureFormat::Bc6hRgbSfloat
+            | TextureFormat::Astc {
+                channel: AstcChannel::Hdr,
+                ..
+            }
+    )
+}
+
+/// Returns the entry point of the default fragment shader that outputs the colors a target of
+/// this format expects.
+///
+/// Float targets get linear colors scaled by the [`HdrConfig`] of the renderer.
+pub fn fragment_entry_point_for(format: TextureFormat) -> &'static str {
+    if is_float_format(format) {
+        FS_ENTRY_POINT_HDR
+    } else if format_expects_linear(format) {
+        FS_ENTRY_POINT_LINEAR
+    } else {
+        FS_ENTRY_POINT_SRGB
+    }
+}
+
+/// Warns when an entry point named like one of the default shader's writes colors in another
+/// color space than the format expects, the UI would come out too dark or too bright.
+fn check_fragment_entry_point(entry_point: &str, format: TextureFormat) {
+    let expected = fragment_entry_point_for(format);
+    let known = [
+        FS_ENTRY_POINT_LINEAR,
+        FS_ENTRY_POINT_SRGB,
+        FS_ENTRY_POINT_HDR,
+    ];
+    if known.contains(&entry_point)
+        && (entry_point != FS_ENTRY_POINT_SRGB) != format_expects_linear(format)
+    {
+        log::warn!(
+            "imgui-wgpu: fragment shader entry point `{}` doesn't match the texture format {:?}, \
+             which expects `{}`",
+            entry_point,
+            format,
+            expected
+        );
+    }
+}
 
 pub type RendererResult<T> = Result<T, RendererError>;
 
@@ -66,11 +166,12 @@ pub struct TextureConfig<'a> {
     pub size: Extent3d,
     /// An optional label for the texture used for debugging.
     pub label: Option<&'a str>,
-    /// The format of the texture, if not set uses the format from the renderer.
+    /// The format of the texture, if not set uses the format from the renderer, or 8 bit sRGB
+    /// when rendering to a float target.
     pub format: Option<TextureFormat>,
     /// The usage of the texture.
     pub usage: TextureUsages,
-    /// The mip level of the texture.
+    /// The number of mip levels of the texture, generated when it's written.
     pub mip_level_count: u32,
     /// The sample count of the texture.
     pub sample_count: u32,
@@ -115,12 +216,27 @@ impl<'a> Default for TextureConfig<'a> {
     }
 }
 
+impl<'a> TextureConfig<'a> {
+    /// Gives the texture a full mip chain for its size, usable for rendering the levels on the
+    /// GPU, and sampled with trilinear filtering.
+    pub fn with_mipmaps(mut self) -> Self {
+        self.mip_level_count = full_mip_level_count(self.size.width, self.size.height);
+        self.usage |= TextureUsages::RENDER_ATTACHMENT;
+        self.sampler_desc.mipmap_filter = FilterMode::Linear;
+        self
+    }
+}
+
 /// A container for a bindable texture.
 pub struct Texture {
     texture: Arc<wgpu::Texture>,
     view: Arc<wgpu::TextureView>,
     bind_group: Arc<BindGroup>,
     size: Extent3d,
+    /// Format of textures created by `Texture::new`, to generate their mip levels with
+    format: Option<TextureFormat>,
+    usage: TextureUsages,
+    mip_level_count: u32,
 }
 
 impl Texture {
@@ -164,11 +280,18 @@ impl Texture {
             view,
             bind_group,
             size,
+            format: None,
+            usage: TextureUsages::empty(),
+            mip_level_count: 1,
         }
     }
 
     /// Create a new GPU texture width the specified `config`.
     pub fn new(device: &Device, renderer: &Renderer, config: TextureConfig) -> Self {
+        let format = config
+            .format
+            .unwrap_or_else(|| renderer.default_texture_format());
+
         // Create the wgpu texture.
         let texture = Arc::new(device.create_texture(&TextureDescriptor {
             label: config.label,
@@ -176,7 +299,7 @@ impl Texture {
             mip_level_count: config.mip_level_count,
             sample_count: config.sample_count,
             dimension: config.dimension,
-            format: config.format.unwrap_or(renderer.config.texture_format),
+            format,
             usage: config.usage,
         }));
 
@@ -207,20 +330,109 @@ impl Texture {
             view,
             bind_group,
             size: config.size,
+            format: Some(format),
+            usage: config.usage,
+            mip_level_count: config.mip_level_count,
         }
     }
 
     /// Write `data` to the texture.
     ///
+    /// When the texture has mip levels and `data` covers all of it, the other levels are
+    /// generated on the CPU. [`write_mipmapped`](Self::write_mipmapped) generates them on the
+    /// GPU instead.
+    ///
     /// - `data`: 32-bit RGBA bitmap data.
     /// - `width`: The width of the source bitmap (`data`) in pixels.
     /// - `height`: The height of the source bitmap (`data`) in pixels.
     pub fn write(&self, queue: &Queue, data: &[u8], width: u32, height: u32) {
+        self.write_level(queue, 0, data, width, height);
+
+        let format = match self.mip_format(width, height) {
+            Some(format) if mipmap::cpu_supports(format) => format,
+            Some(format) => {
+                log::warn!(
+                    "mip levels of {:?} textures can't be generated on the CPU",
+                    format
+                );
+                return;
+            }
+            None => return,
+        };
+        let levels = mipmap::generate_levels(
+            data,
+            width,
+            height,
+            self.mip_level_count,
+            format.describe().srgb,
+        );
+        for (level, data) in (1..).zip(&levels) {
+            let width = (width >> level).max(1);
+            let height = (height >> level).max(1);
+            self.write_level(queue, level, data, width, height);
+        }
+    }
+
+    /// Write `data` to the texture, rendering its other mip levels on the GPU.
+    ///
+    /// Textures created without [`TextureConfig::with_mipmaps`] or with a format that can't be
+    /// rendered to get their levels generated on the CPU, as with [`write`](Self::write).
+    pub fn write_mipmapped(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        renderer: &Renderer,
+        data: &[u8],
+        width: u32,
+        height: u32,
+    ) {
+        match self.mip_format(width, height) {
+            Some(format) if mipmap::gpu_supports(format, self.usage) => {
+                self.write_level(queue, 0, data, width, height);
+                renderer.mipmaps.generate(
+                    device,
+                    queue,
+                    &self.texture,
+                    format,
+                    self.mip_level_count,
+                );
+            }
+            _ => self.write(queue, data, width, height),
+        }
+    }
+
+    /// Renders the mip levels of the texture from the content of its first level, such as after
+    /// rendering into it.
+    pub fn generate_mipmaps(&self, device: &Device, queue: &Queue, renderer: &Renderer) {
+        match self.mip_format(self.size.width, self.size.height) {
+            Some(format) if mipmap::gpu_supports(format, self.usage) => renderer.mipmaps.generate(
+                device,
+                queue,
+                &self.texture,
+                format,
+                self.mip_level_count,
+            ),
+            Some(format) => log::warn!(
+                "mip levels of {:?} textures without RENDER_ATTACHMENT usage can't be rendered",
+                format
+            ),
+            None => {}
+        }
+    }
+
+    /// Returns the format of the texture if it has mip levels to generate from a write of the
+    /// given size.
+    fn mip_format(&self, width: u32, height: u32) -> Option<TextureFormat> {
+        let covered = width == self.size.width && height == self.size.height;
+        self.format.filter(|_| self.mip_level_count > 1 && covered)
+    }
+
+    fn write_level(&self, queue: &Queue, level: u32, data: &[u8], width: u32, height: u32) {
         queue.write_texture(
             // destination (sub)texture
             ImageCopyTexture {
                 texture: &self.texture,
-                mip_level: 0,
+                mip_level: level,
                 origin: Origin3d { x: 0, y: 0, z: 0 },
                 aspect: TextureAspect::All,
             },
@@ -261,6 +473,11 @@ impl Texture {
         self.size
     }
 
+    /// The number of mip levels of the texture.
+    pub fn mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
     /// The underlying `wgpu::Texture`.
     pub fn texture(&self) -> &wgpu::Texture {
         &self.texture
@@ -272,6 +489,43 @@ impl Texture {
     }
 }
 
+/// Brightness of the UI drawn to float render targets, such as an HDR `Rgba16Float` scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrConfig {
+    /// Brightness of white UI elements in nits.
+    pub paper_white_nits: f32,
+    /// Brightness of a color of 1.0 in the render target in nits,
+    /// [`SCRGB_REFERENCE_NITS`] for scRGB surfaces.
+    pub reference_nits: f32,
+    /// Brightness in nits colors beyond paper white are rolled off toward, for HDR images and
+    /// color swatches drawn as float textures. `None` draws them unchanged.
+    ///
+    /// Vertex colors are 8 bit, so the swatches imgui draws itself, such as those of
+    /// `ColorEdit::hdr`, are clamped to paper white and never reach the roll-off.
+    pub peak_nits: Option<f32>,
+}
+
+impl Default for HdrConfig {
+    /// Paper white at the reference brightness, which draws the UI as on SDR targets.
+    fn default() -> Self {
+        HdrConfig {
+            paper_white_nits: SCRGB_REFERENCE_NITS,
+            reference_nits: SCRGB_REFERENCE_NITS,
+            peak_nits: None,
+        }
+    }
+}
+
+impl HdrConfig {
+    /// The parameters of the default shader: paper white and peak relative to the reference.
+    fn uniform(&self) -> [f32; 4] {
+        let peak = self
+            .peak_nits
+            .map_or(0.0, |peak| peak / self.reference_nits);
+        [self.paper_white_nits / self.reference_nits, peak, 0.0, 0.0]
+    }
+}
+
 /// Configuration for the renderer.
 pub struct RendererConfig<'s> {
     pub texture_format: TextureFormat,
@@ -279,7 +533,11 @@ pub struct RendererConfig<'s> {
     pub sample_count: u32,
     pub shader: Option<ShaderModuleDescriptor<'s>>,
     pub vertex_shader_entry_point: Option<&'s str>,
+    /// Entry point of the fragment shader, `None` picks the one of the default shader matching
+    /// the texture format.
     pub fragment_shader_entry_point: Option<&'s str>,
+    /// Brightness of the UI on float render targets.
+    pub hdr: HdrConfig,
 }
 
 impl<'s> RendererConfig<'s> {
@@ -292,24 +550,46 @@ impl<'s> RendererConfig<'s> {
             shader: Some(shader),
             vertex_shader_entry_point: Some(VS_ENTRY_POINT),
             fragment_shader_entry_point: Some(FS_ENTRY_POINT_LINEAR),
+            hdr: HdrConfig::default(),
+        }
+    }
+}
+
+impl RendererConfig<'_> {
+    fn target(&self) -> PipelineTarget {
+        PipelineTarget {
+            texture_format: self.texture_format,
+            sample_count: self.sample_count,
+            depth_format: self.depth_format,
         }
     }
 }
 
 impl Default for RendererConfig<'_> {
-    /// Create a new renderer config with precompiled default shaders outputting linear color.
-    ///
-    /// If you write to a Bgra8UnormSrgb framebuffer, this is what you want.
+    /// Create a new renderer config with precompiled default shaders outputting the color space
+    /// the texture format expects.
     fn default() -> Self {
         Self::new()
     }
 }
 
 impl RendererConfig<'_> {
+    /// Create a new renderer config with precompiled default shaders outputting the color space
+    /// the texture format expects.
+    ///
+    /// Linear color is written to sRGB and float framebuffers, sRGB color to all others. Float
+    /// framebuffers also get the brightness of [`RendererConfig::hdr`].
+    pub fn new() -> Self {
+        RendererConfig {
+            fragment_shader_entry_point: None,
+            ..Self::with_shaders(include_wgsl!("imgui.wgsl"))
+        }
+    }
+
     /// Create a new renderer config with precompiled default shaders outputting linear color.
     ///
     /// If you write to a Bgra8UnormSrgb framebuffer, this is what you want.
-    pub fn new() -> Self {
+    pub fn new_linear() -> Self {
         RendererConfig {
             fragment_shader_entry_point: Some(FS_ENTRY_POINT_LINEAR),
             ..Self::with_shaders(include_wgsl!("imgui.wgsl"))
@@ -331,23 +611,70 @@ pub struct RenderData {
     fb_size: [f32; 2],
     last_size: [f32; 2],
     last_pos: [f32; 2],
-    vertex_buffer: Option<Buffer>,
-    vertex_buffer_size: usize,
-    index_buffer: Option<Buffer>,
-    index_buffer_size: usize,
+    vertex_buffer: FrameBuffer,
+    index_buffer: FrameBuffer,
     draw_list_offsets: SmallVec<[(i32, u32); 4]>,
+    /// Each render data has its own matrix, so several viewports can be prepared for one frame
+    uniform: Option<(Buffer, BindGroup)>,
+    /// HDR parameters last written to the uniform buffer
+    hdr: Option<HdrConfig>,
     render: bool,
 }
 
+/// What a pipeline is built to draw into
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct PipelineTarget {
+    texture_format: TextureFormat,
+    sample_count: u32,
+    depth_format: Option<TextureFormat>,
+}
+
+/// The pipeline of the current render target, and the one of the target before
+struct Pipelines<P = RenderPipeline> {
+    current: (PipelineTarget, P),
+    previous: Option<(PipelineTarget, P)>,
+}
+
+impl<P> Pipelines<P> {
+    fn new(target: PipelineTarget, pipeline: P) -> Self {
+        Pipelines {
+            current: (target, pipeline),
+            previous: None,
+        }
+    }
+
+    /// Makes the pipeline for `target` current, building it unless it's the current or the
+    /// previous one
+    fn switch(&mut self, target: PipelineTarget, build: impl FnOnce() -> P) {
+        if self.current.0 == target {
+            return;
+        }
+        let pipeline = match self.previous.take() {
+            Some(previous) if previous.0 == target => previous,
+            _ => (target, build()),
+        };
+        self.previous = Some(std::mem::replace(&mut self.current, pipeline));
+    }
+}
+
 pub struct Renderer {
-    pipeline: RenderPipeline,
-    uniform_buffer: Buffer,
-    uniform_bind_group: BindGroup,
+    pipelines: Pipelines,
+    /// Shader and entry points the pipelines are built with
+    shader: ShaderModule,
+    vertex_entry_point: String,
+    /// Picked for the texture format when `None`
+    fragment_entry_point: Option<String>,
+    uniform_layout: BindGroupLayout,
     /// Textures of the font atlas and all images.
     pub textures: Textures<Texture>,
     texture_layout: BindGroupLayout,
     render_data: Option<RenderData>,
     config: RendererConfig<'static>,
+    mipmaps: MipmapBlitter,
+    /// Icons drawn into the font atlas whenever it's built
+    font_icons: Vec<FontIconImage>,
+    /// Id of the font texture borrowed from another renderer
+    shared_font_texture: Option<TextureId>,
 }
 
 impl Renderer {
@@ -358,33 +685,35 @@ impl Renderer {
         queue: &Queue,
         config: RendererConfig,
     ) -> Self {
-        let RendererConfig {
-            texture_format,
-            depth_format,
-            sample_count,
-            shader,
-            vertex_shader_entry_point,
-            fragment_shader_entry_point,
-        } = config;
+        let mut renderer = Self::without_fonts(device, config);
 
-        // Load shaders.
-        let shader_module = device.create_shader_module(shader.unwrap());
+        // Immediately load the font texture to the GPU.
+        renderer.reload_font_texture(imgui, device, queue);
 
-        // Create the uniform matrix buffer.
-        let size = 64;
-        let uniform_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("imgui-wgpu uniform buffer"),
-            size,
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        renderer
+    }
+
+    /// Create a renderer for a context sharing the font atlas of the context `fonts` draws,
+    /// which draws text with the font texture of `fonts` instead of uploading a copy of it.
+    /// Icons merged into the fonts of `fonts` show up in both.
+    pub fn with_shared_font_texture(
+        imgui: &mut Context,
+        device: &Device,
+        config: RendererConfig,
+        fonts: &Renderer,
+    ) -> Self {
+        let mut renderer = Self::without_fonts(device, config);
+        renderer.share_font_texture(imgui, fonts);
+        renderer
+    }
 
+    fn without_fonts(device: &Device, config: RendererConfig) -> Self {
         // Create the uniform matrix buffer bind group layout.
         let uniform_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: None,
             entries: &[BindGroupLayoutEntry {
                 binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                 ty: BindingType::Buffer {
                     ty: BufferBindingType::Uniform,
                     has_dynamic_offset: false,
@@ -394,16 +723,6 @@ impl Renderer {
             }],
         });
 
-        // Create the uniform matrix buffer bind group.
-        let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("imgui-wgpu bind group"),
-            layout: &uniform_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-        });
-
         // Create the texture layout for further usage.
         let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("imgui-wgpu bind group layout"),
@@ -427,21 +746,205 @@ impl Renderer {
             ],
         });
 
+        let (shader, vertex_entry_point, fragment_entry_point, config) =
+            Self::create_shader(device, config);
+        let target = config.target();
+        let pipeline = Self::create_pipeline(
+            device,
+            &uniform_layout,
+            &texture_layout,
+            &shader,
+            &vertex_entry_point,
+            fragment_entry_point.as_deref(),
+            target,
+        );
+
+        Self {
+            pipelines: Pipelines::new(target, pipeline),
+            shader,
+            vertex_entry_point,
+            fragment_entry_point,
+            uniform_layout,
+            textures: Textures::new(),
+            texture_layout,
+            render_data: None,
+            config,
+            mipmaps: MipmapBlitter::new(device),
+            font_icons: Vec::new(),
+            shared_font_texture: None,
+        }
+    }
+
+    /// Rebuilds the pipeline for another render target, such as one with another format, sample
+    /// count or depth buffer. Textures, including the font atlas, are kept.
+    pub fn reconfigure(&mut self, device: &Device, config: RendererConfig) {
+        let (shader, vertex_entry_point, fragment_entry_point, config) =
+            Self::create_shader(device, config);
+        let target = config.target();
+        let pipeline = Self::create_pipeline(
+            device,
+            &self.uniform_layout,
+            &self.texture_layout,
+            &shader,
+            &vertex_entry_point,
+            fragment_entry_point.as_deref(),
+            target,
+        );
+        self.pipelines = Pipelines::new(target, pipeline);
+        self.shader = shader;
+        self.vertex_entry_point = vertex_entry_point;
+        self.fragment_entry_point = fragment_entry_point;
+        self.config = config;
+    }
+
+    /// Makes the pipeline draw into another render target, keeping the shader and entry points
+    /// it was configured with. Nothing is rebuilt when the target is the same, and the pipeline
+    /// of the previous target is kept, so drawing into two targets in turn builds each once.
+    pub fn retarget(
+        &mut self,
+        device: &Device,
+        texture_format: TextureFormat,
+        sample_count: u32,
+        depth_format: Option<TextureFormat>,
+    ) {
+        let target = PipelineTarget {
+            texture_format,
+            sample_count,
+            depth_format,
+        };
+        let Self {
+            pipelines,
+            shader,
+            vertex_entry_point,
+            fragment_entry_point,
+            uniform_layout,
+            texture_layout,
+            ..
+        } = self;
+        pipelines.switch(target, || {
+            Self::create_pipeline(
+                device,
+                uniform_layout,
+                texture_layout,
+                shader,
+                vertex_entry_point,
+                fragment_entry_point.as_deref(),
+                target,
+            )
+        });
+        self.config.texture_format = texture_format;
+        self.config.sample_count = sample_count;
+        self.config.depth_format = depth_format;
+    }
+
+    /// Returns the format of the render target the pipeline is built for.
+    pub fn texture_format(&self) -> TextureFormat {
+        self.config.texture_format
+    }
+
+    /// Returns the sample count of the render target the pipeline is built for.
+    pub fn sample_count(&self) -> u32 {
+        self.config.sample_count
+    }
+
+    /// Returns the depth format of the render pass the pipeline is built for.
+    pub fn depth_format(&self) -> Option<TextureFormat> {
+        self.config.depth_format
+    }
+
+    /// Creates the shader module of a config, returning it with the entry points and the config
+    /// to keep.
+    fn create_shader(
+        device: &Device,
+        config: RendererConfig,
+    ) -> (
+        ShaderModule,
+        String,
+        Option<String>,
+        RendererConfig<'static>,
+    ) {
+        let RendererConfig {
+            texture_format,
+            depth_format,
+            sample_count,
+            shader,
+            vertex_shader_entry_point,
+            fragment_shader_entry_point,
+            hdr,
+        } = config;
+
+        // Load shaders.
+        let shader_module = device.create_shader_module(shader.unwrap());
+
+        let config = RendererConfig {
+            texture_format,
+            depth_format,
+            sample_count,
+            shader: None,
+            vertex_shader_entry_point: None,
+            fragment_shader_entry_point: None,
+            hdr,
+        };
+        (
+            shader_module,
+            vertex_shader_entry_point.unwrap().to_owned(),
+            fragment_shader_entry_point.map(str::to_owned),
+            config,
+        )
+    }
+
+    /// Creates the render pipeline for a target.
+    fn create_pipeline(
+        device: &Device,
+        uniform_layout: &BindGroupLayout,
+        texture_layout: &BindGroupLayout,
+        shader_module: &ShaderModule,
+        vertex_shader_entry_point: &str,
+        fragment_shader_entry_point: Option<&str>,
+        target: PipelineTarget,
+    ) -> RenderPipeline {
+        let PipelineTarget {
+            texture_format,
+            sample_count,
+            depth_format,
+        } = target;
+
+        let fragment_shader_entry_point = match fragment_shader_entry_point {
+            Some(entry_point) => {
+                check_fragment_entry_point(entry_point, texture_format);
+                entry_point
+            }
+            None => fragment_entry_point_for(texture_format),
+        };
+
         // Create the render pipeline layout.
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("imgui-wgpu pipeline layout"),
-            bind_group_layouts: &[&uniform_layout, &texture_layout],
+            bind_group_layouts: &[uniform_layout, texture_layout],
             push_constant_ranges: &[],
         });
 
+        // 32 bit float targets can't be blended without extra features, the UI is drawn opaque
+        let blendable = texture_format
+            .describe()
+            .guaranteed_format_features
+            .flags
+            .contains(TextureFormatFeatureFlags::BLENDABLE);
+        if !blendable {
+            log::warn!(
+                "imgui-wgpu: texture format {:?} can't be blended, the UI is drawn without \
+                 transparency",
+                texture_format
+            );
+        }
+
         // Create the render pipeline.
-        // Create the render pipeline.
-        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
             label: Some("imgui-wgpu pipeline"),
             layout: Some(&pipeline_layout),
             vertex: VertexState {
-                module: &shader_module,
-                entry_point: vertex_shader_entry_point.unwrap(),
+                module: shader_module,
+                entry_point: vertex_shader_entry_point,
                 buffers: &[VertexBufferLayout {
                     array_stride: size_of::<DrawVert>() as BufferAddress,
                     step_mode: VertexStepMode::Vertex,
@@ -469,11 +972,11 @@ impl Renderer {
                 ..Default::default()
             },
             fragment: Some(FragmentState {
-                module: &shader_module,
-                entry_point: fragment_shader_entry_point.unwrap(),
+                module: shader_module,
+                entry_point: fragment_shader_entry_point,
                 targets: &[Some(ColorTargetState {
                     format: texture_format,
-                    blend: Some(BlendState {
+                    blend: blendable.then_some(BlendState {
                         color: BlendComponent {
                             src_factor: BlendFactor::SrcAlpha,
                             dst_factor: BlendFactor::OneMinusSrcAlpha,
@@ -489,29 +992,7 @@ impl Renderer {
                 })],
             }),
             multiview: None,
-        });
-
-        let mut renderer = Self {
-            pipeline,
-            uniform_buffer,
-            uniform_bind_group,
-            textures: Textures::new(),
-            texture_layout,
-            render_data: None,
-            config: RendererConfig {
-                texture_format,
-                depth_format,
-                sample_count,
-                shader: None,
-                vertex_shader_entry_point: None,
-                fragment_shader_entry_point: None,
-            },
-        };
-
-        // Immediately load the font texture to the GPU.
-        renderer.reload_font_texture(imgui, device, queue);
-
-        renderer
+        })
     }
 
     /// Prepares buffers for the current imgui frame.  This must be
@@ -531,11 +1012,11 @@ impl Renderer {
             fb_size: [fb_width, fb_height],
             last_size: [0.0, 0.0],
             last_pos: [0.0, 0.0],
-            vertex_buffer: None,
-            vertex_buffer_size: 0,
-            index_buffer: None,
-            index_buffer_size: 0,
+            vertex_buffer: FrameBuffer::new("imgui-wgpu vertex buffer", BufferUsages::VERTEX),
+            index_buffer: FrameBuffer::new("imgui-wgpu index buffer", BufferUsages::INDEX),
             draw_list_offsets: SmallVec::<[_; 4]>::new(),
+            uniform: None,
+            hdr: None,
             render: false,
         });
 
@@ -547,8 +1028,15 @@ impl Renderer {
             render_data.render = true;
         }
 
+        // The matrix buffer is created along with the render data, and written right away
+        let new_uniform = render_data.uniform.is_none();
+        if new_uniform {
+            render_data.uniform = Some(self.create_uniform(device));
+        }
+
         // Only update matrices if the size or position changes
-        if (render_data.last_size[0] - draw_data.display_size[0]).abs() > f32::EPSILON
+        if new_uniform
+            || (render_data.last_size[0] - draw_data.display_size[0]).abs() > f32::EPSILON
             || (render_data.last_size[1] - draw_data.display_size[1]).abs() > f32::EPSILON
             || (render_data.last_pos[0] - draw_data.display_pos[0]).abs() > f32::EPSILON
             || (render_data.last_pos[1] - draw_data.display_pos[1]).abs() > f32::EPSILON
@@ -571,7 +1059,18 @@ impl Renderer {
                 [0.0, 0.0, 1.0, 0.0],
                 [-1.0 - offset_x * 2.0, 1.0 + offset_y * 2.0, 0.0, 1.0],
             ];
-            self.update_uniform_buffer(queue, &matrix);
+            let (uniform_buffer, _) = render_data.uniform.as_ref().unwrap();
+            Self::update_uniform_buffer(queue, uniform_buffer, &matrix);
+        }
+
+        if render_data.hdr != Some(self.config.hdr) {
+            render_data.hdr = Some(self.config.hdr);
+            let (uniform_buffer, _) = render_data.uniform.as_ref().unwrap();
+            queue.write_buffer(
+                uniform_buffer,
+                64,
+                bytemuck::bytes_of(&self.config.hdr.uniform()),
+            );
         }
 
         render_data.draw_list_offsets.clear();
@@ -586,58 +1085,47 @@ impl Renderer {
             index_count += draw_list.idx_buffer().len();
         }
 
-        let mut vertices = Vec::with_capacity(vertex_count * std::mem::size_of::<DrawVertPod>());
-        let mut indices = Vec::with_capacity(index_count * std::mem::size_of::<DrawIdx>());
-
-        for draw_list in draw_data.draw_lists() {
+        // The draw lists are copied straight into the staging memory of the queue
+        let vertex_size = (vertex_count * size_of::<DrawVert>()) as BufferAddress;
+        let vertex_buffer = render_data.vertex_buffer.reserve(device, vertex_size);
+        Self::write_draw_lists(queue, vertex_buffer, vertex_size, draw_data, |draw_list| {
             // Safety: DrawVertPod is #[repr(transparent)] over DrawVert and DrawVert _should_ be Pod.
             let vertices_pod: &[DrawVertPod] = unsafe { draw_list.transmute_vtx_buffer() };
-            vertices.extend_from_slice(bytemuck::cast_slice(vertices_pod));
-            indices.extend_from_slice(bytemuck::cast_slice(draw_list.idx_buffer()));
-        }
+            bytemuck::cast_slice(vertices_pod)
+        });
 
         // Copies in wgpu must be padded to 4 byte alignment
-        indices.resize(
-            indices.len() + COPY_BUFFER_ALIGNMENT as usize
-                - indices.len() % COPY_BUFFER_ALIGNMENT as usize,
-            0,
-        );
-
-        // If the buffer is not created or is too small for the new indices, create a new buffer
-        if render_data.index_buffer.is_none() || render_data.index_buffer_size < indices.len() {
-            let buffer = device.create_buffer_init(&BufferInitDescriptor {
-                label: Some("imgui-wgpu index buffer"),
-                contents: &indices,
-                usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
-            });
-            render_data.index_buffer = Some(buffer);
-            render_data.index_buffer_size = indices.len();
-        } else if let Some(buffer) = render_data.index_buffer.as_ref() {
-            // The buffer is large enough for the new indices, so reuse it
-            queue.write_buffer(buffer, 0, &indices);
-        } else {
-            unreachable!()
-        }
-
-        // If the buffer is not created or is too small for the new vertices, create a new buffer
-        if render_data.vertex_buffer.is_none() || render_data.vertex_buffer_size < vertices.len() {
-            let buffer = device.create_buffer_init(&BufferInitDescriptor {
-                label: Some("imgui-wgpu vertex buffer"),
-                contents: &vertices,
-                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-            });
-            render_data.vertex_buffer = Some(buffer);
-            render_data.vertex_buffer_size = vertices.len();
-        } else if let Some(buffer) = render_data.vertex_buffer.as_ref() {
-            // The buffer is large enough for the new vertices, so reuse it
-            queue.write_buffer(buffer, 0, &vertices);
-        } else {
-            unreachable!()
-        }
+        let index_size = upload::align_copy((index_count * size_of::<DrawIdx>()) as BufferAddress);
+        let index_buffer = render_data.index_buffer.reserve(device, index_size);
+        Self::write_draw_lists(queue, index_buffer, index_size, draw_data, |draw_list| {
+            bytemuck::cast_slice(draw_list.idx_buffer())
+        });
 
         render_data
     }
 
+    /// Copies the vertices or indices of the draw lists one after another to the start of a
+    /// buffer.
+    fn write_draw_lists(
+        queue: &Queue,
+        buffer: &Buffer,
+        size: BufferAddress,
+        draw_data: &DrawData,
+        bytes: fn(&DrawList) -> &[u8],
+    ) {
+        let size = match BufferSize::new(size) {
+            Some(size) => size,
+            None => return,
+        };
+        let mut view = queue.write_buffer_with(buffer, 0, size);
+        let mut offset = 0;
+        for draw_list in draw_data.draw_lists() {
+            let bytes = bytes(draw_list);
+            view[offset..offset + bytes.len()].copy_from_slice(bytes);
+            offset += bytes.len();
+        }
+    }
+
     /// Render the current imgui frame.  `Renderer::prepare` must be
     /// called first, and the output render data must be kept for the
     /// lifetime of the renderpass.
@@ -651,12 +1139,12 @@ impl Renderer {
             return Ok(());
         }
 
-        rpass.set_pipeline(&self.pipeline);
-        rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
-        rpass.set_vertex_buffer(0, render_data.vertex_buffer.as_ref().unwrap().slice(..));
+        rpass.set_pipeline(&self.pipelines.current.1);
+        rpass.set_bind_group(0, &render_data.uniform.as_ref().unwrap().1, &[]);
+        rpass.set_vertex_buffer(0, render_data.vertex_buffer.buffer().unwrap().slice(..));
         rpass.set_index_buffer(
-            render_data.index_buffer.as_ref().unwrap().slice(..),
-            IndexFormat::Uint16,
+            render_data.index_buffer.buffer().unwrap().slice(..),
+            INDEX_FORMAT,
         );
 
         // Execute all the imgui render work.
@@ -754,10 +1242,74 @@ impl Renderer {
         Ok(())
     }
 
-    /// Updates the current uniform buffer containing the transform matrix.
-    fn update_uniform_buffer(&self, queue: &Queue, matrix: &[[f32; 4]; 4]) {
+    /// Format of textures created without one, matching the render target. Float targets get
+    /// 8 bit sRGB textures, the format of the font atlas and most images.
+    fn default_texture_format(&self) -> TextureFormat {
+        if is_float_format(self.config.texture_format) {
+            TextureFormat::Rgba8UnormSrgb
+        } else {
+            self.config.texture_format
+        }
+    }
+
+    /// Returns the brightness of the UI on float render targets.
+    pub fn hdr(&self) -> HdrConfig {
+        self.config.hdr
+    }
+
+    /// Changes the brightness of the UI on float render targets, from the next prepared frame on.
+    pub fn set_hdr(&mut self, hdr: HdrConfig) {
+        self.config.hdr = hdr;
+    }
+
+    /// Creates a uniform buffer for the transform matrix and the HDR parameters, and its bind
+    /// group.
+    fn create_uniform(&self, device: &Device) -> (Buffer, BindGroup) {
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("imgui-wgpu uniform buffer"),
+            size: 80,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("imgui-wgpu bind group"),
+            layout: &self.uniform_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        (uniform_buffer, uniform_bind_group)
+    }
+
+    /// Updates a uniform buffer containing the transform matrix.
+    fn update_uniform_buffer(queue: &Queue, uniform_buffer: &Buffer, matrix: &[[f32; 4]; 4]) {
         let data = bytemuck::bytes_of(matrix);
-        queue.write_buffer(&self.uniform_buffer, 0, data);
+        queue.write_buffer(uniform_buffer, 0, data);
+    }
+
+    /// Draws text with the font texture of another renderer, whose context shares its font atlas
+    /// with `imgui`. Has to be called again whenever `fonts` reloads its font texture.
+    pub fn share_font_texture(&mut self, imgui: &mut Context, fonts: &Renderer) {
+        if let Some(id) = self.shared_font_texture.take() {
+            self.textures.remove(id);
+        }
+
+        // The id is stored in the shared atlas, so every renderer keeps the font texture under it
+        let id = imgui.fonts().tex_id;
+        if let Some(font_texture) = fonts.textures.get(id) {
+            let texture = Texture {
+                texture: font_texture.texture.clone(),
+                view: font_texture.view.clone(),
+                bind_group: font_texture.bind_group.clone(),
+                size: font_texture.size,
+                format: font_texture.format,
+                usage: font_texture.usage,
+                mip_level_count: font_texture.mip_level_count,
+            };
+            self.textures.replace(id, texture);
+            self.shared_font_texture = Some(id);
+        }
     }
 
     /// Updates the texture on the GPU corresponding to the current imgui font atlas.
@@ -777,13 +1329,169 @@ impl Renderer {
                 height: handle.height,
                 ..Default::default()
             },
+            format: Some(rgba8_format_for(self.texture_format())),
             ..Default::default()
         };
 
         let font_texture = Texture::new(device, self, font_texture_cnfig);
-        font_texture.write(queue, handle.data, handle.width, handle.height);
+        if self.font_icons.is_empty() {
+            font_texture.write(queue, handle.data, handle.width, handle.height);
+        } else {
+            let (width, height) = (handle.width, handle.height);
+            let mut data = handle.data.to_vec();
+            atlas::draw_font_icons(fonts, &self.font_icons, &mut data, width);
+            font_texture.write(queue, &data, width, height);
+        }
         fonts.tex_id = self.textures.insert(font_texture);
         // Clear imgui texture data to save memory.
         fonts.clear_tex_data();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipelines_are_kept_for_two_targets() {
+        let target = |texture_format, sample_count| PipelineTarget {
+            texture_format,
+            sample_count,
+            depth_format: None,
+        };
+        let frame = target(TextureFormat::Bgra8UnormSrgb, 1);
+        let offscreen = target(TextureFormat::Rgba16Float, 4);
+        let mut built = 0;
+        let mut build = || {
+            built += 1;
+            built
+        };
+
+        let mut pipelines = Pipelines::new(frame, build());
+        pipelines.switch(frame, &mut build);
+        assert_eq!(pipelines.current, (frame, 1));
+
+        for _ in 0..3 {
+            pipelines.switch(offscreen, &mut build);
+            assert_eq!(pipelines.current, (offscreen, 2));
+            pipelines.switch(frame, &mut build);
+            assert_eq!(pipelines.current, (frame, 1));
+        }
+
+        // a third target replaces the older pipeline
+        let depth = PipelineTarget {
+            depth_format: Some(TextureFormat::Depth32Float),
+            ..frame
+        };
+        pipelines.switch(depth, &mut build);
+        assert_eq!(pipelines.current, (depth, 3));
+        assert_eq!(pipelines.previous, Some((frame, 1)));
+        pipelines.switch(offscreen, &mut build);
+        assert_eq!(pipelines.current, (offscreen, 4));
+    }
+
+    fn expects(formats: &[TextureFormat], entry_point: &str) {
+        for &format in formats {
+            assert_eq!(
+                fragment_entry_point_for(format),
+                entry_point,
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn srgb_formats_get_linear_color() {
+        expects(
+            &[TextureFormat::Rgba8UnormSrgb, TextureFormat::Bgra8UnormSrgb],
+            FS_ENTRY_POINT_LINEAR,
+        );
+    }
+
+    #[test]
+    fn unorm_formats_get_srgb_color() {
+        expects(
+            &[
+                TextureFormat::Rgba8Unorm,
+                TextureFormat::Bgra8Unorm,
+                TextureFormat::Rgb10a2Unorm,
+                TextureFormat::Rgba16Unorm,
+                TextureFormat::R8Unorm,
+            ],
+            FS_ENTRY_POINT_SRGB,
+        );
+    }
+
+    #[test]
+    fn float_formats_get_hdr_color() {
+        expects(
+            &[
+                TextureFormat::Rgba16Float,
+                TextureFormat::Rgba32Float,
+                TextureFormat::Rg11b10Float,
+                TextureFormat::Rgb9e5Ufloat,
+            ],
+            FS_ENTRY_POINT_HDR,
+        );
+    }
+
+    #[test]
+    fn compressed_formats_follow_their_encoding() {
+        expects(
+            &[
+                TextureFormat::Bc1RgbaUnormSrgb,
+                TextureFormat::Bc7RgbaUnormSrgb,
+                TextureFormat::Etc2Rgba8UnormSrgb,
+                TextureFormat::Astc {
+                    block: AstcBlock::B4x4,
+                    channel: AstcChannel::UnormSrgb,
+                },
+            ],
+            FS_ENTRY_POINT_LINEAR,
+        );
+        expects(
+            &[
+                TextureFormat::Bc6hRgbUfloat,
+                TextureFormat::Astc {
+                    block: AstcBlock::B8x8,
+                    channel: AstcChannel::Hdr,
+                },
+            ],
+            FS_ENTRY_POINT_HDR,
+        );
+        expects(
+            &[
+                TextureFormat::Bc1RgbaUnorm,
+                TextureFormat::Bc7RgbaUnorm,
+                TextureFormat::Etc2Rgba8Unorm,
+                TextureFormat::EacR11Unorm,
+                TextureFormat::Astc {
+                    block: AstcBlock::B4x4,
+                    channel: AstcChannel::Unorm,
+                },
+            ],
+            FS_ENTRY_POINT_SRGB,
+        );
+    }
+
+    #[test]
+    fn hdr_brightness_is_relative_to_the_reference() {
+        assert_eq!(HdrConfig::default().uniform(), [1.0, 0.0, 0.0, 0.0]);
+        let hdr = HdrConfig {
+            paper_white_nits: 200.0,
+            reference_nits: SCRGB_REFERENCE_NITS,
+            peak_nits: Some(1000.0),
+        };
+        assert_eq!(hdr.uniform(), [2.5, 12.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn index_format_matches_imgui() {
+        let size = match INDEX_FORMAT {
+            IndexFormat::Uint16 => 2,
+            IndexFormat::Uint32 => 4,
+        };
+        assert_eq!(size, size_of::<DrawIdx>());
+    }
+}
@@ -0,0 +1,708 @@
+//! Loading images into textures, available with the `image` feature.
+//!
+//! PNG, JPEG and TGA files are decoded with the `image` crate. KTX2 files are uploaded as they
+//! are, with their own mip levels and block compressed formats if the device supports them.
+
+use crate::{format_expects_linear, full_mip_level_count, Renderer, Texture, TextureConfig};
+use imgui::TextureId;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use wgpu::{
+    AstcBlock, AstcChannel, Device, Extent3d, FilterMode, ImageCopyTexture, ImageDataLayout,
+    Origin3d, Queue, SamplerDescriptor, TextureAspect, TextureFormat,
+};
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// How the colors of an image are encoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Colors as shown on screen, such as photos and icons
+    Srgb,
+    /// Data that isn't a color, such as masks and normal maps
+    Linear,
+}
+
+/// Options for loading an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadOptions {
+    /// Encoding of decoded images, KTX2 files tell it with their format.
+    pub color_space: ColorSpace,
+    /// Whether the colors of the image are premultiplied by its alpha. They're converted to
+    /// the straight alpha the renderer blends with. KTX2 files tell it themselves.
+    pub premultiplied_alpha: bool,
+    /// Generates the mip levels of the image, unless it's a KTX2 file coming with its own.
+    pub mipmaps: bool,
+    /// Filter used when the image is drawn larger or smaller than it is.
+    pub filter: FilterMode,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            color_space: ColorSpace::Srgb,
+            premultiplied_alpha: false,
+            mipmaps: true,
+            filter: FilterMode::Linear,
+        }
+    }
+}
+
+/// Errors that can occur while loading an image.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Decode(image::ImageError),
+    /// The KTX2 file is malformed or uses something that isn't supported.
+    Ktx2(&'static str),
+    /// The device doesn't have the features to sample textures of this format.
+    UnsupportedFormat(TextureFormat),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "imgui texture load error: {}", err),
+            LoadError::Decode(err) => write!(f, "imgui texture load error: {}", err),
+            LoadError::Ktx2(reason) => write!(f, "imgui texture load error: KTX2 {}", reason),
+            LoadError::UnsupportedFormat(format) => write!(
+                f,
+                "imgui texture load error: the device doesn't support {:?} textures",
+                format
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            LoadError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for LoadError {
+    fn from(err: image::ImageError) -> Self {
+        LoadError::Decode(err)
+    }
+}
+
+/// An image ready for upload
+#[derive(Debug, PartialEq)]
+struct Image {
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    /// Data of each mip level, the largest first
+    levels: Vec<Vec<u8>>,
+    /// Whether the other mip levels are generated after upload
+    generate_mipmaps: bool,
+}
+
+/// Decodes an image. `expects_linear` tells whether the renderer works with linear colors,
+/// sRGB images are then sampled from sRGB textures so they're decoded by the GPU.
+fn decode(
+    bytes: &[u8],
+    format: Option<image::ImageFormat>,
+    options: &LoadOptions,
+    expects_linear: bool,
+) -> Result<Image, LoadError> {
+    if bytes.starts_with(&KTX2_IDENTIFIER) {
+        return parse_ktx2(bytes, options, expects_linear);
+    }
+
+    let decoded = match format {
+        Some(format) => image::load_from_memory_with_format(bytes, format)?,
+        // TGA is the only supported format that can't be told from its first bytes
+        None => image::load_from_memory(bytes).or_else(|err| {
+            image::load_from_memory_with_format(bytes, image::ImageFormat::Tga).map_err(|_| err)
+        })?,
+    };
+    let decoded = decoded.to_rgba8();
+    let (width, height) = decoded.dimensions();
+    let mut data = decoded.into_raw();
+    if options.premultiplied_alpha {
+        unpremultiply(&mut data);
+    }
+
+    let format = if options.color_space == ColorSpace::Srgb && expects_linear {
+        TextureFormat::Rgba8UnormSrgb
+    } else {
+        TextureFormat::Rgba8Unorm
+    };
+    Ok(Image {
+        format,
+        width,
+        height,
+        levels: vec![data],
+        generate_mipmaps: options.mipmaps,
+    })
+}
+
+/// Converts premultiplied RGBA8 colors to straight alpha
+fn unpremultiply(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        if alpha > 0 && alpha < 255 {
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+            }
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, LoadError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(LoadError::Ktx2("file is truncated"))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, LoadError> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(LoadError::Ktx2("file is truncated"))
+}
+
+/// Returns the format to sample a Vulkan format as when the GPU decodes sRGB, and the one to
+/// sample it as when the colors are used as they're stored.
+fn ktx2_formats(vk_format: u32) -> Option<(TextureFormat, TextureFormat)> {
+    use TextureFormat::*;
+
+    let same = |format| Some((format, format));
+    let srgb = |srgb_format, format| Some((srgb_format, format));
+    match vk_format {
+        37 => same(Rgba8Unorm),
+        43 => srgb(Rgba8UnormSrgb, Rgba8Unorm),
+        44 => same(Bgra8Unorm),
+        50 => srgb(Bgra8UnormSrgb, Bgra8Unorm),
+        97 => same(Rgba16Float),
+        133 => same(Bc1RgbaUnorm),
+        134 => srgb(Bc1RgbaUnormSrgb, Bc1RgbaUnorm),
+        135 => same(Bc2RgbaUnorm),
+        136 => srgb(Bc2RgbaUnormSrgb, Bc2RgbaUnorm),
+        137 => same(Bc3RgbaUnorm),
+        138 => srgb(Bc3RgbaUnormSrgb, Bc3RgbaUnorm),
+        139 => same(Bc4RUnorm),
+        141 => same(Bc5RgUnorm),
+        143 => same(Bc6hRgbUfloat),
+        145 => same(Bc7RgbaUnorm),
+        146 => srgb(Bc7RgbaUnormSrgb, Bc7RgbaUnorm),
+        147 => same(Etc2Rgb8Unorm),
+        148 => srgb(Etc2Rgb8UnormSrgb, Etc2Rgb8Unorm),
+        149 => same(Etc2Rgb8A1Unorm),
+        150 => srgb(Etc2Rgb8A1UnormSrgb, Etc2Rgb8A1Unorm),
+        151 => same(Etc2Rgba8Unorm),
+        152 => srgb(Etc2Rgba8UnormSrgb, Etc2Rgba8Unorm),
+        157 => same(Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::Unorm,
+        }),
+        158 => srgb(
+            Astc {
+                block: AstcBlock::B4x4,
+                channel: AstcChannel::UnormSrgb,
+            },
+            Astc {
+                block: AstcBlock::B4x4,
+                channel: AstcChannel::Unorm,
+            },
+        ),
+        _ => None,
+    }
+}
+
+/// Returns the bytes per row and the number of rows of blocks of a mip level, `None` if a row
+/// doesn't fit in a `u32`
+fn level_layout(format: TextureFormat, width: u32, height: u32, level: u32) -> Option<(u32, u32)> {
+    let info = format.describe();
+    let (block_width, block_height) = info.block_dimensions;
+    let width = width.checked_shr(level).unwrap_or(0).max(1);
+    let height = height.checked_shr(level).unwrap_or(0).max(1);
+    let columns = width.div_ceil(block_width as u32);
+    let rows = height.div_ceil(block_height as u32);
+    Some((columns.checked_mul(info.block_size as u32)?, rows))
+}
+
+fn parse_ktx2(
+    bytes: &[u8],
+    options: &LoadOptions,
+    expects_linear: bool,
+) -> Result<Image, LoadError> {
+    let vk_format = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?;
+    let depth = read_u32(bytes, 28)?;
+    let layers = read_u32(bytes, 32)?;
+    let faces = read_u32(bytes, 36)?;
+    let level_count = read_u32(bytes, 40)?;
+    let supercompression = read_u32(bytes, 44)?;
+    let dfd_offset = read_u32(bytes, 48)? as usize;
+    let dfd_length = read_u32(bytes, 52)?;
+
+    if supercompression != 0 {
+        return Err(LoadError::Ktx2("supercompression isn't supported"));
+    }
+    if depth > 1 || layers > 1 || faces != 1 || width == 0 || height == 0 {
+        return Err(LoadError::Ktx2("file isn't a single 2D texture"));
+    }
+    let (srgb_format, format) =
+        ktx2_formats(vk_format).ok_or(LoadError::Ktx2("format isn't supported"))?;
+    let format = if expects_linear { srgb_format } else { format };
+
+    let (block_width, block_height) = format.describe().block_dimensions;
+    if width % block_width as u32 != 0 || height % block_height as u32 != 0 {
+        return Err(LoadError::Ktx2("size isn't a multiple of the block size"));
+    }
+    if level_count > full_mip_level_count(width, height) {
+        return Err(LoadError::Ktx2("file has more levels than its size allows"));
+    }
+
+    let mut levels = Vec::new();
+    for level in 0..level_count.max(1) {
+        let index = 80 + level as usize * 24;
+        let offset = usize::try_from(read_u64(bytes, index)?);
+        let length = usize::try_from(read_u64(bytes, index + 8)?);
+        let (offset, length) = match (offset, length) {
+            (Ok(offset), Ok(length)) => (offset, length),
+            _ => return Err(LoadError::Ktx2("file is truncated")),
+        };
+        let size = level_layout(format, width, height, level)
+            .and_then(|(bytes_per_row, rows)| bytes_per_row.checked_mul(rows))
+            .ok_or(LoadError::Ktx2("level is too large"))?;
+        if length < size as usize {
+            return Err(LoadError::Ktx2("level is smaller than its size"));
+        }
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or(LoadError::Ktx2("file is truncated"))?;
+        levels.push(data.to_vec());
+    }
+
+    // the first flags of the data format descriptor tell whether alpha is premultiplied
+    let flags = dfd_offset
+        .checked_add(15)
+        .and_then(|index| bytes.get(index));
+    let premultiplied = dfd_length >= 16 && matches!(flags, Some(flags) if flags & 1 != 0);
+    let rgba8 = matches!(
+        format,
+        TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb
+    );
+    if premultiplied && rgba8 {
+        levels.iter_mut().for_each(|level| unpremultiply(level));
+    }
+
+    Ok(Image {
+        format,
+        width,
+        height,
+        generate_mipmaps: levels.len() == 1 && level_count == 0 && options.mipmaps && rgba8,
+        levels,
+    })
+}
+
+/// Where a loaded texture came from, to reload it when the file changes
+struct Source {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    options: LoadOptions,
+}
+
+struct Entry {
+    refs: u32,
+    source: Option<Source>,
+}
+
+/// Loads images into textures of a [`Renderer`] and unloads them when they're no longer used.
+///
+/// Every load takes a reference to the texture, and the texture is removed from the renderer
+/// when all of them were released. Files loaded more than once share one texture.
+#[derive(Default)]
+pub struct TextureLoader {
+    entries: HashMap<TextureId, Entry>,
+    paths: HashMap<PathBuf, TextureId>,
+    /// Makes [`reload_changed`](Self::reload_changed) reload textures whose files changed.
+    pub watch: bool,
+}
+
+impl TextureLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads an image from the bytes of a PNG, JPEG, TGA or KTX2 file.
+    pub fn load_bytes(
+        &mut self,
+        bytes: &[u8],
+        options: &LoadOptions,
+        renderer: &mut Renderer,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<TextureId, LoadError> {
+        let texture = create_texture(bytes, None, options, renderer, device, queue)?;
+        let id = renderer.textures.insert(texture);
+        self.entries.insert(
+            id,
+            Entry {
+                refs: 1,
+                source: None,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Loads an image from a PNG, JPEG, TGA or KTX2 file. A file that's already loaded isn't
+    /// loaded again, its texture gets another reference instead.
+    pub fn load_path(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: &LoadOptions,
+        renderer: &mut Renderer,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<TextureId, LoadError> {
+        let path = path.as_ref();
+        if let Some(&id) = self.paths.get(path) {
+            self.retain(id);
+            return Ok(id);
+        }
+
+        let bytes = std::fs::read(path)?;
+        let format = image::ImageFormat::from_path(path).ok();
+        let texture = create_texture(&bytes, format, options, renderer, device, queue)?;
+        let id = renderer.textures.insert(texture);
+        self.entries.insert(
+            id,
+            Entry {
+                refs: 1,
+                source: Some(Source {
+                    path: path.to_owned(),
+                    modified: modified(path),
+                    options: options.clone(),
+                }),
+            },
+        );
+        self.paths.insert(path.to_owned(), id);
+        Ok(id)
+    }
+
+    /// Takes another reference to a loaded texture.
+    pub fn retain(&mut self, id: TextureId) {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.refs += 1;
+        }
+    }
+
+    /// Releases a reference to a loaded texture, removing it from the renderer when it was the
+    /// last one. Returns whether the texture was removed.
+    pub fn release(&mut self, id: TextureId, renderer: &mut Renderer) -> bool {
+        let entry = match self.entries.get_mut(&id) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        entry.refs -= 1;
+        if entry.refs > 0 {
+            return false;
+        }
+
+        if let Some(source) = self.entries.remove(&id).and_then(|entry| entry.source) {
+            self.paths.remove(&source.path);
+        }
+        renderer.textures.remove(id);
+        true
+    }
+
+    /// Returns the number of references to a loaded texture.
+    pub fn ref_count(&self, id: TextureId) -> u32 {
+        self.entries.get(&id).map_or(0, |entry| entry.refs)
+    }
+
+    /// Number of textures loaded
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Reloads the textures whose files changed since they were loaded, if `watch` is set. The
+    /// textures keep their ids, and a texture that fails to load again keeps its old image.
+    /// Returns the ids of the reloaded textures.
+    pub fn reload_changed(
+        &mut self,
+        renderer: &mut Renderer,
+        device: &Device,
+        queue: &Queue,
+    ) -> Vec<TextureId> {
+        let mut reloaded = Vec::new();
+        if !self.watch {
+            return reloaded;
+        }
+
+        for (&id, entry) in &mut self.entries {
+            let source = match entry.source.as_mut() {
+                Some(source) => source,
+                None => continue,
+            };
+            let modified = modified(&source.path);
+            if modified == source.modified {
+                continue;
+            }
+            source.modified = modified;
+
+            let format = image::ImageFormat::from_path(&source.path).ok();
+            let texture = std::fs::read(&source.path)
+                .map_err(LoadError::from)
+                .and_then(|bytes| {
+                    create_texture(&bytes, format, &source.options, renderer, device, queue)
+                });
+            match texture {
+                Ok(texture) => {
+                    renderer.textures.replace(id, texture);
+                    reloaded.push(id);
+                }
+                Err(err) => log::warn!("{}: {}", source.path.display(), err),
+            }
+        }
+        reloaded
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn create_texture(
+    bytes: &[u8],
+    format: Option<image::ImageFormat>,
+    options: &LoadOptions,
+    renderer: &Renderer,
+    device: &Device,
+    queue: &Queue,
+) -> Result<Texture, LoadError> {
+    let expects_linear = format_expects_linear(renderer.texture_format());
+    let image = decode(bytes, format, options, expects_linear)?;
+    if !device
+        .features()
+        .contains(image.format.describe().required_features)
+    {
+        return Err(LoadError::UnsupportedFormat(image.format));
+    }
+
+    let size = Extent3d {
+        width: image.width,
+        height: image.height,
+        depth_or_array_layers: 1,
+    };
+    let defaults = TextureConfig::default();
+    let config = TextureConfig {
+        size,
+        label: Some("imgui-wgpu loaded texture"),
+        format: Some(image.format),
+        mip_level_count: image.levels.len() as u32,
+        sampler_desc: SamplerDescriptor {
+            mag_filter: options.filter,
+            min_filter: options.filter,
+            ..defaults.sampler_desc
+        },
+        ..defaults
+    };
+    if image.generate_mipmaps {
+        let texture = Texture::new(device, renderer, config.with_mipmaps());
+        let data = &image.levels[0];
+        texture.write_mipmapped(device, queue, renderer, data, image.width, image.height);
+        return Ok(texture);
+    }
+
+    let texture = Texture::new(device, renderer, config);
+    for (level, data) in image.levels.iter().enumerate() {
+        let level = level as u32;
+        let (bytes_per_row, rows) = level_layout(image.format, image.width, image.height, level)
+            .expect("levels of a size that doesn't fit are rejected when loading");
+        let (block_width, block_height) = image.format.describe().block_dimensions;
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: texture.texture(),
+                mip_level: level,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: NonZeroU32::new(rows),
+            },
+            // block compressed levels are copied in whole blocks
+            Extent3d {
+                width: bytes_per_row / image.format.describe().block_size as u32
+                    * block_width as u32,
+                height: rows * block_height as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+        use image::ImageEncoder;
+
+        let mut bytes = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut bytes)
+            .write_image(pixels, width, height, image::ColorType::Rgba8)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn decoded_images_match_the_color_space_of_the_renderer() {
+        let bytes = png(1, 1, &[255, 128, 0, 255]);
+        let options = LoadOptions {
+            mipmaps: false,
+            ..Default::default()
+        };
+
+        let image = decode(&bytes, None, &options, true).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(image.levels, vec![vec![255, 128, 0, 255]]);
+
+        let image = decode(&bytes, None, &options, false).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8Unorm);
+
+        let linear = LoadOptions {
+            color_space: ColorSpace::Linear,
+            ..options
+        };
+        let image = decode(&bytes, None, &linear, true).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8Unorm);
+    }
+
+    #[test]
+    fn premultiplied_alpha_is_made_straight() {
+        let mut data = vec![64, 32, 0, 128, 10, 20, 30, 0, 10, 20, 30, 255];
+        unpremultiply(&mut data);
+        assert_eq!(data, vec![128, 64, 0, 128, 10, 20, 30, 0, 10, 20, 30, 255]);
+    }
+
+    fn ktx2(vk_format: u32, width: u32, height: u32, levels: &[&[u8]], flags: u8) -> Vec<u8> {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        let dfd_offset = 80 + levels.len() as u32 * 24;
+        for value in [vk_format, 1, width, height, 0, 0, 1, levels.len() as u32, 0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        // data format descriptor, key/value data and supercompression data
+        bytes.extend(dfd_offset.to_le_bytes());
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend([0; 8]);
+        bytes.extend([0; 16]);
+
+        let mut offset = dfd_offset as u64 + 16;
+        for level in levels {
+            bytes.extend(offset.to_le_bytes());
+            bytes.extend((level.len() as u64).to_le_bytes());
+            bytes.extend((level.len() as u64).to_le_bytes());
+            offset += level.len() as u64;
+        }
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend([0; 11]);
+        bytes.push(flags);
+        for level in levels {
+            bytes.extend(*level);
+        }
+        bytes
+    }
+
+    #[test]
+    fn ktx2_files_keep_their_levels() {
+        let bytes = ktx2(
+            43,
+            2,
+            1,
+            &[&[255, 0, 0, 255, 0, 255, 0, 255], &[128, 128, 0, 255]],
+            0,
+        );
+        let image = decode(&bytes, None, &LoadOptions::default(), true).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.levels.len(), 2);
+
+        let image = decode(&bytes, None, &LoadOptions::default(), false).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8Unorm);
+    }
+
+    #[test]
+    fn ktx2_premultiplied_alpha_is_made_straight() {
+        let bytes = ktx2(37, 1, 1, &[&[64, 32, 0, 128]], 1);
+        let image = decode(&bytes, None, &LoadOptions::default(), true).unwrap();
+        assert_eq!(image.levels, vec![vec![128, 64, 0, 128]]);
+    }
+
+    #[test]
+    fn ktx2_block_compressed_levels_are_checked() {
+        let block = [0u8; 8];
+        let bytes = ktx2(134, 4, 4, &[&block], 0);
+        let image = decode(&bytes, None, &LoadOptions::default(), true).unwrap();
+        assert_eq!(image.format, TextureFormat::Bc1RgbaUnormSrgb);
+
+        let bytes = ktx2(134, 8, 4, &[&block], 0);
+        assert!(matches!(
+            decode(&bytes, None, &LoadOptions::default(), true),
+            Err(LoadError::Ktx2(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_ktx2_headers_are_errors() {
+        let bytes = ktx2(37, 2, 1, &[&[0; 8]], 0);
+        let fails = |bytes: &[u8]| {
+            matches!(
+                decode(bytes, None, &LoadOptions::default(), true),
+                Err(LoadError::Ktx2(_))
+            )
+        };
+        let patched = |index: usize, value: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[index..index + value.len()].copy_from_slice(value);
+            bytes
+        };
+
+        // level data cut off
+        assert!(fails(&bytes[..bytes.len() - 1]));
+        // a row wider than a u32
+        assert!(fails(&patched(20, &u32::MAX.to_le_bytes())));
+        // more levels than a mip chain of the size has, shifting the size away
+        assert!(fails(&patched(40, &40u32.to_le_bytes())));
+        // a level ending beyond the address space
+        assert!(fails(&patched(80, &u64::MAX.to_le_bytes())));
+        assert!(fails(&patched(88, &u64::MAX.to_le_bytes())));
+    }
+}
@@ -0,0 +1,285 @@
+//! Generating the mip levels of textures, with a blit pass on the GPU or a box filter on the CPU
+//! when there's no device to render with.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use wgpu::*;
+
+/// Returns the number of mip levels going from a size down to a single pixel.
+pub fn full_mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Returns whether the CPU can generate the mip levels of a format.
+pub(crate) fn cpu_supports(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb
+    )
+}
+
+/// Returns whether the GPU can generate the mip levels of a texture by rendering into them.
+pub(crate) fn gpu_supports(format: TextureFormat, usage: TextureUsages) -> bool {
+    let features = format.describe().guaranteed_format_features;
+    usage.contains(TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT)
+        && features
+            .allowed_usages
+            .contains(TextureUsages::RENDER_ATTACHMENT)
+        && features
+            .flags
+            .contains(TextureFormatFeatureFlags::FILTERABLE)
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Generates the levels after the first of 8 bit RGBA data with a 2x2 box filter. Colors of
+/// sRGB data are averaged in linear space, as the GPU does when sampling them.
+pub(crate) fn generate_levels(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    level_count: u32,
+    srgb: bool,
+) -> Vec<Vec<u8>> {
+    let mut to_float = [0.0; 256];
+    for (value, float) in to_float.iter_mut().enumerate() {
+        *float = value as f32 / 255.0;
+    }
+    let mut to_linear = to_float;
+    if srgb {
+        to_linear
+            .iter_mut()
+            .for_each(|value| *value = srgb_to_linear(*value));
+    }
+
+    let mut levels: Vec<Vec<u8>> = Vec::with_capacity(level_count.saturating_sub(1) as usize);
+    let (mut width, mut height) = (width as usize, height as usize);
+    for _ in 1..level_count {
+        let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+        let previous = levels.last().map_or(data, Vec::as_slice);
+        let mut next = Vec::with_capacity(next_width * next_height * 4);
+        for y in 0..next_height {
+            // the last row or column of an odd size is left out, a size of 1 is used twice
+            let rows = [(y * 2).min(height - 1), (y * 2 + 1).min(height - 1)];
+            for x in 0..next_width {
+                let columns = [(x * 2).min(width - 1), (x * 2 + 1).min(width - 1)];
+                for channel in 0..4 {
+                    // alpha is linear in sRGB formats too
+                    let table = if channel < 3 { &to_linear } else { &to_float };
+                    let mut sum = 0.0;
+                    for row in rows {
+                        for column in columns {
+                            sum += table[previous[(row * width + column) * 4 + channel] as usize];
+                        }
+                    }
+                    let mut average = sum / 4.0;
+                    if srgb && channel < 3 {
+                        average = linear_to_srgb(average);
+                    }
+                    next.push((average * 255.0).round() as u8);
+                }
+            }
+        }
+        levels.push(next);
+        width = next_width;
+        height = next_height;
+    }
+    levels
+}
+
+/// Renders each mip level of a texture from the one above it.
+pub(crate) struct MipmapBlitter {
+    shader: ShaderModule,
+    bind_group_layout: BindGroupLayout,
+    pipeline_layout: PipelineLayout,
+    sampler: Sampler,
+    /// Pipelines are created for the formats of the textures as they're met
+    pipelines: Mutex<HashMap<TextureFormat, RenderPipeline>>,
+}
+
+impl MipmapBlitter {
+    pub(crate) fn new(device: &Device) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("mipmap.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("imgui-wgpu mipmap bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("imgui-wgpu mipmap pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("imgui-wgpu mipmap sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            shader,
+            bind_group_layout,
+            pipeline_layout,
+            sampler,
+            pipelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn create_pipeline(&self, device: &Device, format: TextureFormat) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("imgui-wgpu mipmap pipeline"),
+            layout: Some(&self.pipeline_layout),
+            vertex: VertexState {
+                module: &self.shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &self.shader,
+                entry_point: "fs_main",
+                targets: &[Some(format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    /// Renders the levels after the first of a texture, which has to support
+    /// [`gpu_supports`].
+    pub(crate) fn generate(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        texture: &Texture,
+        format: TextureFormat,
+        level_count: u32,
+    ) {
+        let mut pipelines = self.pipelines.lock().unwrap();
+        let pipeline = pipelines
+            .entry(format)
+            .or_insert_with(|| self.create_pipeline(device, format));
+
+        let views: Vec<TextureView> = (0..level_count)
+            .map(|level| {
+                texture.create_view(&TextureViewDescriptor {
+                    label: Some("imgui-wgpu mip level"),
+                    base_mip_level: level,
+                    mip_level_count: NonZeroU32::new(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("imgui-wgpu mipmap encoder"),
+        });
+        for levels in views.windows(2) {
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: Some("imgui-wgpu mipmap bind group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&levels[0]),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("imgui-wgpu mipmap pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &levels[1],
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_chains_go_down_to_one_pixel() {
+        assert_eq!(full_mip_level_count(1, 1), 1);
+        assert_eq!(full_mip_level_count(3, 2), 2);
+        assert_eq!(full_mip_level_count(256, 64), 9);
+        assert_eq!(full_mip_level_count(0, 0), 1);
+    }
+
+    #[test]
+    fn levels_are_box_filtered() {
+        let data: Vec<u8> = [[0, 0, 0, 255], [255, 255, 255, 255], [100, 100, 100, 255]]
+            .iter()
+            .cycle()
+            .take(3 * 2)
+            .flatten()
+            .copied()
+            .collect();
+        let levels = generate_levels(&data, 3, 2, 2, false);
+        assert_eq!(levels, vec![vec![128, 128, 128, 255]]);
+
+        // half of the light of white is brighter than the middle of the sRGB range
+        let levels = generate_levels(&data, 3, 2, 2, true);
+        assert_eq!(levels, vec![vec![188, 188, 188, 255]]);
+    }
+
+    #[test]
+    fn every_requested_level_is_generated() {
+        let data = vec![255; 8 * 4 * 4];
+        let levels = generate_levels(&data, 8, 4, full_mip_level_count(8, 4), true);
+        let sizes: Vec<usize> = levels.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![4 * 2 * 4, 2 * 4, 4]);
+        assert!(levels.iter().flatten().all(|&value| value == 255));
+    }
+}
@@ -0,0 +1,138 @@
+//! Vertex and index buffers kept across frames, growing with headroom and shrinking back after
+//! a while of low use.
+
+use wgpu::*;
+
+/// Smallest size of the buffers, enough for a few simple windows
+const MIN_SIZE: BufferAddress = 16 * 1024;
+/// Frames the content has to fill less than a quarter of a buffer for before it shrinks
+const SHRINK_AFTER_FRAMES: u32 = 300;
+
+/// Rounds a size up to the alignment of buffer copies
+pub(crate) fn align_copy(size: BufferAddress) -> BufferAddress {
+    size.div_ceil(COPY_BUFFER_ALIGNMENT) * COPY_BUFFER_ALIGNMENT
+}
+
+/// Size of a buffer holding `needed` bytes, with half as much again so content growing a bit
+/// doesn't create a new buffer every frame.
+fn size_with_headroom(needed: BufferAddress) -> BufferAddress {
+    align_copy(needed + needed / 2).max(MIN_SIZE)
+}
+
+/// A buffer written from the start every frame
+pub(crate) struct FrameBuffer {
+    label: &'static str,
+    usage: BufferUsages,
+    buffer: Option<Buffer>,
+    size: BufferAddress,
+    /// Frames in a row the content was under a quarter of the buffer
+    low_use_frames: u32,
+    /// Largest content during those frames
+    low_use_peak: BufferAddress,
+}
+
+impl FrameBuffer {
+    pub(crate) fn new(label: &'static str, usage: BufferUsages) -> Self {
+        Self {
+            label,
+            usage: usage | BufferUsages::COPY_DST,
+            buffer: None,
+            size: 0,
+            low_use_frames: 0,
+            low_use_peak: 0,
+        }
+    }
+
+    pub(crate) fn buffer(&self) -> Option<&Buffer> {
+        self.buffer.as_ref()
+    }
+
+    /// Returns a buffer with room for `needed` bytes, creating a new one when it's too small or
+    /// has been much larger than needed for a while.
+    pub(crate) fn reserve(&mut self, device: &Device, needed: BufferAddress) -> &Buffer {
+        if let Some(size) = self.resize(needed) {
+            self.buffer = Some(device.create_buffer(&BufferDescriptor {
+                label: Some(self.label),
+                size,
+                usage: self.usage,
+                mapped_at_creation: false,
+            }));
+            self.size = size;
+        }
+        self.buffer.as_ref().unwrap()
+    }
+
+    /// Returns the size of the buffer to create for `needed` bytes, if the current one doesn't
+    /// fit anymore.
+    fn resize(&mut self, needed: BufferAddress) -> Option<BufferAddress> {
+        // no buffer has a size of 0
+        if needed > self.size || self.size == 0 {
+            self.low_use_frames = 0;
+            return Some(size_with_headroom(needed));
+        }
+        if needed > self.size / 4 || self.size <= MIN_SIZE {
+            self.low_use_frames = 0;
+            return None;
+        }
+
+        if self.low_use_frames == 0 {
+            self.low_use_peak = 0;
+        }
+        self.low_use_frames += 1;
+        self.low_use_peak = self.low_use_peak.max(needed);
+        if self.low_use_frames < SHRINK_AFTER_FRAMES {
+            return None;
+        }
+        self.low_use_frames = 0;
+        Some(size_with_headroom(self.low_use_peak))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sized(size: BufferAddress) -> FrameBuffer {
+        let mut buffer = FrameBuffer::new("test", BufferUsages::VERTEX);
+        buffer.size = size;
+        buffer
+    }
+
+    #[test]
+    fn buffers_grow_with_headroom() {
+        let mut buffer = sized(0);
+        assert_eq!(buffer.resize(0), Some(MIN_SIZE));
+        assert_eq!(buffer.resize(100), Some(MIN_SIZE));
+
+        let mut buffer = sized(MIN_SIZE);
+        assert_eq!(buffer.resize(MIN_SIZE), None);
+        assert_eq!(buffer.resize(MIN_SIZE * 2 + 2), Some(MIN_SIZE * 3 + 4));
+        assert_eq!(align_copy(6), 8);
+        assert_eq!(align_copy(8), 8);
+    }
+
+    #[test]
+    fn buffers_shrink_after_low_use() {
+        let mut buffer = sized(MIN_SIZE * 16);
+        for frame in 1..SHRINK_AFTER_FRAMES {
+            let needed = if frame == 10 { MIN_SIZE * 2 } else { MIN_SIZE };
+            assert_eq!(buffer.resize(needed), None);
+        }
+        // shrinks to fit the largest content of the low use frames
+        assert_eq!(buffer.resize(MIN_SIZE), Some(MIN_SIZE * 3));
+
+        // a frame of high use starts the count again
+        let mut buffer = sized(MIN_SIZE * 16);
+        for _ in 1..SHRINK_AFTER_FRAMES {
+            assert_eq!(buffer.resize(MIN_SIZE), None);
+        }
+        assert_eq!(buffer.resize(MIN_SIZE * 8), None);
+        assert_eq!(buffer.resize(MIN_SIZE), None);
+
+        // the smallest buffers are kept
+        let mut buffer = sized(MIN_SIZE);
+        for _ in 0..SHRINK_AFTER_FRAMES * 2 {
+            assert_eq!(buffer.resize(0), None);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Software mouse cursors.
+//!
+//! When enabled, the OS cursor is hidden and the cursor requested by imgui is drawn into the
+//! foreground draw list instead, which keeps a visible cursor in fullscreen games that hide or
+//! lock the OS one. Cursors without an image of their own use the shapes built into the font
+//! atlas, and the OS cursor stays visible when the atlas was built without them.
+
+use imgui::{MouseCursor, TextureId, Ui};
+
+/// A cursor drawn from an application texture
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CursorImage {
+    pub texture_id: TextureId,
+    /// Size of the cursor on screen, in logical pixels
+    pub size: [f32; 2],
+    /// Point of the image that follows the mouse, in logical pixels from its top left corner
+    pub hotspot: [f32; 2],
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    /// Color the image is multiplied with
+    pub tint: [f32; 4],
+}
+
+impl CursorImage {
+    /// A cursor showing a whole texture
+    pub fn new(texture_id: TextureId, size: [f32; 2], hotspot: [f32; 2]) -> Self {
+        CursorImage {
+            texture_id,
+            size,
+            hotspot,
+            uv_min: [0.0, 0.0],
+            uv_max: [1.0, 1.0],
+            tint: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+}
+
+/// Software cursor settings.
+///
+/// This replaces `io.mouse_draw_cursor`, which should stay disabled while it's in use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftwareCursor {
+    /// Hides the OS cursor and draws the cursor with imgui
+    pub enabled: bool,
+    /// Scale of the cursor shapes built into the font atlas
+    pub scale: f32,
+    images: [Option<CursorImage>; MouseCursor::COUNT],
+}
+
+impl Default for SoftwareCursor {
+    fn default() -> Self {
+        SoftwareCursor {
+            enabled: false,
+            scale: 1.0,
+            images: [None; MouseCursor::COUNT],
+        }
+    }
+}
+
+impl SoftwareCursor {
+    /// Sets the image drawn for a cursor type, `None` goes back to the built in shape
+    pub fn set_image(&mut self, cursor: MouseCursor, image: Option<CursorImage>) {
+        self.images[cursor as usize] = image;
+    }
+
+    /// The image drawn for a cursor type, `None` if it uses the built in shape
+    pub fn image(&self, cursor: MouseCursor) -> Option<&CursorImage> {
+        self.images[cursor as usize].as_ref()
+    }
+
+    /// Draws the cursor imgui asks for. Returns whether it was drawn, the OS cursor is shown
+    /// otherwise.
+    pub(crate) fn draw(&self, ui: &Ui) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let cursor = match ui.mouse_cursor() {
+            Some(cursor) => cursor,
+            None => return false,
+        };
+        let pos = ui.io().mouse_pos;
+        if !(pos[0].is_finite() && pos[1].is_finite()) || pos[0] <= -f32::MAX * 0.5 {
+            return false;
+        }
+
+        let draw_list = ui.get_foreground_draw_list();
+        if let Some(image) = self.image(cursor) {
+            let min = [pos[0] - image.hotspot[0], pos[1] - image.hotspot[1]];
+            draw_list
+                .add_image(
+                    image.texture_id,
+                    min,
+                    [min[0] + image.size[0], min[1] + image.size[1]],
+                )
+                .uv_min(image.uv_min)
+                .uv_max(image.uv_max)
+                .col(image.tint)
+                .build();
+            return true;
+        }
+
+        let fonts = ui.fonts();
+        let data = match fonts.mouse_cursor_tex_data(cursor) {
+            Some(data) => data,
+            None => return false,
+        };
+        // Same layers as the cursor imgui draws itself: two shadows, the border, then the fill
+        let scale = self.scale;
+        let min = [
+            pos[0] - data.offset[0] * scale,
+            pos[1] - data.offset[1] * scale,
+        ];
+        let size = [data.size[0] * scale, data.size[1] * scale];
+        let shadow = [0.0, 0.0, 0.0, 48.0 / 255.0];
+        let layers = [
+            (1.0, data.uv_fill, shadow),
+            (2.0, data.uv_fill, shadow),
+            (0.0, data.uv_border, [0.0, 0.0, 0.0, 1.0]),
+            (0.0, data.uv_fill, [1.0, 1.0, 1.0, 1.0]),
+        ];
+        for &(offset, uv, color) in layers.iter() {
+            let layer_min = [min[0] + offset * scale, min[1]];
+            draw_list
+                .add_image(
+                    fonts.tex_id,
+                    layer_min,
+                    [layer_min[0] + size[0], layer_min[1] + size[1]],
+                )
+                .uv_min(uv[0])
+                .uv_max(uv[1])
+                .col(color)
+                .build();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imgui::{Context, DrawVert, FontAtlasFlags};
+
+    fn context() -> (std::sync::MutexGuard<'static, ()>, Context) {
+        let (guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        io.display_size = [800.0, 600.0];
+        io.mouse_pos = [100.0, 100.0];
+        (guard, imgui)
+    }
+
+    /// Draws the cursor in a frame, returning whether it was drawn and the vertices of the
+    /// foreground draw list
+    fn draw(imgui: &mut Context, cursor: &SoftwareCursor) -> (bool, Vec<DrawVert>) {
+        let ui = imgui.frame();
+        let drawn = cursor.draw(ui);
+        let draw_data = imgui.render();
+        // empty draw lists have no vertex buffer to look at
+        if draw_data.total_vtx_count == 0 {
+            return (drawn, Vec::new());
+        }
+        let foreground = draw_data.draw_lists().last().unwrap();
+        (drawn, foreground.vtx_buffer().to_vec())
+    }
+
+    fn corners(vertices: &[DrawVert]) -> [[f32; 2]; 2] {
+        [vertices[0].pos, vertices[2].pos]
+    }
+
+    #[test]
+    fn atlas_shapes_are_placed_by_their_hotspot() {
+        let (_guard, mut imgui) = context();
+        imgui.fonts().build_rgba32_texture();
+        let data = imgui
+            .fonts()
+            .mouse_cursor_tex_data(MouseCursor::Arrow)
+            .unwrap();
+        let cursor = SoftwareCursor {
+            enabled: true,
+            scale: 2.0,
+            ..Default::default()
+        };
+
+        let (drawn, vertices) = draw(&mut imgui, &cursor);
+        assert!(drawn);
+        // two shadows, the border and the fill
+        assert_eq!(vertices.len(), 16);
+        let min = [100.0 - data.offset[0] * 2.0, 100.0 - data.offset[1] * 2.0];
+        let max = [min[0] + data.size[0] * 2.0, min[1] + data.size[1] * 2.0];
+        assert_eq!(
+            corners(&vertices[0..]),
+            [[min[0] + 2.0, min[1]], [max[0] + 2.0, max[1]]]
+        );
+        assert_eq!(
+            corners(&vertices[4..]),
+            [[min[0] + 4.0, min[1]], [max[0] + 4.0, max[1]]]
+        );
+        assert_eq!(corners(&vertices[8..]), [min, max]);
+        assert_eq!(corners(&vertices[12..]), [min, max]);
+        assert_eq!(vertices[12].uv, data.uv_fill[0]);
+        assert_eq!(vertices[12].col, [255; 4]);
+    }
+
+    #[test]
+    fn images_are_placed_by_their_hotspot() {
+        let (_guard, mut imgui) = context();
+        imgui.fonts().build_rgba32_texture();
+        let mut cursor = SoftwareCursor {
+            enabled: true,
+            ..Default::default()
+        };
+        let image = CursorImage {
+            uv_min: [0.5, 0.0],
+            uv_max: [1.0, 0.5],
+            tint: [1.0, 0.0, 0.0, 1.0],
+            ..CursorImage::new(TextureId::new(7), [32.0, 24.0], [4.0, 6.0])
+        };
+        cursor.set_image(MouseCursor::Arrow, Some(image));
+        assert_eq!(cursor.image(MouseCursor::Arrow), Some(&image));
+        assert_eq!(cursor.image(MouseCursor::Hand), None);
+
+        let (drawn, vertices) = draw(&mut imgui, &cursor);
+        assert!(drawn);
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(corners(&vertices), [[96.0, 94.0], [128.0, 118.0]]);
+        assert_eq!(vertices[0].uv, [0.5, 0.0]);
+        assert_eq!(vertices[2].uv, [1.0, 0.5]);
+        assert_eq!(vertices[0].col, [255, 0, 0, 255]);
+
+        // Going back to the built in shape
+        cursor.set_image(MouseCursor::Arrow, None);
+        assert_eq!(draw(&mut imgui, &cursor).1.len(), 16);
+    }
+
+    #[test]
+    fn os_cursor_is_kept_without_atlas_cursors() {
+        let (_guard, mut imgui) = context();
+        imgui.fonts().flags |= FontAtlasFlags::NO_MOUSE_CURSORS;
+        imgui.fonts().build_rgba32_texture();
+        let mut cursor = SoftwareCursor {
+            enabled: true,
+            ..Default::default()
+        };
+
+        assert_eq!(draw(&mut imgui, &cursor), (false, Vec::new()));
+
+        // Images don't need the atlas
+        cursor.set_image(
+            MouseCursor::Arrow,
+            Some(CursorImage::new(
+                TextureId::new(7),
+                [16.0, 16.0],
+                [0.0, 0.0],
+            )),
+        );
+        assert!(draw(&mut imgui, &cursor).0);
+
+        cursor.enabled = false;
+        assert_eq!(draw(&mut imgui, &cursor), (false, Vec::new()));
+    }
+}
@@ -0,0 +1,125 @@
+//! Files dragged onto the window from outside the application.
+//!
+//! While the OS reports files over the window, they're handed to imgui as an external drag and
+//! drop source with the left mouse button held, and dropping them releases the button so the
+//! target under the mouse receives them. The OS doesn't send mouse moves during such drags on
+//! every platform, the files then land on the widget under the last known mouse position.
+
+use imgui::{Io, MouseButton, Ui};
+use std::path::PathBuf;
+use winit::event::WindowEvent;
+
+#[derive(Debug, Default)]
+pub(crate) struct FileDropState {
+    files: Vec<PathBuf>,
+    hovering: bool,
+    dropped: bool,
+    /// Frames the files were handed to imgui for
+    frames: u32,
+}
+
+impl FileDropState {
+    pub(crate) fn handle(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::HoveredFile(path) => {
+                if !self.hovering {
+                    self.reset();
+                    self.hovering = true;
+                }
+                self.files.push(path.clone());
+            }
+            WindowEvent::HoveredFileCancelled => self.reset(),
+            WindowEvent::DroppedFile(path) => {
+                if !self.hovering && !self.dropped {
+                    // dropped without being reported over the window first
+                    self.reset();
+                }
+                if !self.files.contains(path) {
+                    self.files.push(path.clone());
+                }
+                self.hovering = false;
+                self.dropped = true;
+            }
+            _ => (),
+        }
+    }
+
+    /// Files held over the window keep the mouse button down. Dropped files keep it down until
+    /// targets saw them for a frame, so they can be delivered when it's released.
+    fn holds_mouse(&self) -> bool {
+        self.hovering || (self.dropped && self.frames < 2)
+    }
+
+    pub(crate) fn prepare_frame(&self, io: &mut Io) {
+        if self.holds_mouse() {
+            io.hold_mouse_button(MouseButton::Left);
+        }
+    }
+
+    pub(crate) fn prepare_render(&mut self, ui: &Ui) {
+        if self.files.is_empty() {
+            return;
+        }
+        if self.holds_mouse() {
+            ui.drag_drop_external_files(&self.files);
+            self.frames += 1;
+        } else {
+            // the button was released this frame, the files went to the target under the mouse
+            self.reset();
+        }
+    }
+
+    pub(crate) fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    fn reset(&mut self) {
+        self.files.clear();
+        self.hovering = false;
+        self.dropped = false;
+        self.frames = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imgui::DragDropFlags;
+
+    #[test]
+    fn dropped_files_reach_the_target() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        imgui.fonts().build_rgba32_texture();
+        let io = imgui.io_mut();
+        io.display_size = [800.0, 600.0];
+        io.delta_time = 1.0 / 60.0;
+
+        let path = PathBuf::from("assets/grass.png");
+        let mut files = FileDropState::default();
+        // some platforms report dropped files without hovering them first
+        files.handle(&WindowEvent::DroppedFile(path.clone()));
+
+        let mut delivered = None;
+        for _ in 0..4 {
+            let io = imgui.io_mut();
+            io.mouse_pos = [20.0, 35.0];
+            io.mouse_down[0] = false;
+            files.prepare_frame(io);
+            let ui = imgui.frame();
+            ui.window("Assets")
+                .position([0.0, 0.0], imgui::Condition::Always)
+                .build(|| {
+                    ui.button("Drop here");
+                    if let Some(target) = ui.drag_drop_target() {
+                        if let Some(payload) = target.accept_files(DragDropFlags::empty()) {
+                            delivered = Some(payload.paths);
+                        }
+                    }
+                });
+            files.prepare_render(ui);
+            imgui.render();
+        }
+        assert_eq!(delivered, Some(vec![path]));
+        assert!(files.files().is_empty());
+    }
+}
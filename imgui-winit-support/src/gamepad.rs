@@ -0,0 +1,382 @@
+//! Gamepad navigation support.
+//!
+//! winit doesn't report gamepads, so the platform reads them through the [`GamepadInput`] trait,
+//! which can be implemented on top of any gamepad library. [`VirtualGamepad`] is a synthetic
+//! implementation, useful to drive navigation from tests or from other input sources.
+
+use imgui::{BackendFlags, ConfigFlags, Io, NavInput};
+
+/// A gamepad button, named after its position on the pad so layouts with different labels map
+/// the same way.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum GamepadButton {
+    /// Bottom face button (A on Xbox, Cross on PlayStation, B on Nintendo)
+    South,
+    /// Right face button (B on Xbox, Circle on PlayStation, A on Nintendo)
+    East,
+    /// Left face button (X on Xbox, Square on PlayStation, Y on Nintendo)
+    West,
+    /// Top face button (Y on Xbox, Triangle on PlayStation, X on Nintendo)
+    North,
+    DpadLeft,
+    DpadRight,
+    DpadUp,
+    DpadDown,
+    LeftShoulder,
+    RightShoulder,
+    Start,
+    Select,
+}
+
+impl GamepadButton {
+    /// All possible `GamepadButton` variants
+    pub const VARIANTS: [GamepadButton; 12] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::DpadLeft,
+        GamepadButton::DpadRight,
+        GamepadButton::DpadUp,
+        GamepadButton::DpadDown,
+        GamepadButton::LeftShoulder,
+        GamepadButton::RightShoulder,
+        GamepadButton::Start,
+        GamepadButton::Select,
+    ];
+}
+
+/// A gamepad axis. Sticks range from -1.0 to 1.0 with positive values pointing right and up,
+/// triggers range from 0.0 to 1.0.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// All possible `GamepadAxis` variants
+    pub const VARIANTS: [GamepadAxis; 6] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+
+    /// The other axis of the same stick, `None` for triggers
+    fn stick_partner(self) -> Option<GamepadAxis> {
+        match self {
+            GamepadAxis::LeftStickX => Some(GamepadAxis::LeftStickY),
+            GamepadAxis::LeftStickY => Some(GamepadAxis::LeftStickX),
+            GamepadAxis::RightStickX => Some(GamepadAxis::RightStickY),
+            GamepadAxis::RightStickY => Some(GamepadAxis::RightStickX),
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => None,
+        }
+    }
+}
+
+/// Source of gamepad state, read by the platform every frame.
+pub trait GamepadInput {
+    /// Polls the device, called once per frame before reading it.
+    fn update(&mut self) {}
+    /// Returns true if a gamepad is connected.
+    fn is_connected(&self) -> bool;
+    /// State of a button, from 0.0 (released) to 1.0 (fully pressed).
+    fn button(&self, button: GamepadButton) -> f32;
+    /// Raw value of an axis, before deadzones are applied.
+    fn axis(&self, axis: GamepadAxis) -> f32;
+}
+
+/// Where the value of a navigation input comes from
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GamepadSource {
+    Button(GamepadButton),
+    /// The positive half of an axis
+    AxisPositive(GamepadAxis),
+    /// The negative half of an axis
+    AxisNegative(GamepadAxis),
+}
+
+/// Mapping of gamepad buttons and axes to imgui navigation inputs
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamepadMapping {
+    /// Sources of each navigation input, several sources for one input use the highest value
+    pub inputs: Vec<(NavInput, GamepadSource)>,
+    /// Sticks pushed less than this far from the center are ignored, in any direction
+    pub stick_deadzone: f32,
+    /// Trigger values below this are ignored
+    pub trigger_deadzone: f32,
+}
+
+impl Default for GamepadMapping {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl GamepadMapping {
+    /// Xbox and PlayStation style mapping: south activates, east cancels.
+    pub fn standard() -> Self {
+        use GamepadAxis::*;
+        use GamepadSource::*;
+        GamepadMapping {
+            inputs: vec![
+                (NavInput::Activate, Button(GamepadButton::South)),
+                (NavInput::Cancel, Button(GamepadButton::East)),
+                (NavInput::Menu, Button(GamepadButton::West)),
+                (NavInput::Input, Button(GamepadButton::North)),
+                (NavInput::DpadLeft, Button(GamepadButton::DpadLeft)),
+                (NavInput::DpadRight, Button(GamepadButton::DpadRight)),
+                (NavInput::DpadUp, Button(GamepadButton::DpadUp)),
+                (NavInput::DpadDown, Button(GamepadButton::DpadDown)),
+                (NavInput::FocusPrev, Button(GamepadButton::LeftShoulder)),
+                (NavInput::FocusNext, Button(GamepadButton::RightShoulder)),
+                (NavInput::TweakSlow, Button(GamepadButton::LeftShoulder)),
+                (NavInput::TweakFast, Button(GamepadButton::RightShoulder)),
+                (NavInput::TweakSlow, AxisPositive(LeftTrigger)),
+                (NavInput::TweakFast, AxisPositive(RightTrigger)),
+                (NavInput::LStickLeft, AxisNegative(LeftStickX)),
+                (NavInput::LStickRight, AxisPositive(LeftStickX)),
+                (NavInput::LStickUp, AxisPositive(LeftStickY)),
+                (NavInput::LStickDown, AxisNegative(LeftStickY)),
+            ],
+            stick_deadzone: 0.2,
+            trigger_deadzone: 0.1,
+        }
+    }
+
+    /// Nintendo style mapping: east activates, south cancels.
+    pub fn nintendo() -> Self {
+        let mut mapping = Self::standard();
+        for (input, source) in mapping.inputs.iter_mut() {
+            match input {
+                NavInput::Activate => *source = GamepadSource::Button(GamepadButton::East),
+                NavInput::Cancel => *source = GamepadSource::Button(GamepadButton::South),
+                NavInput::Menu => *source = GamepadSource::Button(GamepadButton::North),
+                NavInput::Input => *source = GamepadSource::Button(GamepadButton::West),
+                _ => (),
+            }
+        }
+        mapping
+    }
+
+    /// Reads the value of a source with deadzones applied, from 0.0 to 1.0. The deadzone of a
+    /// stick applies to how far it's pushed, both of its axes are scaled along.
+    pub fn read(&self, gamepad: &dyn GamepadInput, source: GamepadSource) -> f32 {
+        let (axis, sign) = match source {
+            GamepadSource::Button(button) => return gamepad.button(button).clamp(0.0, 1.0),
+            GamepadSource::AxisPositive(axis) => (axis, 1.0),
+            GamepadSource::AxisNegative(axis) => (axis, -1.0),
+        };
+        let value = gamepad.axis(axis) * sign;
+        let partner = match axis.stick_partner() {
+            Some(partner) => partner,
+            None => return apply_deadzone(value, self.trigger_deadzone),
+        };
+        let length = value.hypot(gamepad.axis(partner));
+        if value <= 0.0 || length <= 0.0 {
+            return 0.0;
+        }
+        (value / length * apply_deadzone(length, self.stick_deadzone)).min(1.0)
+    }
+
+    /// Fills `io.nav_inputs` from the gamepad.
+    ///
+    /// Does nothing unless `ConfigFlags::NAV_ENABLE_GAMEPAD` is set. `BackendFlags::HAS_GAMEPAD`
+    /// follows the connection state of the gamepad.
+    pub fn apply(&self, io: &mut Io, gamepad: &dyn GamepadInput) {
+        if !io.config_flags.contains(ConfigFlags::NAV_ENABLE_GAMEPAD) {
+            return;
+        }
+
+        for value in io.nav_inputs.iter_mut() {
+            *value = 0.0;
+        }
+        io.backend_flags
+            .set(BackendFlags::HAS_GAMEPAD, gamepad.is_connected());
+        if !gamepad.is_connected() {
+            return;
+        }
+
+        for &(input, source) in self.inputs.iter() {
+            let value = self.read(gamepad, source);
+            if value > io[input] {
+                io[input] = value;
+            }
+        }
+    }
+}
+
+/// Rescales a value so the deadzone maps to 0.0 and the rest of the range still reaches 1.0.
+/// Negative values are clamped to 0.0.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value <= deadzone {
+        0.0
+    } else {
+        ((value - deadzone) / (1.0 - deadzone).max(f32::EPSILON)).min(1.0)
+    }
+}
+
+/// A gamepad that is driven from code instead of hardware.
+#[derive(Clone, Debug)]
+pub struct VirtualGamepad {
+    /// Whether the gamepad reports itself as connected
+    pub connected: bool,
+    buttons: [f32; GamepadButton::VARIANTS.len()],
+    axes: [f32; GamepadAxis::VARIANTS.len()],
+}
+
+impl Default for VirtualGamepad {
+    fn default() -> Self {
+        VirtualGamepad {
+            connected: true,
+            buttons: [0.0; GamepadButton::VARIANTS.len()],
+            axes: [0.0; GamepadAxis::VARIANTS.len()],
+        }
+    }
+}
+
+impl VirtualGamepad {
+    /// Creates a connected gamepad with everything released
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Fully presses a button
+    pub fn press(&mut self, button: GamepadButton) {
+        self.set_button(button, 1.0);
+    }
+    /// Releases a button
+    pub fn release(&mut self, button: GamepadButton) {
+        self.set_button(button, 0.0);
+    }
+    /// Sets the analog value of a button
+    pub fn set_button(&mut self, button: GamepadButton, value: f32) {
+        self.buttons[button as usize] = value;
+    }
+    /// Sets the value of an axis
+    pub fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes[axis as usize] = value;
+    }
+    /// Releases every button and centers every axis
+    pub fn reset(&mut self) {
+        self.buttons = [0.0; GamepadButton::VARIANTS.len()];
+        self.axes = [0.0; GamepadAxis::VARIANTS.len()];
+    }
+}
+
+impl GamepadInput for VirtualGamepad {
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+    fn button(&self, button: GamepadButton) -> f32 {
+        self.buttons[button as usize]
+    }
+    fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_rescales() {
+        assert_eq!(apply_deadzone(0.1, 0.2), 0.0);
+        assert_eq!(apply_deadzone(-0.5, 0.2), 0.0);
+        assert!((apply_deadzone(0.6, 0.2) - 0.5).abs() < 1e-6);
+        assert_eq!(apply_deadzone(1.0, 0.2), 1.0);
+    }
+
+    #[test]
+    fn stick_deadzone_is_radial() {
+        let mapping = GamepadMapping::standard();
+        let mut gamepad = VirtualGamepad::new();
+        let read = |gamepad: &VirtualGamepad| {
+            [
+                mapping.read(
+                    gamepad,
+                    GamepadSource::AxisPositive(GamepadAxis::LeftStickX),
+                ),
+                mapping.read(
+                    gamepad,
+                    GamepadSource::AxisNegative(GamepadAxis::LeftStickX),
+                ),
+                mapping.read(
+                    gamepad,
+                    GamepadSource::AxisPositive(GamepadAxis::LeftStickY),
+                ),
+            ]
+        };
+
+        // Inside the deadzone diagonally, though each axis alone would be outside of it
+        gamepad.set_axis(GamepadAxis::LeftStickX, 0.15);
+        gamepad.set_axis(GamepadAxis::LeftStickY, 0.1);
+        assert_eq!(read(&gamepad), [0.0, 0.0, 0.0]);
+
+        // Outside of it diagonally with each axis inside, the direction is kept
+        gamepad.set_axis(GamepadAxis::LeftStickX, 0.18);
+        gamepad.set_axis(GamepadAxis::LeftStickY, 0.18);
+        let [right, left, up] = read(&gamepad);
+        assert!(right > 0.0);
+        assert_eq!(left, 0.0);
+        assert_eq!(up, right);
+        let length = (0.18f32).hypot(0.18);
+        assert!((right.hypot(up) - (length - 0.2) / 0.8).abs() < 1e-6);
+
+        // Fully pushed sticks reach 1.0
+        gamepad.set_axis(GamepadAxis::LeftStickX, -1.0);
+        gamepad.set_axis(GamepadAxis::LeftStickY, 0.0);
+        assert_eq!(read(&gamepad), [0.0, 1.0, 0.0]);
+
+        // Sticks don't affect each other, and triggers have their own deadzone
+        gamepad.set_axis(GamepadAxis::LeftStickX, 0.0);
+        gamepad.set_axis(GamepadAxis::LeftStickY, 0.0);
+        gamepad.set_axis(GamepadAxis::RightStickX, 1.0);
+        gamepad.set_axis(GamepadAxis::LeftTrigger, 0.55);
+        assert_eq!(read(&gamepad), [0.0, 0.0, 0.0]);
+        let trigger = mapping.read(
+            &gamepad,
+            GamepadSource::AxisPositive(GamepadAxis::LeftTrigger),
+        );
+        assert!((trigger - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn virtual_gamepad_fills_nav_inputs() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        io.config_flags.insert(ConfigFlags::NAV_ENABLE_GAMEPAD);
+
+        let mut gamepad = VirtualGamepad::new();
+        gamepad.press(GamepadButton::South);
+        gamepad.set_axis(GamepadAxis::LeftStickY, -1.0);
+        gamepad.set_axis(GamepadAxis::LeftStickX, 0.1);
+
+        GamepadMapping::standard().apply(io, &gamepad);
+        assert!(io.backend_flags.contains(BackendFlags::HAS_GAMEPAD));
+        assert_eq!(io[NavInput::Activate], 1.0);
+        assert_eq!(io[NavInput::Cancel], 0.0);
+        // The stick is pushed all the way, so only its direction is left
+        let length = 1.0f32.hypot(0.1);
+        assert!((io[NavInput::LStickDown] - 1.0 / length).abs() < 1e-6);
+        assert_eq!(io[NavInput::LStickUp], 0.0);
+        assert!((io[NavInput::LStickRight] - 0.1 / length).abs() < 1e-6);
+        assert_eq!(io[NavInput::LStickLeft], 0.0);
+
+        GamepadMapping::nintendo().apply(io, &gamepad);
+        assert_eq!(io[NavInput::Activate], 0.0);
+        assert_eq!(io[NavInput::Cancel], 1.0);
+
+        gamepad.connected = false;
+        GamepadMapping::standard().apply(io, &gamepad);
+        assert!(!io.backend_flags.contains(BackendFlags::HAS_GAMEPAD));
+        assert_eq!(io[NavInput::Cancel], 0.0);
+    }
+}
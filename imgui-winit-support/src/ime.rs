@@ -0,0 +1,298 @@
+//! Input method editor support.
+//!
+//! IME input is allowed on the window while an imgui text field is active. The text being composed
+//! is drawn over the field at the text cursor, committed text is sent to imgui as characters, and
+//! the candidate window follows the text cursor.
+
+use imgui::{Io, StyleColor, Ui};
+use winit::event::Ime;
+
+#[derive(Debug, Default)]
+pub(crate) struct ImeState {
+    enabled: bool,
+    preedit: String,
+    preedit_cursor: Option<(usize, usize)>,
+    /// Candidate window position requested by the last frame, `None` when no text field is active
+    wanted: Option<[f32; 2]>,
+    /// What was last applied to the window
+    allowed: Option<bool>,
+    position: Option<[f32; 2]>,
+}
+
+impl ImeState {
+    pub(crate) fn handle(&mut self, io: &mut Io, event: &Ime) {
+        match event {
+            Ime::Enabled => self.enabled = true,
+            Ime::Preedit(text, cursor) => {
+                self.preedit.clone_from(text);
+                self.preedit_cursor = *cursor;
+            }
+            Ime::Commit(text) => {
+                self.preedit.clear();
+                self.preedit_cursor = None;
+                for ch in text.chars() {
+                    io.add_input_character(ch);
+                }
+            }
+            Ime::Disabled => {
+                self.enabled = false;
+                self.preedit.clear();
+                self.preedit_cursor = None;
+            }
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// True while the IME is composing text, keys belong to the IME during that time
+    pub(crate) fn composing(&self) -> bool {
+        !self.preedit.is_empty()
+    }
+
+    /// Whether a key event is kept from imgui. Releases still reach it, so keys held when the
+    /// composition started don't stay down.
+    pub(crate) fn takes_key(&self, pressed: bool) -> bool {
+        pressed && self.composing()
+    }
+
+    pub(crate) fn preedit(&self) -> Option<(&str, Option<(usize, usize)>)> {
+        if self.composing() {
+            Some((&self.preedit, self.preedit_cursor))
+        } else {
+            None
+        }
+    }
+
+    /// Draws the text being composed at the text cursor of the active text field, and records
+    /// where the candidate window should go
+    pub(crate) fn prepare(&mut self, ui: &Ui) {
+        if !ui.io().want_text_input {
+            self.wanted = None;
+            return;
+        }
+
+        let pos = ui.ime_input_pos();
+        if let Some((text, cursor)) = self.preedit() {
+            draw_preedit(ui, pos, text, cursor);
+        }
+        // The candidate window goes below the line being edited
+        self.wanted = Some([pos[0], pos[1] + ui.current_font_size()]);
+    }
+
+    /// Allows IME input on the window with `set_allowed` while a text field is active. Returns
+    /// the position the candidate window has to be moved to, if it changed.
+    pub(crate) fn apply(&mut self, set_allowed: impl FnOnce(bool)) -> Option<[f32; 2]> {
+        let allowed = self.wanted.is_some();
+        if self.allowed != Some(allowed) {
+            set_allowed(allowed);
+            self.allowed = Some(allowed);
+            self.position = None;
+            if !allowed {
+                self.preedit.clear();
+                self.preedit_cursor = None;
+            }
+        }
+
+        match self.wanted {
+            Some(position) if self.position != Some(position) => {
+                self.position = Some(position);
+                Some(position)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Draws the text being composed over the text field, underlined, with the IME cursor or
+/// selection
+fn draw_preedit(ui: &Ui, pos: [f32; 2], text: &str, cursor: Option<(usize, usize)>) {
+    let style = ui.clone_style();
+    let size = ui.calc_text_size(text);
+    let bottom = pos[1] + size[1];
+    let draw_list = ui.get_foreground_draw_list();
+
+    // The frame color is usually translucent, so the window color goes below it to hide the text
+    // of the field
+    for color in [StyleColor::WindowBg, StyleColor::FrameBg] {
+        draw_list
+            .add_rect(pos, [pos[0] + size[0] + 1.0, bottom], style[color])
+            .filled(true)
+            .build();
+    }
+
+    if let Some((start, end)) = cursor {
+        let offset = |index: usize| {
+            pos[0]
+                + text
+                    .get(..index)
+                    .map_or(size[0], |before| ui.calc_text_size(before)[0])
+        };
+        let (start, end) = (offset(start), offset(end));
+        if end > start {
+            draw_list
+                .add_rect(
+                    [start, pos[1]],
+                    [end, bottom],
+                    style[StyleColor::TextSelectedBg],
+                )
+                .filled(true)
+                .build();
+        } else {
+            draw_list
+                .add_line([start, pos[1]], [start, bottom], style[StyleColor::Text])
+                .build();
+        }
+    }
+
+    draw_list.add_text(pos, style[StyleColor::Text], text);
+    draw_list
+        .add_line(
+            [pos[0], bottom - 1.0],
+            [pos[0] + size[0], bottom - 1.0],
+            style[StyleColor::Text],
+        )
+        .build();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imgui::Context;
+
+    fn preedit(text: &str, cursor: Option<(usize, usize)>) -> Ime {
+        Ime::Preedit(text.to_owned(), cursor)
+    }
+
+    fn frame(imgui: &mut Context, ime: &mut ImeState, text_field: bool) {
+        let ui = imgui.frame();
+        ui.window("Text")
+            .position([0.0, 0.0], imgui::Condition::Always)
+            .build(|| {
+                if text_field {
+                    ui.set_keyboard_focus_here();
+                    let mut text = String::new();
+                    ui.input_text("Name", &mut text).build();
+                }
+            });
+        ime.prepare(ui);
+        imgui.render();
+    }
+
+    #[test]
+    fn keys_and_characters_go_to_the_ime_while_composing() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        let mut ime = ImeState::default();
+        assert!(!ime.takes_key(true));
+
+        ime.handle(io, &Ime::Enabled);
+        assert!(ime.enabled());
+        assert!(!ime.composing());
+        assert!(!ime.takes_key(true));
+
+        ime.handle(io, &preedit("か", Some((3, 3))));
+        assert!(ime.composing());
+        assert!(ime.takes_key(true));
+        assert!(!ime.takes_key(false));
+        assert_eq!(ime.preedit(), Some(("か", Some((3, 3)))));
+
+        ime.handle(io, &preedit("漢字", Some((0, 6))));
+        assert_eq!(ime.preedit(), Some(("漢字", Some((0, 6)))));
+        assert_eq!(io.peek_input_characters(), "");
+
+        // The committed text is typed into imgui and the keys are back to it
+        ime.handle(io, &Ime::Commit("漢字".to_owned()));
+        assert_eq!(io.peek_input_characters(), "漢字");
+        assert!(!ime.composing());
+        assert_eq!(ime.preedit(), None);
+        assert!(!ime.takes_key(true));
+    }
+
+    #[test]
+    fn cancelling_drops_the_preedit() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        let mut ime = ImeState::default();
+
+        ime.handle(io, &Ime::Enabled);
+        ime.handle(io, &preedit("かん", Some((6, 6))));
+        // IMEs cancel the composition with an empty preedit
+        ime.handle(io, &preedit("", None));
+        assert!(!ime.composing());
+        assert!(ime.enabled());
+
+        ime.handle(io, &preedit("かん", Some((6, 6))));
+        ime.handle(io, &Ime::Disabled);
+        assert!(!ime.composing());
+        assert!(!ime.enabled());
+        assert_eq!(io.peek_input_characters(), "");
+    }
+
+    #[test]
+    fn ime_is_allowed_while_a_text_field_is_active() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        imgui.fonts().build_rgba32_texture();
+        imgui.io_mut().display_size = [800.0, 600.0];
+        let mut ime = ImeState::default();
+        let mut allowed = Vec::new();
+
+        frame(&mut imgui, &mut ime, false);
+        assert_eq!(ime.apply(|value| allowed.push(value)), None);
+        assert_eq!(allowed, [false]);
+
+        // Focusing the field takes a frame, imgui reports it wants text the frame after
+        for _ in 0..3 {
+            frame(&mut imgui, &mut ime, true);
+        }
+        let position = ime.apply(|value| allowed.push(value));
+        assert_eq!(allowed, [false, true]);
+        let position = position.expect("no candidate window position");
+        assert!(position[1] > 0.0);
+        // Nothing to update while the field stays where it is
+        frame(&mut imgui, &mut ime, true);
+        assert_eq!(ime.apply(|value| allowed.push(value)), None);
+        assert_eq!(allowed, [false, true]);
+
+        // Leaving the field disallows the IME and drops what was being composed
+        ime.handle(imgui.io_mut(), &preedit("か", Some((3, 3))));
+        for _ in 0..3 {
+            frame(&mut imgui, &mut ime, false);
+        }
+        assert_eq!(ime.apply(|value| allowed.push(value)), None);
+        assert_eq!(allowed, [false, true, false]);
+        assert!(!ime.composing());
+    }
+
+    #[test]
+    fn preedit_is_drawn_at_the_text_cursor() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        imgui.fonts().build_rgba32_texture();
+        imgui.io_mut().display_size = [800.0, 600.0];
+
+        let ui = imgui.frame();
+        let pos = [100.0, 50.0];
+        let size = ui.calc_text_size("hello");
+        let selection = [ui.calc_text_size("h")[0], ui.calc_text_size("hel")[0]];
+        draw_preedit(ui, pos, "hello", Some((1, 3)));
+        let draw_data = imgui.render();
+
+        let foreground = draw_data.draw_lists().last().unwrap();
+        let corners = |first: usize| {
+            let vertices = &foreground.vtx_buffer()[first..first + 4];
+            [vertices[0].pos, vertices[2].pos]
+        };
+        // The background covers the text of the field, then the selection is on top of it
+        let background = [pos, [pos[0] + size[0] + 1.0, pos[1] + size[1]]];
+        assert_eq!(corners(0), background);
+        assert_eq!(corners(4), background);
+        assert_eq!(
+            corners(8),
+            [
+                [pos[0] + selection[0], pos[1]],
+                [pos[0] + selection[1], pos[1] + size[1]]
+            ]
+        );
+    }
+}
@@ -75,7 +75,30 @@
 
 use imgui::{self, BackendFlags, ConfigFlags, Context, Io, Key, Ui};
 use std::cell::Cell;
-use std::cmp::Ordering;
+#[cfg(feature = "docking")]
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+mod cursor;
+mod files;
+mod gamepad;
+mod ime;
+mod mouse;
+mod scroll;
+#[cfg(test)]
+mod test;
+mod touch;
+#[cfg(feature = "docking")]
+mod viewports;
+pub use cursor::{CursorImage, SoftwareCursor};
+pub use gamepad::{
+    GamepadAxis, GamepadButton, GamepadInput, GamepadMapping, GamepadSource, VirtualGamepad,
+};
+pub use mouse::{back_buttons, forward_buttons, MouseButtonMapping};
+pub use scroll::ScrollConfig;
+pub use touch::TouchConfig;
 
 // Re-export winit to make it easier for users to use the correct version.
 pub use winit;
@@ -84,10 +107,10 @@ use winit::dpi::{LogicalPosition, LogicalSize};
 use winit::{
     error::ExternalError,
     event::{
-        DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, TouchPhase,
-        VirtualKeyCode, WindowEvent,
+        DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, Touch, VirtualKeyCode,
+        WindowEvent,
     },
-    window::{CursorIcon as MouseCursor, Window},
+    window::{CursorIcon as MouseCursor, Window, WindowId},
 };
 
 /// State of a single mouse button. Used so that we can detect cases where mouse
@@ -128,7 +151,17 @@ pub struct WinitPlatform {
     hidpi_mode: ActiveHiDpiMode,
     hidpi_factor: f64,
     cursor_cache: Option<CursorSettings>,
+    cursor_request: Option<CursorSettings>,
+    software_cursor: SoftwareCursor,
     mouse_buttons: [Button; 5],
+    mouse: mouse::MouseState,
+    files: files::FileDropState,
+    gamepad_mapping: GamepadMapping,
+    touch: touch::TouchState,
+    ime: ime::ImeState,
+    scroll: scroll::ScrollState,
+    #[cfg(feature = "docking")]
+    viewports: Option<Rc<RefCell<viewports::ViewportWindows>>>,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -151,6 +184,133 @@ fn to_winit_cursor(cursor: imgui::MouseCursor) -> MouseCursor {
     }
 }
 
+/// Maps a winit virtual key code to the imgui-rs key stored at the same place in `Io::keys_down`.
+///
+/// Returns `None` for keys without an imgui-rs equivalent, like media keys.
+pub fn to_imgui_key(keycode: VirtualKeyCode) -> Option<Key> {
+    let key = match keycode {
+        VirtualKeyCode::Tab => Key::Tab,
+        VirtualKeyCode::Left => Key::LeftArrow,
+        VirtualKeyCode::Right => Key::RightArrow,
+        VirtualKeyCode::Up => Key::UpArrow,
+        VirtualKeyCode::Down => Key::DownArrow,
+        VirtualKeyCode::PageUp => Key::PageUp,
+        VirtualKeyCode::PageDown => Key::PageDown,
+        VirtualKeyCode::Home => Key::Home,
+        VirtualKeyCode::End => Key::End,
+        VirtualKeyCode::Insert => Key::Insert,
+        VirtualKeyCode::Delete => Key::Delete,
+        VirtualKeyCode::Back => Key::Backspace,
+        VirtualKeyCode::Space => Key::Space,
+        VirtualKeyCode::Return => Key::Enter,
+        VirtualKeyCode::Escape => Key::Escape,
+        VirtualKeyCode::NumpadEnter => Key::KeyPadEnter,
+        VirtualKeyCode::LControl => Key::LeftCtrl,
+        VirtualKeyCode::LShift => Key::LeftShift,
+        VirtualKeyCode::LAlt => Key::LeftAlt,
+        VirtualKeyCode::LWin => Key::LeftSuper,
+        VirtualKeyCode::RControl => Key::RightCtrl,
+        VirtualKeyCode::RShift => Key::RightShift,
+        VirtualKeyCode::RAlt => Key::RightAlt,
+        VirtualKeyCode::RWin => Key::RightSuper,
+        VirtualKeyCode::Apps => Key::Menu,
+        VirtualKeyCode::Key0 => Key::Alpha0,
+        VirtualKeyCode::Key1 => Key::Alpha1,
+        VirtualKeyCode::Key2 => Key::Alpha2,
+        VirtualKeyCode::Key3 => Key::Alpha3,
+        VirtualKeyCode::Key4 => Key::Alpha4,
+        VirtualKeyCode::Key5 => Key::Alpha5,
+        VirtualKeyCode::Key6 => Key::Alpha6,
+        VirtualKeyCode::Key7 => Key::Alpha7,
+        VirtualKeyCode::Key8 => Key::Alpha8,
+        VirtualKeyCode::Key9 => Key::Alpha9,
+        VirtualKeyCode::A => Key::A,
+        VirtualKeyCode::B => Key::B,
+        VirtualKeyCode::C => Key::C,
+        VirtualKeyCode::D => Key::D,
+        VirtualKeyCode::E => Key::E,
+        VirtualKeyCode::F => Key::F,
+        VirtualKeyCode::G => Key::G,
+        VirtualKeyCode::H => Key::H,
+        VirtualKeyCode::I => Key::I,
+        VirtualKeyCode::J => Key::J,
+        VirtualKeyCode::K => Key::K,
+        VirtualKeyCode::L => Key::L,
+        VirtualKeyCode::M => Key::M,
+        VirtualKeyCode::N => Key::N,
+        VirtualKeyCode::O => Key::O,
+        VirtualKeyCode::P => Key::P,
+        VirtualKeyCode::Q => Key::Q,
+        VirtualKeyCode::R => Key::R,
+        VirtualKeyCode::S => Key::S,
+        VirtualKeyCode::T => Key::T,
+        VirtualKeyCode::U => Key::U,
+        VirtualKeyCode::V => Key::V,
+        VirtualKeyCode::W => Key::W,
+        VirtualKeyCode::X => Key::X,
+        VirtualKeyCode::Y => Key::Y,
+        VirtualKeyCode::Z => Key::Z,
+        VirtualKeyCode::F1 => Key::F1,
+        VirtualKeyCode::F2 => Key::F2,
+        VirtualKeyCode::F3 => Key::F3,
+        VirtualKeyCode::F4 => Key::F4,
+        VirtualKeyCode::F5 => Key::F5,
+        VirtualKeyCode::F6 => Key::F6,
+        VirtualKeyCode::F7 => Key::F7,
+        VirtualKeyCode::F8 => Key::F8,
+        VirtualKeyCode::F9 => Key::F9,
+        VirtualKeyCode::F10 => Key::F10,
+        VirtualKeyCode::F11 => Key::F11,
+        VirtualKeyCode::F12 => Key::F12,
+        VirtualKeyCode::F13 => Key::F13,
+        VirtualKeyCode::F14 => Key::F14,
+        VirtualKeyCode::F15 => Key::F15,
+        VirtualKeyCode::F16 => Key::F16,
+        VirtualKeyCode::F17 => Key::F17,
+        VirtualKeyCode::F18 => Key::F18,
+        VirtualKeyCode::F19 => Key::F19,
+        VirtualKeyCode::F20 => Key::F20,
+        VirtualKeyCode::F21 => Key::F21,
+        VirtualKeyCode::F22 => Key::F22,
+        VirtualKeyCode::F23 => Key::F23,
+        VirtualKeyCode::F24 => Key::F24,
+        VirtualKeyCode::Apostrophe => Key::Apostrophe,
+        VirtualKeyCode::Comma => Key::Comma,
+        VirtualKeyCode::Minus => Key::Minus,
+        VirtualKeyCode::Period => Key::Period,
+        VirtualKeyCode::Slash => Key::Slash,
+        VirtualKeyCode::Semicolon => Key::Semicolon,
+        VirtualKeyCode::Equals => Key::Equal,
+        VirtualKeyCode::LBracket => Key::LeftBracket,
+        VirtualKeyCode::Backslash => Key::Backslash,
+        VirtualKeyCode::RBracket => Key::RightBracket,
+        VirtualKeyCode::Grave => Key::GraveAccent,
+        VirtualKeyCode::Capital => Key::CapsLock,
+        VirtualKeyCode::Scroll => Key::ScrollLock,
+        VirtualKeyCode::Numlock => Key::NumLock,
+        VirtualKeyCode::Snapshot => Key::PrintScreen,
+        VirtualKeyCode::Pause => Key::Pause,
+        VirtualKeyCode::Numpad0 => Key::KeyPad0,
+        VirtualKeyCode::Numpad1 => Key::KeyPad1,
+        VirtualKeyCode::Numpad2 => Key::KeyPad2,
+        VirtualKeyCode::Numpad3 => Key::KeyPad3,
+        VirtualKeyCode::Numpad4 => Key::KeyPad4,
+        VirtualKeyCode::Numpad5 => Key::KeyPad5,
+        VirtualKeyCode::Numpad6 => Key::KeyPad6,
+        VirtualKeyCode::Numpad7 => Key::KeyPad7,
+        VirtualKeyCode::Numpad8 => Key::KeyPad8,
+        VirtualKeyCode::Numpad9 => Key::KeyPad9,
+        VirtualKeyCode::NumpadDecimal => Key::KeyPadDecimal,
+        VirtualKeyCode::NumpadDivide => Key::KeyPadDivide,
+        VirtualKeyCode::NumpadMultiply => Key::KeyPadMultiply,
+        VirtualKeyCode::NumpadSubtract => Key::KeyPadSubtract,
+        VirtualKeyCode::NumpadAdd => Key::KeyPadAdd,
+        VirtualKeyCode::NumpadEquals => Key::KeyPadEqual,
+        _ => return None,
+    };
+    Some(key)
+}
+
 impl CursorSettings {
     fn apply(&self, window: &Window) {
         match self.cursor {
@@ -209,34 +369,13 @@ impl WinitPlatform {
     /// This function configures imgui-rs in the following ways:
     ///
     /// * backend flags are updated
-    /// * keys are configured
+    /// * keys are configured to the standard layout, see [`to_imgui_key`]
     /// * platform name is set
     pub fn init(imgui: &mut Context) -> WinitPlatform {
         let io = imgui.io_mut();
         io.backend_flags.insert(BackendFlags::HAS_MOUSE_CURSORS);
         io.backend_flags.insert(BackendFlags::HAS_SET_MOUSE_POS);
-        io[Key::Tab] = VirtualKeyCode::Tab as _;
-        io[Key::LeftArrow] = VirtualKeyCode::Left as _;
-        io[Key::RightArrow] = VirtualKeyCode::Right as _;
-        io[Key::UpArrow] = VirtualKeyCode::Up as _;
-        io[Key::DownArrow] = VirtualKeyCode::Down as _;
-        io[Key::PageUp] = VirtualKeyCode::PageUp as _;
-        io[Key::PageDown] = VirtualKeyCode::PageDown as _;
-        io[Key::Home] = VirtualKeyCode::Home as _;
-        io[Key::End] = VirtualKeyCode::End as _;
-        io[Key::Insert] = VirtualKeyCode::Insert as _;
-        io[Key::Delete] = VirtualKeyCode::Delete as _;
-        io[Key::Backspace] = VirtualKeyCode::Back as _;
-        io[Key::Space] = VirtualKeyCode::Space as _;
-        io[Key::Enter] = VirtualKeyCode::Return as _;
-        io[Key::Escape] = VirtualKeyCode::Escape as _;
-        io[Key::KeyPadEnter] = VirtualKeyCode::NumpadEnter as _;
-        io[Key::A] = VirtualKeyCode::A as _;
-        io[Key::C] = VirtualKeyCode::C as _;
-        io[Key::V] = VirtualKeyCode::V as _;
-        io[Key::X] = VirtualKeyCode::X as _;
-        io[Key::Y] = VirtualKeyCode::Y as _;
-        io[Key::Z] = VirtualKeyCode::Z as _;
+        io.map_keys_to_indices();
         imgui.set_platform_name(Some(format!(
             "imgui-winit-support {}",
             env!("CARGO_PKG_VERSION")
@@ -245,7 +384,17 @@ impl WinitPlatform {
             hidpi_mode: ActiveHiDpiMode::Default,
             hidpi_factor: 1.0,
             cursor_cache: None,
+            cursor_request: None,
+            software_cursor: SoftwareCursor::default(),
             mouse_buttons: [Button::INIT; 5],
+            mouse: mouse::MouseState::default(),
+            files: files::FileDropState::default(),
+            gamepad_mapping: GamepadMapping::default(),
+            touch: touch::TouchState::default(),
+            ime: ime::ImeState::default(),
+            scroll: scroll::ScrollState::default(),
+            #[cfg(feature = "docking")]
+            viewports: None,
         }
     }
     /// Attaches the platform instance to a winit window.
@@ -324,6 +473,7 @@ impl WinitPlatform {
     /// * window size / dpi factor changes are applied
     /// * keyboard state is updated
     /// * mouse state is updated
+    /// * touch input is turned into mouse input
     pub fn handle_event<T>(&mut self, io: &mut Io, window: &Window, event: &Event<T>) {
         match *event {
             Event::WindowEvent {
@@ -339,9 +489,18 @@ impl WinitPlatform {
                     io.key_alt = modifiers.alt();
                     io.key_super = modifiers.logo();
                 }
+                #[cfg(feature = "docking")]
+                if let (WindowEvent::Moved(_), Some(viewports)) = (event, &self.viewports) {
+                    viewports.borrow_mut().update_main_pos(window);
+                }
 
                 self.handle_window_event(io, window, event);
             }
+            #[cfg(feature = "docking")]
+            Event::WindowEvent {
+                window_id,
+                ref event,
+            } if self.viewports.is_some() => self.handle_viewport_event(io, window_id, event),
             // Track key release events outside our window. If we don't do this,
             // we might never see the release event if some other window gets focus.
             Event::DeviceEvent {
@@ -353,11 +512,43 @@ impl WinitPlatform {
                     }),
                 ..
             } => {
-                io.keys_down[key as usize] = false;
+                if let Some(key) = to_imgui_key(key) {
+                    io.keys_down[key.index()] = false;
+                }
             }
             _ => (),
         }
     }
+    /// Handles the events of viewport windows, the input goes to imgui like the main window's
+    #[cfg(feature = "docking")]
+    fn handle_viewport_event(&mut self, io: &mut Io, window_id: WindowId, event: &WindowEvent) {
+        let viewports = match self.viewports.clone() {
+            Some(viewports) => viewports,
+            None => return,
+        };
+        if viewports.borrow_mut().handle_event(window_id, event) {
+            return;
+        }
+        let viewports = viewports.borrow();
+        if let Some(window) = viewports.find_window(window_id) {
+            if let WindowEvent::ModifiersChanged(modifiers) = event {
+                io.key_shift = modifiers.shift();
+                io.key_ctrl = modifiers.ctrl();
+                io.key_alt = modifiers.alt();
+                io.key_super = modifiers.logo();
+            }
+            self.handle_window_event(io, window, event);
+        }
+    }
+    /// Position of a window on the desktop when viewports are enabled, imgui uses desktop
+    /// coordinates then
+    fn window_origin(&self, _window_id: WindowId) -> [f32; 2] {
+        #[cfg(feature = "docking")]
+        if let Some(viewports) = &self.viewports {
+            return viewports.borrow().origin(_window_id);
+        }
+        [0.0, 0.0]
+    }
     fn handle_window_event(&mut self, io: &mut Io, window: &Window, event: &WindowEvent) {
         match *event {
             WindowEvent::Resized(physical_size) => {
@@ -396,7 +587,13 @@ impl WinitPlatform {
                 ..
             } => {
                 let pressed = state == ElementState::Pressed;
-                io.keys_down[key as usize] = pressed;
+                // Keys pressed while composing edit the IME text, not the text field
+                if self.ime.takes_key(pressed) {
+                    return;
+                }
+                if let Some(key) = to_imgui_key(key) {
+                    io.keys_down[key.index()] = pressed;
+                }
 
                 // This is a bit redundant here, but we'll leave it in. The OS occasionally
                 // fails to send modifiers keys, but it doesn't seem to send false-positives,
@@ -413,72 +610,236 @@ impl WinitPlatform {
             WindowEvent::ReceivedCharacter(ch) => {
                 // Exclude the backspace key ('\u{7f}'). Otherwise we will insert this char and then
                 // delete it.
-                if ch != '\u{7f}' {
+                if ch != '\u{7f}' && !self.ime.composing() {
                     io.add_input_character(ch)
                 }
             }
+            WindowEvent::Ime(ref ime) => self.ime.handle(io, ime),
             WindowEvent::CursorMoved { position, .. } => {
                 let position = position.to_logical(window.scale_factor());
                 let position = self.scale_pos_from_winit(window, position);
-                io.mouse_pos = [position.x as f32, position.y as f32];
+                let origin = self.window_origin(window.id());
+                io.mouse_pos = [origin[0] + position.x as f32, origin[1] + position.y as f32];
+            }
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                self.scroll.handle(io, delta, phase, self.hidpi_factor)
             }
-            WindowEvent::MouseWheel {
-                delta,
-                phase: TouchPhase::Moved,
-                ..
-            } => match delta {
-                MouseScrollDelta::LineDelta(h, v) => {
-                    io.mouse_wheel_h = h;
-                    io.mouse_wheel = v;
-                }
-                MouseScrollDelta::PixelDelta(pos) => {
-                    let pos = pos.to_logical::<f64>(self.hidpi_factor);
-                    match pos.x.partial_cmp(&0.0) {
-                        Some(Ordering::Greater) => io.mouse_wheel_h += 1.0,
-                        Some(Ordering::Less) => io.mouse_wheel_h -= 1.0,
-                        _ => (),
-                    }
-                    match pos.y.partial_cmp(&0.0) {
-                        Some(Ordering::Greater) => io.mouse_wheel += 1.0,
-                        Some(Ordering::Less) => io.mouse_wheel -= 1.0,
-                        _ => (),
-                    }
-                }
-            },
             WindowEvent::MouseInput { state, button, .. } => {
                 let pressed = state == ElementState::Pressed;
-                match button {
-                    MouseButton::Left => self.mouse_buttons[0].set(pressed),
-                    MouseButton::Right => self.mouse_buttons[1].set(pressed),
-                    MouseButton::Middle => self.mouse_buttons[2].set(pressed),
-                    MouseButton::Other(idx @ 0..=4) => {
-                        self.mouse_buttons[idx as usize].set(pressed)
-                    }
-                    _ => (),
+                self.mouse.handle(button, pressed);
+                if let Some(button) = self.mouse.mapping.get(button) {
+                    self.mouse_buttons[button as usize].set(pressed);
                 }
             }
+            WindowEvent::HoveredFile(_)
+            | WindowEvent::HoveredFileCancelled
+            | WindowEvent::DroppedFile(_) => self.files.handle(event),
+            WindowEvent::Touch(Touch {
+                phase,
+                location,
+                id,
+                ..
+            }) => {
+                let position = location.to_logical(window.scale_factor());
+                let position = self.scale_pos_from_winit(window, position);
+                let origin = self.window_origin(window.id());
+                self.touch.handle(
+                    io,
+                    &self.mouse_buttons,
+                    id,
+                    phase,
+                    [origin[0] + position.x as f32, origin[1] + position.y as f32],
+                );
+            }
             WindowEvent::Focused(newly_focused) => {
                 if !newly_focused {
                     // Set focus-lost to avoid stuck keys (like 'alt'
                     // when alt-tabbing)
                     io.app_focus_lost = true;
+                    self.mouse.release_all();
                 }
             }
             _ => (),
         }
     }
+    /// Returns the mapping used to fill navigation inputs from a gamepad.
+    pub fn gamepad_mapping(&self) -> &GamepadMapping {
+        &self.gamepad_mapping
+    }
+    /// Returns the mapping used to fill navigation inputs from a gamepad, to change the layout or
+    /// deadzones.
+    pub fn gamepad_mapping_mut(&mut self) -> &mut GamepadMapping {
+        &mut self.gamepad_mapping
+    }
+    /// Polls a gamepad and fills the navigation inputs from it.
+    ///
+    /// Call this every frame before the imgui-rs context `frame` function. It does nothing unless
+    /// `ConfigFlags::NAV_ENABLE_GAMEPAD` is set.
+    pub fn update_gamepad(&self, io: &mut Io, gamepad: &mut dyn GamepadInput) {
+        gamepad.update();
+        self.gamepad_mapping.apply(io, gamepad);
+    }
+    /// Returns the mapping of winit mouse buttons to imgui mouse buttons.
+    pub fn mouse_button_mapping(&self) -> &MouseButtonMapping {
+        &self.mouse.mapping
+    }
+    /// Returns the mapping of winit mouse buttons to imgui mouse buttons, to move buttons or give
+    /// imgui the side buttons of another mouse.
+    pub fn mouse_button_mapping_mut(&mut self) -> &mut MouseButtonMapping {
+        &mut self.mouse.mapping
+    }
+    /// Returns true while a winit mouse button is held, whether imgui knows the button or not.
+    pub fn mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse.is_down(button)
+    }
+    /// Returns true if a winit mouse button was pressed since the previous frame, whether imgui
+    /// knows the button or not.
+    pub fn mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse.was_pressed(button)
+    }
+    /// Returns true if the back side button of the mouse was pressed since the previous frame.
+    pub fn mouse_back_pressed(&self) -> bool {
+        back_buttons()
+            .iter()
+            .any(|&button| self.mouse.was_pressed(button))
+    }
+    /// Returns true if the forward side button of the mouse was pressed since the previous frame.
+    pub fn mouse_forward_pressed(&self) -> bool {
+        forward_buttons()
+            .iter()
+            .any(|&button| self.mouse.was_pressed(button))
+    }
+    /// Returns the files dragged over the window, or dropped and not yet delivered.
+    ///
+    /// Widgets receive them with `DragDropTarget::accept_files`.
+    pub fn dragged_files(&self) -> &[std::path::PathBuf] {
+        self.files.files()
+    }
+    /// Returns the scrolling settings.
+    pub fn scroll_config(&self) -> &ScrollConfig {
+        &self.scroll.config
+    }
+    /// Returns the scrolling settings, to change speeds or enable smooth and kinetic scrolling.
+    pub fn scroll_config_mut(&mut self) -> &mut ScrollConfig {
+        &mut self.scroll.config
+    }
+    /// Returns the touch gesture settings.
+    pub fn touch_config(&self) -> &TouchConfig {
+        &self.touch.config
+    }
+    /// Returns the touch gesture settings, to change thresholds and timings.
+    pub fn touch_config_mut(&mut self) -> &mut TouchConfig {
+        &mut self.touch.config
+    }
+    /// Returns true once a touch event has been received.
+    pub fn touch_detected(&self) -> bool {
+        self.touch.detected()
+    }
+    /// Returns the zoom factor of the pinch gesture since the last frame, 1.0 when not pinching.
+    /// Read it while building the UI, it's cleared by `prepare_render`.
+    pub fn pinch_zoom(&self) -> f32 {
+        self.touch.zoom()
+    }
+    /// Raises `style.touch_extra_padding` to the value of the touch settings if touch input has
+    /// been detected, making widgets easier to hit with a finger.
+    pub fn apply_touch_style(&self, style: &mut imgui::Style) {
+        self.touch.apply_style(style);
+    }
+    /// Returns true while the OS input method editor is enabled on the window.
+    pub fn ime_enabled(&self) -> bool {
+        self.ime.enabled()
+    }
+    /// Returns the text being composed with the input method editor and the byte range of its
+    /// cursor, `None` when not composing.
+    pub fn ime_preedit(&self) -> Option<(&str, Option<(usize, usize)>)> {
+        self.ime.preedit()
+    }
+    /// Makes the next `prepare_render` apply the mouse cursor again, after it was changed or
+    /// hidden outside the platform.
+    pub fn invalidate_cursor(&mut self) {
+        self.cursor_cache = None;
+    }
+    /// Returns the software cursor settings.
+    pub fn software_cursor(&self) -> &SoftwareCursor {
+        &self.software_cursor
+    }
+    /// Returns the software cursor settings, to enable it or set cursor images.
+    pub fn software_cursor_mut(&mut self) -> &mut SoftwareCursor {
+        &mut self.software_cursor
+    }
+    /// Lets imgui windows leave the main window, as OS windows of their own.
+    ///
+    /// This installs the platform viewport backend and the monitors, `ConfigFlags::VIEWPORTS_ENABLE`
+    /// and a renderer that draws viewports are needed as well. Viewports get their windows from
+    /// [`add_viewport_window`](Self::add_viewport_window) or
+    /// [`create_viewport_windows`](Self::create_viewport_windows), and the events of those windows
+    /// go through `handle_event` like the main window's. `io.mouse_pos` is in desktop coordinates
+    /// from then on.
+    #[cfg(feature = "docking")]
+    pub fn enable_viewports(&mut self, imgui: &mut Context, window: &Window) {
+        self.viewports = Some(viewports::enable(imgui, window));
+    }
+    /// Adds a window viewports can use, it's hidden until a viewport needs it.
+    ///
+    /// Winit can only create windows from the event loop, so a few can be made up front for
+    /// applications that don't have the event loop at hand while running.
+    #[cfg(feature = "docking")]
+    pub fn add_viewport_window(&mut self, window: Window) {
+        if let Some(viewports) = &self.viewports {
+            viewports.borrow_mut().add_window(window);
+        }
+    }
+    /// Creates windows for the viewports that are waiting for one.
+    #[cfg(feature = "docking")]
+    pub fn create_viewport_windows<T>(
+        &mut self,
+        target: &winit::event_loop::EventLoopWindowTarget<T>,
+    ) -> Result<(), winit::error::OsError> {
+        match &self.viewports {
+            Some(viewports) => viewports.borrow_mut().create_windows(target),
+            None => Ok(()),
+        }
+    }
+    /// Returns the number of viewports waiting for a window.
+    #[cfg(feature = "docking")]
+    pub fn pending_viewport_windows(&self) -> usize {
+        self.viewports
+            .as_ref()
+            .map_or(0, |viewports| viewports.borrow().pending())
+    }
+    /// Returns the window of a viewport, `None` for the main viewport and viewports still waiting
+    /// for a window.
+    #[cfg(feature = "docking")]
+    pub fn viewport_window(&self, viewport_id: imgui::sys::ImGuiID) -> Option<Ref<'_, Window>> {
+        let viewports = self.viewports.as_ref()?;
+        Ref::filter_map(viewports.borrow(), |viewports| {
+            viewports.window(viewport_id)
+        })
+        .ok()
+    }
     /// Frame preparation callback.
     ///
     /// Call this before calling the imgui-rs context `frame` function.
     /// This function performs the following actions:
     ///
     /// * mouse cursor is repositioned (if requested by imgui-rs)
+    /// * touch long presses are turned into right clicks
+    /// * smooth and kinetic scrolling continue
+    /// * the mouse button is held while files are dragged over the window
     pub fn prepare_frame(&self, io: &mut Io, window: &Window) -> Result<(), ExternalError> {
+        self.touch.prepare_frame(&self.mouse_buttons);
+        self.mouse.prepare_frame();
+        self.scroll.prepare_frame(io);
         self.copy_mouse_to_io(&mut io.mouse_down);
+        self.files.prepare_frame(io);
         if io.want_set_mouse_pos {
+            let origin = self.window_origin(window.id());
             let logical_pos = self.scale_pos_for_winit(
                 window,
-                LogicalPosition::new(f64::from(io.mouse_pos[0]), f64::from(io.mouse_pos[1])),
+                LogicalPosition::new(
+                    f64::from(io.mouse_pos[0] - origin[0]),
+                    f64::from(io.mouse_pos[1] - origin[1]),
+                ),
             );
             window.set_cursor_position(logical_pos)
         } else {
@@ -498,16 +859,55 @@ impl WinitPlatform {
     /// This function performs the following actions:
     ///
     /// * mouse cursor is changed and/or hidden (if requested by imgui-rs)
+    /// * files dragged over the window are handed to imgui as a drag and drop payload
+    /// * the software cursor is drawn, if enabled
+    /// * IME input is allowed while a text field is active, the text being composed is drawn at
+    ///   the text cursor and the candidate window is moved there
     pub fn prepare_render(&mut self, ui: &Ui, window: &Window) {
+        self.prepare_render_ui(ui);
+        self.prepare_render_window(window);
+    }
+
+    /// The part of `prepare_render` that needs the `Ui`: draws the software cursor and the text
+    /// being composed, and records the cursor and IME state the window has to be updated with.
+    ///
+    /// Only needed when the UI is built where the window isn't available. Call it after building
+    /// the UI, then `prepare_render_window` before rendering.
+    pub fn prepare_render_ui(&mut self, ui: &Ui) {
+        self.files.prepare_render(ui);
+        self.touch.prepare_render();
+        self.ime.prepare(ui);
+        let software_cursor = self.software_cursor.draw(ui);
+
         let io = ui.io();
-        if !io
+        self.cursor_request = if io
             .config_flags
             .contains(ConfigFlags::NO_MOUSE_CURSOR_CHANGE)
         {
-            let cursor = CursorSettings {
+            None
+        } else {
+            Some(CursorSettings {
                 cursor: ui.mouse_cursor(),
-                draw_cursor: io.mouse_draw_cursor,
-            };
+                draw_cursor: io.mouse_draw_cursor || software_cursor,
+            })
+        };
+    }
+
+    /// The part of `prepare_render` that needs the window: changes or hides the mouse cursor and
+    /// updates the IME state, as recorded by `prepare_render_ui`.
+    pub fn prepare_render_window(&mut self, window: &Window) {
+        if let Some(position) = self.ime.apply(|allowed| window.set_ime_allowed(allowed)) {
+            let origin = self.window_origin(window.id());
+            window.set_ime_position(self.scale_pos_for_winit(
+                window,
+                LogicalPosition::new(
+                    f64::from(position[0] - origin[0]),
+                    f64::from(position[1] - origin[1]),
+                ),
+            ));
+        }
+
+        if let Some(cursor) = self.cursor_request {
             if self.cursor_cache != Some(cursor) {
                 cursor.apply(window);
                 self.cursor_cache = Some(cursor);
@@ -0,0 +1,193 @@
+//! Mouse buttons.
+//!
+//! winit numbers the buttons beyond left, right and middle the way each platform does, so the side
+//! buttons are `Other(1)` on Windows but `Other(8)` on X11. They reach imgui's five buttons through
+//! a mapping, and every button, mapped or not, can be queried directly, for example to bind the
+//! side buttons to back and forward navigation.
+
+use imgui::MouseButton as ImguiMouseButton;
+use std::cell::RefCell;
+use winit::event::MouseButton;
+
+#[cfg(target_os = "windows")]
+const BACK: &[MouseButton] = &[MouseButton::Other(1)];
+#[cfg(target_os = "windows")]
+const FORWARD: &[MouseButton] = &[MouseButton::Other(2)];
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const BACK: &[MouseButton] = &[MouseButton::Other(3)];
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const FORWARD: &[MouseButton] = &[MouseButton::Other(4)];
+
+// web browsers number the buttons after left, middle and right from 0
+#[cfg(target_arch = "wasm32")]
+const BACK: &[MouseButton] = &[MouseButton::Other(0)];
+#[cfg(target_arch = "wasm32")]
+const FORWARD: &[MouseButton] = &[MouseButton::Other(1)];
+
+// X11 button numbers, then the evdev codes reported on Wayland: BTN_SIDE and BTN_BACK for back,
+// BTN_EXTRA and BTN_FORWARD for forward
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
+const BACK: &[MouseButton] = &[
+    MouseButton::Other(8),
+    MouseButton::Other(0x113),
+    MouseButton::Other(0x116),
+];
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
+const FORWARD: &[MouseButton] = &[
+    MouseButton::Other(9),
+    MouseButton::Other(0x114),
+    MouseButton::Other(0x115),
+];
+
+/// Returns the buttons winit reports for the back side button on this platform.
+pub fn back_buttons() -> &'static [MouseButton] {
+    BACK
+}
+
+/// Returns the buttons winit reports for the forward side button on this platform.
+pub fn forward_buttons() -> &'static [MouseButton] {
+    FORWARD
+}
+
+/// Mapping of winit mouse buttons to imgui mouse buttons
+#[derive(Clone, Debug, PartialEq)]
+pub struct MouseButtonMapping {
+    /// The imgui button of each winit button, several winit buttons can drive one imgui button.
+    /// Buttons not listed only show up in the raw button queries.
+    pub buttons: Vec<(MouseButton, ImguiMouseButton)>,
+}
+
+impl Default for MouseButtonMapping {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl MouseButtonMapping {
+    /// Left, right and middle buttons, with the back and forward side buttons as
+    /// `Extra1` and `Extra2`.
+    pub fn standard() -> Self {
+        let mut buttons = vec![
+            (MouseButton::Left, ImguiMouseButton::Left),
+            (MouseButton::Right, ImguiMouseButton::Right),
+            (MouseButton::Middle, ImguiMouseButton::Middle),
+        ];
+        buttons.extend(
+            BACK.iter()
+                .map(|&button| (button, ImguiMouseButton::Extra1)),
+        );
+        buttons.extend(
+            FORWARD
+                .iter()
+                .map(|&button| (button, ImguiMouseButton::Extra2)),
+        );
+        MouseButtonMapping { buttons }
+    }
+
+    /// Returns the imgui button a winit button drives, if any.
+    pub fn get(&self, button: MouseButton) -> Option<ImguiMouseButton> {
+        self.buttons
+            .iter()
+            .find(|(winit_button, _)| *winit_button == button)
+            .map(|&(_, imgui_button)| imgui_button)
+    }
+}
+
+/// Raw state of every winit button, kept apart from the five buttons imgui knows
+#[derive(Debug, Default)]
+pub(crate) struct MouseState {
+    pub(crate) mapping: MouseButtonMapping,
+    down: Vec<MouseButton>,
+    /// Pressed since the last frame was prepared
+    pressed: RefCell<Vec<MouseButton>>,
+    /// Pressed before the current frame was prepared
+    pressed_frame: RefCell<Vec<MouseButton>>,
+}
+
+impl MouseState {
+    pub(crate) fn handle(&mut self, button: MouseButton, pressed: bool) {
+        self.down.retain(|&down| down != button);
+        if pressed {
+            self.down.push(button);
+            self.pressed.get_mut().push(button);
+        }
+    }
+
+    pub(crate) fn prepare_frame(&self) {
+        let mut pressed_frame = self.pressed_frame.borrow_mut();
+        pressed_frame.clear();
+        pressed_frame.append(&mut self.pressed.borrow_mut());
+    }
+
+    pub(crate) fn is_down(&self, button: MouseButton) -> bool {
+        self.down.contains(&button)
+    }
+
+    pub(crate) fn was_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_frame.borrow().contains(&button)
+    }
+
+    /// Forgets held buttons, their release may never arrive once the window lost focus
+    pub(crate) fn release_all(&mut self) {
+        self.down.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn other_buttons_leave_the_main_buttons_alone() {
+        let mapping = MouseButtonMapping::standard();
+        assert_eq!(mapping.get(MouseButton::Left), Some(ImguiMouseButton::Left));
+        assert_eq!(
+            mapping.get(MouseButton::Middle),
+            Some(ImguiMouseButton::Middle)
+        );
+        assert_eq!(
+            mapping.get(back_buttons()[0]),
+            Some(ImguiMouseButton::Extra1)
+        );
+        assert_eq!(
+            mapping.get(forward_buttons()[0]),
+            Some(ImguiMouseButton::Extra2)
+        );
+        for index in 0..3 {
+            let button = MouseButton::Other(index);
+            if !back_buttons().contains(&button) && !forward_buttons().contains(&button) {
+                assert_eq!(mapping.get(button), None);
+            }
+        }
+    }
+
+    #[test]
+    fn presses_last_one_frame() {
+        let mut mouse = MouseState::default();
+        let button = MouseButton::Other(12);
+        mouse.handle(button, true);
+        mouse.handle(button, false);
+        assert!(!mouse.was_pressed(button));
+
+        mouse.prepare_frame();
+        assert!(mouse.was_pressed(button));
+        assert!(!mouse.is_down(button));
+
+        mouse.handle(button, true);
+        mouse.prepare_frame();
+        mouse.prepare_frame();
+        assert!(!mouse.was_pressed(button));
+        assert!(mouse.is_down(button));
+    }
+}
@@ -0,0 +1,229 @@
+//! Mouse wheel and trackpad scrolling.
+//!
+//! Line deltas from mouse wheels are passed through as wheel steps. Pixel deltas from trackpads are
+//! turned into fractional steps, where one step covers the same distance imgui scrolls for one
+//! wheel notch, so a trackpad scrolls the content by the distance the fingers moved.
+
+use imgui::Io;
+use std::cell::Cell;
+use std::time::Duration;
+use winit::event::{MouseScrollDelta, TouchPhase};
+
+/// Lines imgui scrolls for one wheel step
+const LINES_PER_STEP: f32 = 5.0;
+
+/// Scrolling settings
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScrollConfig {
+    /// Multiplier for each axis, `[horizontal, vertical]`. Negative values invert the axis.
+    pub speed: [f32; 2],
+    /// Spreads each scroll over a few frames instead of jumping to the new position
+    pub smooth: bool,
+    /// Time a smooth scroll takes to cover about two thirds of its distance
+    pub smooth_time: Duration,
+    /// Keeps scrolling after a trackpad gesture ends, slowing down over time. Only useful on
+    /// platforms that report the end of trackpad gestures and don't add inertia themselves.
+    pub kinetic: bool,
+    /// Fraction of the kinetic scrolling speed lost every second, from 0.0 to 1.0
+    pub kinetic_friction: f32,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        ScrollConfig {
+            speed: [1.0, 1.0],
+            smooth: false,
+            smooth_time: Duration::from_millis(80),
+            kinetic: false,
+            kinetic_friction: 0.95,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ScrollState {
+    pub(crate) config: ScrollConfig,
+    /// Steps not yet handed to imgui when scrolling smoothly
+    pending: Cell<[f32; 2]>,
+    /// Steps received from a trackpad since the last frame
+    frame_delta: Cell<[f32; 2]>,
+    /// Trackpad speed in steps per second, averaged over the last frames
+    velocity: Cell<[f32; 2]>,
+    /// Speed of the kinetic scroll in progress
+    momentum: Cell<Option<[f32; 2]>>,
+}
+
+impl ScrollState {
+    pub(crate) fn handle(
+        &mut self,
+        io: &mut Io,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+        hidpi_factor: f64,
+    ) {
+        let (steps, precise) = match delta {
+            MouseScrollDelta::LineDelta(h, v) => ([h, v], false),
+            MouseScrollDelta::PixelDelta(pos) => {
+                let pos = pos.to_logical::<f64>(hidpi_factor);
+                let step = (io.default_font_size() * LINES_PER_STEP).max(1.0);
+                ([pos.x as f32 / step, pos.y as f32 / step], true)
+            }
+        };
+        let steps = [
+            steps[0] * self.config.speed[0],
+            steps[1] * self.config.speed[1],
+        ];
+
+        match phase {
+            TouchPhase::Started => {
+                self.momentum.set(None);
+                self.velocity.set([0.0, 0.0]);
+            }
+            TouchPhase::Ended if precise && self.config.kinetic => {
+                self.momentum.set(Some(self.velocity.get()));
+            }
+            _ => (),
+        }
+        if precise {
+            let frame_delta = self.frame_delta.get();
+            self.frame_delta
+                .set([frame_delta[0] + steps[0], frame_delta[1] + steps[1]]);
+        } else {
+            self.momentum.set(None);
+        }
+
+        self.scroll(io, steps);
+    }
+
+    fn scroll(&self, io: &mut Io, steps: [f32; 2]) {
+        if self.config.smooth {
+            let pending = self.pending.get();
+            self.pending
+                .set([pending[0] + steps[0], pending[1] + steps[1]]);
+        } else {
+            io.mouse_wheel_h += steps[0];
+            io.mouse_wheel += steps[1];
+        }
+    }
+
+    /// Continues kinetic scrolling and hands the next part of smooth scrolls to imgui
+    pub(crate) fn prepare_frame(&self, io: &mut Io) {
+        let delta_time = io.delta_time;
+        if delta_time <= 0.0 {
+            return;
+        }
+
+        let velocity = self.velocity.get();
+        let frame_delta = self.frame_delta.replace([0.0, 0.0]);
+        self.velocity.set([
+            (velocity[0] + frame_delta[0] / delta_time) * 0.5,
+            (velocity[1] + frame_delta[1] / delta_time) * 0.5,
+        ]);
+
+        if let Some(mut momentum) = self.momentum.get() {
+            self.scroll(io, [momentum[0] * delta_time, momentum[1] * delta_time]);
+            let decay = (1.0 - self.config.kinetic_friction.clamp(0.0, 1.0)).powf(delta_time);
+            momentum = [momentum[0] * decay, momentum[1] * decay];
+            if momentum[0].abs().max(momentum[1].abs()) > 0.05 {
+                self.momentum.set(Some(momentum));
+            } else {
+                self.momentum.set(None);
+            }
+        }
+
+        if self.config.smooth {
+            let smooth_time = self.config.smooth_time.as_secs_f32();
+            let factor = if smooth_time > 0.0 {
+                1.0 - (-delta_time / smooth_time).exp()
+            } else {
+                1.0
+            };
+            let release = |pending: &mut f32| {
+                let step = if pending.abs() < 0.001 {
+                    *pending
+                } else {
+                    *pending * factor
+                };
+                *pending -= step;
+                step
+            };
+            let mut pending = self.pending.get();
+            io.mouse_wheel_h += release(&mut pending[0]);
+            io.mouse_wheel += release(&mut pending[1]);
+            self.pending.set(pending);
+        } else {
+            let pending = self.pending.replace([0.0, 0.0]);
+            io.mouse_wheel_h += pending[0];
+            io.mouse_wheel += pending[1];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::MutexGuard;
+    use winit::dpi::PhysicalPosition;
+
+    fn context() -> (MutexGuard<'static, ()>, imgui::Context) {
+        let (guard, mut imgui) = crate::test::test_ctx();
+        imgui.fonts().build_rgba32_texture();
+        imgui.io_mut().delta_time = 1.0 / 60.0;
+        (guard, imgui)
+    }
+
+    #[test]
+    fn pixel_deltas_keep_their_magnitude() {
+        let (_guard, mut imgui) = context();
+        let io = imgui.io_mut();
+        let step = io.default_font_size() * LINES_PER_STEP;
+        let mut scroll = ScrollState::default();
+
+        let pixels = MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, f64::from(step)));
+        scroll.handle(io, pixels, TouchPhase::Moved, 1.0);
+        scroll.handle(io, pixels, TouchPhase::Moved, 1.0);
+        assert!((io.mouse_wheel - 2.0).abs() < 1e-5);
+
+        scroll.handle(
+            io,
+            MouseScrollDelta::LineDelta(0.0, -1.0),
+            TouchPhase::Moved,
+            1.0,
+        );
+        assert!((io.mouse_wheel - 1.0).abs() < 1e-5);
+
+        scroll.config.speed = [1.0, -0.5];
+        scroll.handle(
+            io,
+            MouseScrollDelta::LineDelta(0.0, 2.0),
+            TouchPhase::Moved,
+            1.0,
+        );
+        assert!(io.mouse_wheel.abs() < 1e-5);
+    }
+
+    #[test]
+    fn smooth_scrolling_spreads_over_frames() {
+        let (_guard, mut imgui) = context();
+        let io = imgui.io_mut();
+        let mut scroll = ScrollState::default();
+        scroll.config.smooth = true;
+
+        scroll.handle(
+            io,
+            MouseScrollDelta::LineDelta(0.0, 1.0),
+            TouchPhase::Moved,
+            1.0,
+        );
+        assert_eq!(io.mouse_wheel, 0.0);
+
+        let mut total = 0.0;
+        for _ in 0..60 {
+            io.mouse_wheel = 0.0;
+            scroll.prepare_frame(io);
+            assert!(io.mouse_wheel < 1.0);
+            total += io.mouse_wheel;
+        }
+        assert!((total - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,13 @@
+use imgui::Context;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// imgui has one current context per process, so tests creating one take turns
+static TEST_MUTEX: Mutex<()> = Mutex::new(());
+
+pub(crate) fn test_ctx() -> (MutexGuard<'static, ()>, Context) {
+    // A failed test leaves the lock poisoned, the other tests can still run
+    let guard = TEST_MUTEX.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut ctx = Context::create();
+    ctx.set_ini_filename(None);
+    (guard, ctx)
+}
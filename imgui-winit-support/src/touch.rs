@@ -0,0 +1,327 @@
+//! Touch input support.
+//!
+//! The primary finger acts as the mouse: a tap is a left click, dragging holds the left button
+//! and a long press is a right click. Two fingers scroll, and pinching produces a zoom factor the
+//! application can read through [`WinitPlatform::pinch_zoom`](crate::WinitPlatform::pinch_zoom).
+
+use imgui::{ConfigFlags, Io, Style};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use winit::event::TouchPhase;
+
+use crate::Button;
+
+/// Touch gesture settings
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TouchConfig {
+    /// How long the primary finger has to be held still to right-click
+    pub long_press_time: Duration,
+    /// Distance in logical pixels the primary finger can move before it's considered a drag
+    pub drag_threshold: f32,
+    /// Distance in logical pixels a two finger pan has to move to scroll by one wheel step
+    pub scroll_step: f32,
+    /// `style.touch_extra_padding` is raised to at least this value once touch input is detected
+    pub extra_padding: [f32; 2],
+}
+
+impl Default for TouchConfig {
+    fn default() -> Self {
+        TouchConfig {
+            long_press_time: Duration::from_millis(500),
+            drag_threshold: 8.0,
+            scroll_step: 20.0,
+            extra_padding: [4.0, 4.0],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Finger {
+    id: u64,
+    pos: [f32; 2],
+}
+
+/// State of the primary finger before it turns into a drag, a tap or a long press
+#[derive(Copy, Clone, Debug)]
+struct PendingPress {
+    start: Instant,
+    pos: [f32; 2],
+}
+
+#[derive(Debug)]
+pub(crate) struct TouchState {
+    pub(crate) config: TouchConfig,
+    fingers: Vec<Finger>,
+    primary: Option<u64>,
+    pending: Cell<Option<PendingPress>>,
+    dragging: bool,
+    gesture: Option<([f32; 2], f32)>,
+    zoom: Cell<f32>,
+    detected: bool,
+}
+
+impl Default for TouchState {
+    fn default() -> Self {
+        TouchState {
+            config: TouchConfig::default(),
+            fingers: Vec::new(),
+            primary: None,
+            pending: Cell::new(None),
+            dragging: false,
+            gesture: None,
+            zoom: Cell::new(1.0),
+            detected: false,
+        }
+    }
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+impl TouchState {
+    pub(crate) fn handle(
+        &mut self,
+        io: &mut Io,
+        buttons: &[Button; 5],
+        id: u64,
+        phase: TouchPhase,
+        pos: [f32; 2],
+    ) {
+        if !self.detected {
+            self.detected = true;
+            io.config_flags.insert(ConfigFlags::IS_TOUCH_SCREEN);
+        }
+
+        match phase {
+            TouchPhase::Started => {
+                self.fingers.push(Finger { id, pos });
+                match self.fingers.len() {
+                    1 => {
+                        self.primary = Some(id);
+                        self.dragging = false;
+                        self.pending.set(Some(PendingPress {
+                            start: Instant::now(),
+                            pos,
+                        }));
+                        io.mouse_pos = pos;
+                    }
+                    2 => {
+                        // A second finger turns the touch into a gesture, so the primary
+                        // finger stops acting as the mouse
+                        self.pending.set(None);
+                        if self.dragging {
+                            buttons[0].set(false);
+                            self.dragging = false;
+                        }
+                        self.gesture = Some(self.centroid_and_spread());
+                    }
+                    _ => (),
+                }
+            }
+            TouchPhase::Moved => {
+                if let Some(finger) = self.fingers.iter_mut().find(|finger| finger.id == id) {
+                    finger.pos = pos;
+                }
+
+                if let Some((last_centroid, last_spread)) = self.gesture {
+                    let (centroid, spread) = self.centroid_and_spread();
+                    let step = self.config.scroll_step.max(1.0);
+                    io.mouse_wheel_h += (centroid[0] - last_centroid[0]) / step;
+                    io.mouse_wheel += (centroid[1] - last_centroid[1]) / step;
+                    if last_spread > 0.0 && spread > 0.0 {
+                        self.zoom.set(self.zoom.get() * spread / last_spread);
+                    }
+                    self.gesture = Some((centroid, spread));
+                } else if self.primary == Some(id) {
+                    io.mouse_pos = pos;
+                    if let Some(pending) = self.pending.get() {
+                        if distance(pending.pos, pos) > self.config.drag_threshold {
+                            self.pending.set(None);
+                            self.dragging = true;
+                            buttons[0].set(true);
+                        }
+                    }
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.fingers.retain(|finger| finger.id != id);
+
+                if self.primary == Some(id) {
+                    if self.pending.take().is_some() && phase == TouchPhase::Ended {
+                        // A tap, pressed and released within the same frame
+                        buttons[0].set(true);
+                        buttons[0].set(false);
+                    } else if self.dragging {
+                        buttons[0].set(false);
+                    }
+                    self.dragging = false;
+                    self.primary = None;
+                }
+
+                if self.fingers.len() < 2 {
+                    self.gesture = None;
+                }
+                if self.fingers.is_empty() {
+                    self.primary = None;
+                }
+            }
+        }
+    }
+
+    /// Fires the long press
+    pub(crate) fn prepare_frame(&self, buttons: &[Button; 5]) {
+        if let Some(pending) = self.pending.get() {
+            if pending.start.elapsed() >= self.config.long_press_time {
+                self.pending.set(None);
+                buttons[1].set(true);
+                buttons[1].set(false);
+            }
+        }
+    }
+
+    /// Clears the zoom factor once the UI of the frame read it
+    pub(crate) fn prepare_render(&self) {
+        self.zoom.set(1.0);
+    }
+
+    pub(crate) fn zoom(&self) -> f32 {
+        self.zoom.get()
+    }
+
+    pub(crate) fn detected(&self) -> bool {
+        self.detected
+    }
+
+    pub(crate) fn apply_style(&self, style: &mut Style) {
+        if self.detected {
+            for (padding, extra) in style
+                .touch_extra_padding
+                .iter_mut()
+                .zip(self.config.extra_padding.iter())
+            {
+                *padding = padding.max(*extra);
+            }
+        }
+    }
+
+    fn centroid_and_spread(&self) -> ([f32; 2], f32) {
+        match self.fingers.as_slice() {
+            [a, b, ..] => (
+                [(a.pos[0] + b.pos[0]) * 0.5, (a.pos[1] + b.pos[1]) * 0.5],
+                distance(a.pos, b.pos),
+            ),
+            [a] => (a.pos, 0.0),
+            [] => ([0.0, 0.0], 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primary_touch_acts_as_the_mouse() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        let buttons = [Button::INIT; 5];
+        let mut touch = TouchState::default();
+
+        touch.handle(io, &buttons, 1, TouchPhase::Started, [10.0, 20.0]);
+        assert!(touch.detected());
+        assert!(io.config_flags.contains(ConfigFlags::IS_TOUCH_SCREEN));
+        assert_eq!(io.mouse_pos, [10.0, 20.0]);
+        assert!(!buttons[0].get());
+
+        // Small moves still count as a tap
+        touch.handle(io, &buttons, 1, TouchPhase::Moved, [13.0, 20.0]);
+        assert_eq!(io.mouse_pos, [13.0, 20.0]);
+        assert!(!buttons[0].get());
+
+        touch.handle(io, &buttons, 1, TouchPhase::Moved, [40.0, 20.0]);
+        assert_eq!(io.mouse_pos, [40.0, 20.0]);
+        assert!(buttons[0].get());
+        assert!(buttons[0].get());
+
+        // Other fingers don't move the mouse, they turn the drag into a gesture
+        touch.handle(io, &buttons, 2, TouchPhase::Started, [80.0, 20.0]);
+        assert!(!buttons[0].get());
+        touch.handle(io, &buttons, 2, TouchPhase::Moved, [80.0, 60.0]);
+        assert_eq!(io.mouse_pos, [40.0, 20.0]);
+        assert_eq!(io.mouse_wheel, 1.0);
+    }
+
+    #[test]
+    fn ending_a_touch_taps_and_cancelling_it_doesnt() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        let buttons = [Button::INIT; 5];
+        let mut touch = TouchState::default();
+
+        touch.handle(io, &buttons, 1, TouchPhase::Started, [10.0, 20.0]);
+        touch.handle(io, &buttons, 1, TouchPhase::Ended, [10.0, 20.0]);
+        assert!(buttons[0].get());
+        assert!(!buttons[0].get());
+
+        touch.handle(io, &buttons, 2, TouchPhase::Started, [10.0, 20.0]);
+        touch.handle(io, &buttons, 2, TouchPhase::Cancelled, [10.0, 20.0]);
+        assert!(!buttons[0].get());
+
+        // Both release a drag
+        touch.handle(io, &buttons, 3, TouchPhase::Started, [10.0, 20.0]);
+        touch.handle(io, &buttons, 3, TouchPhase::Moved, [60.0, 20.0]);
+        assert!(buttons[0].get());
+        touch.handle(io, &buttons, 3, TouchPhase::Cancelled, [60.0, 20.0]);
+        assert!(!buttons[0].get());
+        touch.handle(io, &buttons, 4, TouchPhase::Started, [10.0, 20.0]);
+        touch.handle(io, &buttons, 4, TouchPhase::Moved, [60.0, 20.0]);
+        assert!(buttons[0].get());
+        touch.handle(io, &buttons, 4, TouchPhase::Ended, [60.0, 20.0]);
+        assert!(!buttons[0].get());
+
+        // A finger that is gone no longer moves the mouse
+        touch.handle(io, &buttons, 4, TouchPhase::Moved, [90.0, 20.0]);
+        assert_eq!(io.mouse_pos, [60.0, 20.0]);
+    }
+
+    #[test]
+    fn long_press_right_clicks_once() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        let buttons = [Button::INIT; 5];
+        let mut touch = TouchState::default();
+        touch.config.long_press_time = Duration::ZERO;
+
+        touch.handle(io, &buttons, 1, TouchPhase::Started, [10.0, 20.0]);
+        touch.prepare_frame(&buttons);
+        assert!(buttons[1].get());
+        touch.prepare_frame(&buttons);
+        assert!(!buttons[1].get());
+
+        // and lifting the finger afterwards is no tap
+        touch.handle(io, &buttons, 1, TouchPhase::Ended, [10.0, 20.0]);
+        assert!(!buttons[0].get());
+    }
+
+    #[test]
+    fn pinch_zoom_lasts_until_the_ui_is_built() {
+        let (_guard, mut imgui) = crate::test::test_ctx();
+        let io = imgui.io_mut();
+        let buttons = [Button::INIT; 5];
+        let mut touch = TouchState::default();
+
+        touch.handle(io, &buttons, 1, TouchPhase::Started, [100.0, 100.0]);
+        touch.handle(io, &buttons, 2, TouchPhase::Started, [200.0, 100.0]);
+        touch.handle(io, &buttons, 2, TouchPhase::Moved, [300.0, 100.0]);
+
+        touch.prepare_frame(&buttons);
+        assert_eq!(touch.zoom(), 2.0);
+        touch.prepare_render();
+        assert_eq!(touch.zoom(), 1.0);
+
+        touch.handle(io, &buttons, 1, TouchPhase::Moved, [200.0, 100.0]);
+        touch.prepare_frame(&buttons);
+        assert_eq!(touch.zoom(), 0.5);
+    }
+}
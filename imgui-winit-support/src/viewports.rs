@@ -0,0 +1,626 @@
+//! Platform windows for imgui viewports.
+//!
+//! With viewports enabled, imgui windows dragged outside the main window get an OS window of their
+//! own. Positions are desktop coordinates in logical pixels, so the mouse position handed to imgui
+//! is offset by the position of the window it's over.
+//!
+//! Winit can only create windows from the event loop, so viewports take their windows from a pool
+//! of hidden windows the application adds, or the application creates the missing windows when it
+//! has the event loop at hand. Windows of closed viewports are hidden and go back to the pool.
+
+use imgui::sys::ImGuiID;
+use imgui::{Context, PlatformMonitor, PlatformViewportBackend, Viewport, ViewportFlags};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::rc::Rc;
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition};
+use winit::error::OsError;
+use winit::event::WindowEvent;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{Window, WindowBuilder, WindowId};
+
+/// The calls viewports make on their windows, a trait so the bookkeeping can be tested without a
+/// display
+pub(crate) trait PlatformWindow {
+    fn id(&self) -> WindowId;
+    fn set_visible(&self, visible: bool);
+    fn set_decorations(&self, decorations: bool);
+    fn set_title(&self, title: &str);
+    /// Resizes the client area, in logical pixels
+    fn set_size(&self, size: [f32; 2]);
+    /// Moves the client area, in logical pixels on the desktop
+    fn set_pos(&self, pos: [f32; 2]);
+    /// Position of the client area, in logical pixels on the desktop
+    fn pos(&self) -> Option<[f32; 2]>;
+    fn focus(&self);
+    fn is_minimized(&self) -> bool;
+    fn scale_factor(&self) -> f64;
+}
+
+impl PlatformWindow for Window {
+    fn id(&self) -> WindowId {
+        self.id()
+    }
+
+    fn set_visible(&self, visible: bool) {
+        self.set_visible(visible)
+    }
+
+    fn set_decorations(&self, decorations: bool) {
+        self.set_decorations(decorations)
+    }
+
+    fn set_title(&self, title: &str) {
+        self.set_title(title)
+    }
+
+    fn set_size(&self, size: [f32; 2]) {
+        self.set_inner_size(LogicalSize::new(size[0], size[1]))
+    }
+
+    fn set_pos(&self, pos: [f32; 2]) {
+        if let (Ok(inner), Ok(outer)) = (self.inner_position(), self.outer_position()) {
+            self.set_outer_position(outer_pos(pos, inner, outer, self.scale_factor()));
+        } else {
+            self.set_outer_position(LogicalPosition::new(pos[0], pos[1]));
+        }
+    }
+
+    fn pos(&self) -> Option<[f32; 2]> {
+        let pos = self.inner_position().ok()?;
+        let pos = pos.to_logical::<f32>(self.scale_factor());
+        Some([pos.x, pos.y])
+    }
+
+    fn focus(&self) {
+        self.focus_window()
+    }
+
+    fn is_minimized(&self) -> bool {
+        let size = self.inner_size();
+        size.width == 0 || size.height == 0
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor()
+    }
+}
+
+/// Position to give the whole window so its client area ends up at `pos`. imgui places the client
+/// area, winit the whole window, which starts the border and title bar further up and left.
+fn outer_pos(
+    pos: [f32; 2],
+    inner: PhysicalPosition<i32>,
+    outer: PhysicalPosition<i32>,
+    scale: f64,
+) -> LogicalPosition<f64> {
+    LogicalPosition::new(
+        f64::from(pos[0]) - f64::from(inner.x - outer.x) / scale,
+        f64::from(pos[1]) - f64::from(inner.y - outer.y) / scale,
+    )
+}
+
+/// Everything imgui asked for a viewport, applied to its window once it has one
+#[derive(Debug)]
+struct ViewportWindow<W = Window> {
+    window: Option<W>,
+    pos: [f32; 2],
+    size: [f32; 2],
+    title: String,
+    decorations: bool,
+    visible: bool,
+    focused: bool,
+    request_move: bool,
+    request_resize: bool,
+    request_close: bool,
+}
+
+impl<W: PlatformWindow> ViewportWindow<W> {
+    fn new(viewport: &Viewport) -> Self {
+        ViewportWindow {
+            window: None,
+            pos: viewport.pos,
+            size: viewport.size,
+            title: String::new(),
+            decorations: !viewport.flags.contains(ViewportFlags::NO_DECORATION),
+            visible: false,
+            focused: false,
+            request_move: false,
+            request_resize: false,
+            request_close: false,
+        }
+    }
+
+    /// Gives the viewport a window, bringing it to the state imgui asked for
+    fn attach(&mut self, window: W) {
+        window.set_decorations(self.decorations);
+        window.set_title(&self.title);
+        window.set_size(self.size);
+        window.set_pos(self.pos);
+        window.set_visible(self.visible);
+        self.window = Some(window);
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ViewportWindows<W = Window> {
+    windows: HashMap<ImGuiID, ViewportWindow<W>>,
+    spare: Vec<W>,
+    main_pos: [f32; 2],
+}
+
+impl<W> Default for ViewportWindows<W> {
+    fn default() -> Self {
+        ViewportWindows {
+            windows: HashMap::new(),
+            spare: Vec::new(),
+            main_pos: [0.0, 0.0],
+        }
+    }
+}
+
+impl ViewportWindows {
+    /// Creates windows for the viewports waiting for one
+    pub(crate) fn create_windows<T>(
+        &mut self,
+        target: &EventLoopWindowTarget<T>,
+    ) -> Result<(), OsError> {
+        for viewport in self
+            .windows
+            .values_mut()
+            .filter(|viewport| viewport.window.is_none())
+        {
+            let window = WindowBuilder::new().with_visible(false).build(target)?;
+            viewport.attach(window);
+        }
+        Ok(())
+    }
+
+    /// Moves the main viewport along with the main window
+    pub(crate) fn update_main_pos(&mut self, window: &Window) {
+        if let Some(pos) = PlatformWindow::pos(window) {
+            self.main_pos = pos;
+        }
+    }
+}
+
+impl<W: PlatformWindow> ViewportWindows<W> {
+    fn find(&self, window_id: WindowId) -> Option<(&ImGuiID, &ViewportWindow<W>)> {
+        self.windows
+            .iter()
+            .find(|(_, viewport)| viewport.window.as_ref().map(W::id) == Some(window_id))
+    }
+
+    fn find_mut(&mut self, window_id: WindowId) -> Option<&mut ViewportWindow<W>> {
+        self.windows
+            .values_mut()
+            .find(|viewport| viewport.window.as_ref().map(W::id) == Some(window_id))
+    }
+
+    /// Adds a window to the pool, or gives it to a viewport waiting for one
+    pub(crate) fn add_window(&mut self, window: W) {
+        window.set_visible(false);
+        match self
+            .windows
+            .values_mut()
+            .find(|viewport| viewport.window.is_none())
+        {
+            Some(viewport) => viewport.attach(window),
+            None => self.spare.push(window),
+        }
+    }
+
+    /// Number of viewports waiting for a window
+    pub(crate) fn pending(&self) -> usize {
+        self.windows
+            .values()
+            .filter(|viewport| viewport.window.is_none())
+            .count()
+    }
+
+    pub(crate) fn window(&self, viewport_id: ImGuiID) -> Option<&W> {
+        self.windows
+            .get(&viewport_id)
+            .and_then(|viewport| viewport.window.as_ref())
+    }
+
+    /// The window of a viewport from its window id
+    pub(crate) fn find_window(&self, window_id: WindowId) -> Option<&W> {
+        self.find(window_id)
+            .and_then(|(_, viewport)| viewport.window.as_ref())
+    }
+
+    /// Position of a window on the desktop, the main window's for windows that aren't viewports
+    pub(crate) fn origin(&self, window_id: WindowId) -> [f32; 2] {
+        self.find(window_id)
+            .map_or(self.main_pos, |(_, viewport)| viewport.pos)
+    }
+
+    /// Handles the events that change the window of a viewport, returns false for events imgui
+    /// has to see as input
+    pub(crate) fn handle_event(&mut self, window_id: WindowId, event: &WindowEvent) -> bool {
+        let viewport = match self.find_mut(window_id) {
+            Some(viewport) => viewport,
+            None => return false,
+        };
+        let window = viewport.window.as_ref().unwrap();
+        match *event {
+            WindowEvent::Moved(_) => {
+                if let Some(pos) = window.pos() {
+                    viewport.pos = pos;
+                    viewport.request_move = true;
+                }
+            }
+            WindowEvent::Resized(size) => {
+                let size = size.to_logical::<f32>(window.scale_factor());
+                viewport.size = [size.width, size.height];
+                viewport.request_resize = true;
+            }
+            WindowEvent::ScaleFactorChanged { .. } => viewport.request_resize = true,
+            WindowEvent::CloseRequested => viewport.request_close = true,
+            WindowEvent::Focused(focused) => viewport.focused = focused,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// The platform backend installed in the imgui context
+struct Backend<W = Window>(Rc<RefCell<ViewportWindows<W>>>);
+
+impl<W> Backend<W> {
+    fn with<R>(
+        &self,
+        viewport: &Viewport,
+        f: impl FnOnce(&mut ViewportWindow<W>) -> R,
+    ) -> Option<R> {
+        self.0.borrow_mut().windows.get_mut(&viewport.id).map(f)
+    }
+}
+
+impl<W: PlatformWindow + 'static> PlatformViewportBackend for Backend<W> {
+    fn create_window(&mut self, viewport: &mut Viewport) {
+        let mut windows = self.0.borrow_mut();
+        let mut window = ViewportWindow::new(viewport);
+        if let Some(spare) = windows.spare.pop() {
+            window.attach(spare);
+        }
+        windows.windows.insert(viewport.id, window);
+    }
+
+    fn destroy_window(&mut self, viewport: &mut Viewport) {
+        let mut windows = self.0.borrow_mut();
+        if let Some(window) = windows
+            .windows
+            .remove(&viewport.id)
+            .and_then(|viewport| viewport.window)
+        {
+            windows.add_window(window);
+        }
+    }
+
+    fn show_window(&mut self, viewport: &mut Viewport) {
+        self.with(viewport, |window| {
+            window.visible = true;
+            if let Some(window) = &window.window {
+                window.set_visible(true);
+            }
+        });
+    }
+
+    fn set_window_pos(&mut self, viewport: &mut Viewport, pos: [f32; 2]) {
+        self.with(viewport, |window| {
+            window.pos = pos;
+            if let Some(window) = &window.window {
+                window.set_pos(pos);
+            }
+        });
+    }
+
+    fn get_window_pos(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+        if viewport.flags.contains(ViewportFlags::OWNED_BY_APP) {
+            return self.0.borrow().main_pos;
+        }
+        self.with(viewport, |window| window.pos)
+            .unwrap_or(viewport.pos)
+    }
+
+    fn set_window_size(&mut self, viewport: &mut Viewport, size: [f32; 2]) {
+        self.with(viewport, |window| {
+            window.size = size;
+            if let Some(window) = &window.window {
+                window.set_size(size);
+            }
+        });
+    }
+
+    fn get_window_size(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+        self.with(viewport, |window| window.size)
+            .unwrap_or(viewport.size)
+    }
+
+    fn set_window_focus(&mut self, viewport: &mut Viewport) {
+        self.with(viewport, |window| {
+            if let Some(window) = &window.window {
+                window.focus();
+            }
+        });
+    }
+
+    fn get_window_focus(&mut self, viewport: &mut Viewport) -> bool {
+        self.with(viewport, |window| window.focused)
+            .unwrap_or(false)
+    }
+
+    fn get_window_minimized(&mut self, viewport: &mut Viewport) -> bool {
+        self.with(viewport, |window| {
+            window.window.as_ref().map(W::is_minimized)
+        })
+        .flatten()
+        .unwrap_or(false)
+    }
+
+    fn set_window_title(&mut self, viewport: &mut Viewport, title: &str) {
+        self.with(viewport, |window| {
+            window.title = title.to_owned();
+            if let Some(window) = &window.window {
+                window.set_title(title);
+            }
+        });
+    }
+
+    fn set_window_alpha(&mut self, _viewport: &mut Viewport, _alpha: f32) {
+        // Not supported by winit
+    }
+
+    fn update_window(&mut self, viewport: &mut Viewport) {
+        // The requests are read by imgui on the next frame
+        if let Some(window) = self.0.borrow_mut().windows.get_mut(&viewport.id) {
+            viewport.platform_request_move |= std::mem::take(&mut window.request_move);
+            viewport.platform_request_resize |= std::mem::take(&mut window.request_resize);
+            viewport.platform_request_close |= std::mem::take(&mut window.request_close);
+        }
+    }
+
+    fn get_window_dpi_scale(&mut self, viewport: &mut Viewport) -> f32 {
+        self.with(viewport, |window| {
+            window
+                .window
+                .as_ref()
+                .map(|window| window.scale_factor() as f32)
+        })
+        .flatten()
+        .unwrap_or(1.0)
+    }
+}
+
+pub(crate) fn enable(imgui: &mut Context, window: &Window) -> Rc<RefCell<ViewportWindows>> {
+    let windows = Rc::new(RefCell::new(ViewportWindows::default()));
+    windows.borrow_mut().update_main_pos(window);
+    let main_viewport = imgui.main_viewport_mut();
+    main_viewport.platform_handle = Rc::as_ptr(&windows) as *mut c_void;
+    imgui.set_platform_backend(Backend(windows.clone()));
+
+    let monitors: Vec<_> = window
+        .available_monitors()
+        .map(|monitor| {
+            let scale = monitor.scale_factor();
+            let pos = monitor.position().to_logical::<f32>(scale);
+            let size = monitor.size().to_logical::<f32>(scale);
+            PlatformMonitor {
+                main_pos: [pos.x, pos.y],
+                main_size: [size.width, size.height],
+                work_pos: [pos.x, pos.y],
+                work_size: [size.width, size.height],
+                dpi_scale: scale as f32,
+            }
+        })
+        .collect();
+    if monitors.is_empty() {
+        // imgui needs at least one, the main window is better than nothing
+        let size = window.inner_size().to_logical::<f32>(window.scale_factor());
+        let main_pos = windows.borrow().main_pos;
+        imgui.set_monitors(&[PlatformMonitor {
+            main_pos,
+            main_size: [size.width, size.height],
+            work_pos: main_pos,
+            work_size: [size.width, size.height],
+            dpi_scale: window.scale_factor() as f32,
+        }]);
+    } else {
+        imgui.set_monitors(&monitors);
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::sync::MutexGuard;
+    use winit::dpi::PhysicalSize;
+
+    /// Records what viewports asked of it, in place of an OS window
+    #[derive(Debug, Default)]
+    struct TestWindow {
+        id: u64,
+        visible: Cell<bool>,
+        decorations: Cell<bool>,
+        title: RefCell<String>,
+        size: Cell<[f32; 2]>,
+        pos: Cell<[f32; 2]>,
+        scale: f64,
+    }
+
+    impl TestWindow {
+        fn new(id: u64) -> Self {
+            TestWindow {
+                id,
+                visible: Cell::new(true),
+                scale: 2.0,
+                ..Default::default()
+            }
+        }
+    }
+
+    impl PlatformWindow for TestWindow {
+        fn id(&self) -> WindowId {
+            WindowId::from(self.id)
+        }
+        fn set_visible(&self, visible: bool) {
+            self.visible.set(visible);
+        }
+        fn set_decorations(&self, decorations: bool) {
+            self.decorations.set(decorations);
+        }
+        fn set_title(&self, title: &str) {
+            *self.title.borrow_mut() = title.to_owned();
+        }
+        fn set_size(&self, size: [f32; 2]) {
+            self.size.set(size);
+        }
+        fn set_pos(&self, pos: [f32; 2]) {
+            self.pos.set(pos);
+        }
+        fn pos(&self) -> Option<[f32; 2]> {
+            Some(self.pos.get())
+        }
+        fn focus(&self) {}
+        fn is_minimized(&self) -> bool {
+            false
+        }
+        fn scale_factor(&self) -> f64 {
+            self.scale
+        }
+    }
+
+    fn backend() -> (MutexGuard<'static, ()>, Context, Backend<TestWindow>) {
+        let (guard, imgui) = crate::test::test_ctx();
+        let backend = Backend(Rc::new(RefCell::new(ViewportWindows::default())));
+        (guard, imgui, backend)
+    }
+
+    fn viewport(imgui: &mut Context, id: ImGuiID) -> &mut Viewport {
+        let viewport = imgui.main_viewport_mut();
+        viewport.id = id;
+        viewport.flags = ViewportFlags::empty();
+        viewport.pos = [100.0, 50.0];
+        viewport.size = [300.0, 200.0];
+        viewport
+    }
+
+    #[test]
+    fn viewports_take_windows_from_the_pool() {
+        let (_guard, mut imgui, mut backend) = backend();
+        backend.0.borrow_mut().add_window(TestWindow::new(1));
+        assert!(!backend.0.borrow().spare[0].visible.get());
+
+        backend.create_window(viewport(&mut imgui, 10));
+        backend.set_window_title(viewport(&mut imgui, 10), "Tools");
+        backend.show_window(viewport(&mut imgui, 10));
+        {
+            let windows = backend.0.borrow();
+            assert!(windows.spare.is_empty());
+            assert_eq!(windows.pending(), 0);
+            let window = windows.window(10).unwrap();
+            assert_eq!(window.id, 1);
+            assert_eq!(*window.title.borrow(), "Tools");
+            assert!(window.visible.get());
+        }
+
+        // Without spare windows, what imgui asks for is kept until a window is added
+        backend.create_window(viewport(&mut imgui, 20));
+        backend.set_window_title(viewport(&mut imgui, 20), "Profiler");
+        backend.set_window_pos(viewport(&mut imgui, 20), [400.0, 30.0]);
+        backend.set_window_size(viewport(&mut imgui, 20), [250.0, 150.0]);
+        backend.show_window(viewport(&mut imgui, 20));
+        assert_eq!(backend.0.borrow().pending(), 1);
+        assert!(backend.0.borrow().window(20).is_none());
+        assert_eq!(
+            backend.get_window_pos(viewport(&mut imgui, 20)),
+            [400.0, 30.0]
+        );
+
+        backend.0.borrow_mut().add_window(TestWindow::new(2));
+        {
+            let windows = backend.0.borrow();
+            assert_eq!(windows.pending(), 0);
+            let window = windows.window(20).unwrap();
+            assert_eq!(window.id, 2);
+            assert_eq!(*window.title.borrow(), "Profiler");
+            assert_eq!(window.pos.get(), [400.0, 30.0]);
+            assert_eq!(window.size.get(), [250.0, 150.0]);
+            assert!(window.decorations.get());
+            assert!(window.visible.get());
+        }
+
+        // Closed viewports give their window back hidden, for the next viewport to use
+        backend.destroy_window(viewport(&mut imgui, 10));
+        {
+            let windows = backend.0.borrow();
+            assert!(windows.window(10).is_none());
+            assert_eq!(windows.spare.len(), 1);
+            assert_eq!(windows.spare[0].id, 1);
+            assert!(!windows.spare[0].visible.get());
+        }
+        backend.create_window(viewport(&mut imgui, 30));
+        assert_eq!(backend.0.borrow().window(30).unwrap().id, 1);
+        assert!(backend.0.borrow().spare.is_empty());
+    }
+
+    #[test]
+    fn positions_are_relative_to_the_window_under_the_mouse() {
+        let (_guard, mut imgui, mut backend) = backend();
+        backend.0.borrow_mut().main_pos = [10.0, 20.0];
+        backend.0.borrow_mut().add_window(TestWindow::new(1));
+        backend.create_window(viewport(&mut imgui, 10));
+
+        let windows = backend.0.clone();
+        assert_eq!(windows.borrow().origin(WindowId::from(1)), [100.0, 50.0]);
+        // The main window, or any window that isn't a viewport
+        assert_eq!(windows.borrow().origin(WindowId::from(5)), [10.0, 20.0]);
+        let main = viewport(&mut imgui, 1);
+        main.flags = ViewportFlags::OWNED_BY_APP;
+        assert_eq!(backend.get_window_pos(main), [10.0, 20.0]);
+
+        // Moving the window moves the origin, and imgui is told on its next update
+        windows.borrow().window(10).unwrap().pos.set([700.0, 80.0]);
+        let moved = WindowEvent::Moved(PhysicalPosition::new(1400, 160));
+        assert!(windows.borrow_mut().handle_event(WindowId::from(1), &moved));
+        assert_eq!(windows.borrow().origin(WindowId::from(1)), [700.0, 80.0]);
+        let resized = WindowEvent::Resized(PhysicalSize::new(400, 300));
+        assert!(windows
+            .borrow_mut()
+            .handle_event(WindowId::from(1), &resized));
+        assert!(!windows.borrow_mut().handle_event(WindowId::from(5), &moved));
+
+        let viewport = viewport(&mut imgui, 10);
+        viewport.platform_request_move = false;
+        viewport.platform_request_resize = false;
+        viewport.platform_request_close = false;
+        backend.update_window(viewport);
+        assert!(viewport.platform_request_move);
+        assert!(viewport.platform_request_resize);
+        assert!(!viewport.platform_request_close);
+        assert_eq!(backend.get_window_pos(viewport), [700.0, 80.0]);
+        // logical pixels, at a scale of 2
+        assert_eq!(backend.get_window_size(viewport), [200.0, 150.0]);
+
+        viewport.platform_request_move = false;
+        backend.update_window(viewport);
+        assert!(!viewport.platform_request_move);
+    }
+
+    #[test]
+    fn windows_are_placed_by_their_client_area() {
+        // A border of 2 physical pixels and a title bar of 30, at a scale of 2
+        let outer = PhysicalPosition::new(198, 70);
+        let inner = PhysicalPosition::new(200, 100);
+        let pos = outer_pos([100.0, 50.0], inner, outer, 2.0);
+        assert_eq!((pos.x, pos.y), (99.0, 35.0));
+
+        let pos = outer_pos([100.0, 50.0], inner, inner, 2.0);
+        assert_eq!((pos.x, pos.y), (100.0, 50.0));
+    }
+}
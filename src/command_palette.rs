@@ -0,0 +1,337 @@
+use crate::keybindings::Keybindings;
+use imgui::{Condition, Key, Ui, WindowFlags};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Identifies a registered command
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CommandId(usize);
+
+/// An action that can be run from the palette or from menus
+pub struct Command {
+    pub name: String,
+    pub category: String,
+    /// Id of the keybinding that runs the command, its chord is shown as shortcut
+    pub binding: Option<String>,
+    callback: Box<dyn FnMut()>,
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .field("category", &self.category)
+            .field("binding", &self.binding)
+            .finish()
+    }
+}
+
+/// Scores how well `query` matches `text`, `None` if the characters of the query don't appear
+/// in order. Consecutive characters and characters at the start of a word score higher.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut text_chars = text.char_indices();
+    let mut previous_match: Option<usize> = None;
+    let mut previous_char: Option<char> = None;
+
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        let query_char = query_char.to_ascii_lowercase();
+        loop {
+            let (index, text_char) = text_chars.next()?;
+            let word_start = match previous_char {
+                None => true,
+                Some(previous) => {
+                    !previous.is_alphanumeric()
+                        || (previous.is_lowercase() && text_char.is_uppercase())
+                }
+            };
+            previous_char = Some(text_char);
+
+            if text_char.to_ascii_lowercase() == query_char {
+                score += 1;
+                if word_start {
+                    score += 8;
+                }
+                match previous_match {
+                    Some(previous) if previous + 1 == index => score += 5,
+                    Some(previous) => score -= (index - previous).min(5) as i32,
+                    None => score -= index.min(10) as i32,
+                }
+                previous_match = Some(index);
+                break;
+            }
+        }
+    }
+
+    Some(score)
+}
+
+/// Registry of commands, with a searchable overlay to run them
+#[derive(Debug)]
+pub struct CommandPalette {
+    commands: Vec<Command>,
+    recent: VecDeque<CommandId>,
+    query: String,
+    selected: usize,
+    just_opened: bool,
+    /// Whether the palette overlay is shown
+    pub open: bool,
+    /// Id of the keybinding that toggles the palette
+    pub toggle_binding: String,
+    /// Amount of recently run commands remembered for ranking
+    pub recent_limit: usize,
+    /// Maximum amount of results shown
+    pub max_results: usize,
+    /// Builds a main menu bar from the registered commands every frame
+    pub main_menu_bar: bool,
+}
+
+impl Default for CommandPalette {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            recent: VecDeque::new(),
+            query: String::new(),
+            selected: 0,
+            just_opened: false,
+            open: false,
+            toggle_binding: "command_palette.toggle".to_string(),
+            recent_limit: 10,
+            max_results: 12,
+            main_menu_bar: false,
+        }
+    }
+}
+
+impl CommandPalette {
+    /// Registers a new command
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        category: impl Into<String>,
+        binding: Option<String>,
+        callback: impl FnMut() + 'static,
+    ) -> CommandId {
+        self.commands.push(Command {
+            name: name.into(),
+            category: category.into(),
+            binding,
+            callback: Box::new(callback),
+        });
+
+        CommandId(self.commands.len() - 1)
+    }
+
+    /// Returns a registered command
+    pub fn get(&self, id: CommandId) -> Option<&Command> {
+        self.commands.get(id.0)
+    }
+
+    /// Runs a command and marks it as recently used
+    pub fn run(&mut self, id: CommandId) {
+        if let Some(command) = self.commands.get_mut(id.0) {
+            (command.callback)();
+
+            self.recent.retain(|recent| *recent != id);
+            self.recent.push_front(id);
+            self.recent.truncate(self.recent_limit);
+        }
+    }
+
+    /// Opens the palette with an empty query
+    pub fn show(&mut self) {
+        self.open = true;
+        self.just_opened = true;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Commands matching the query, best first. Recently used commands rank higher.
+    pub fn search(&self, query: &str) -> Vec<CommandId> {
+        let mut results: Vec<(i32, CommandId)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| {
+                let id = CommandId(index);
+                let label = format!("{}: {}", command.category, command.name);
+                let score = fuzzy_match(query, &command.name)
+                    .into_iter()
+                    .chain(fuzzy_match(query, &label))
+                    .max()?;
+                let recency = self
+                    .recent
+                    .iter()
+                    .position(|recent| *recent == id)
+                    .map(|position| self.recent_limit.saturating_sub(position) as i32 * 4)
+                    .unwrap_or(0);
+
+                Some((score + recency, id))
+            })
+            .collect();
+
+        // stable sort keeps registration order for equal scores
+        results.sort_by_key(|result| std::cmp::Reverse(result.0));
+        results.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Runs the commands whose keybinding was pressed, and handles the palette keybinding
+    pub fn handle_shortcuts(&mut self, ui: &Ui, bindings: &Keybindings) {
+        if ui.io().want_text_input {
+            return;
+        }
+
+        if bindings.pressed(ui, &self.toggle_binding) {
+            if self.open {
+                self.open = false;
+            } else {
+                self.show();
+            }
+            return;
+        }
+
+        let pressed: Vec<CommandId> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, command)| {
+                command
+                    .binding
+                    .as_ref()
+                    .is_some_and(|binding| bindings.pressed(ui, binding))
+            })
+            .map(|(index, _)| CommandId(index))
+            .collect();
+        for id in pressed {
+            self.run(id);
+        }
+    }
+
+    /// Handles shortcuts and draws the palette when open
+    pub fn render(&mut self, ui: &Ui, bindings: &Keybindings) {
+        self.handle_shortcuts(ui, bindings);
+        if self.main_menu_bar {
+            ui.main_menu_bar(|| self.menus(ui, bindings));
+        }
+        if !self.open {
+            return;
+        }
+
+        let display_size = ui.io().display_size;
+        let results = self.search(&self.query);
+        let mut chosen = None;
+
+        ui.window("##command_palette")
+            .position(
+                [display_size[0] * 0.5f32, display_size[1] * 0.15f32],
+                Condition::Always,
+            )
+            .position_pivot([0.5f32, 0f32])
+            .size(
+                [(display_size[0] * 0.5f32).max(300f32), 0f32],
+                Condition::Always,
+            )
+            .flags(
+                WindowFlags::NO_DECORATION
+                    | WindowFlags::NO_SAVED_SETTINGS
+                    | WindowFlags::NO_MOVE
+                    | WindowFlags::NO_DOCKING,
+            )
+            .build(|| {
+                if self.just_opened {
+                    ui.set_keyboard_focus_here();
+                    self.just_opened = false;
+                }
+                ui.set_next_item_width(-1f32);
+                if ui
+                    .input_text("##command_palette_query", &mut self.query)
+                    .hint("Type a command")
+                    .build()
+                {
+                    self.selected = 0;
+                }
+
+                let shown = results.len().min(self.max_results);
+                if shown > 0 {
+                    if ui.is_key_pressed(Key::DownArrow) {
+                        self.selected = (self.selected + 1) % shown;
+                    }
+                    if ui.is_key_pressed(Key::UpArrow) {
+                        self.selected = (self.selected + shown - 1) % shown;
+                    }
+                    self.selected = self.selected.min(shown - 1);
+                }
+                if ui.is_key_pressed(Key::Enter) || ui.is_key_pressed(Key::KeyPadEnter) {
+                    chosen = results.get(self.selected).copied();
+                }
+                if ui.is_key_pressed(Key::Escape) {
+                    self.open = false;
+                }
+
+                for (index, id) in results.iter().take(shown).enumerate() {
+                    let command = &self.commands[id.0];
+                    let label = format!("{}: {}##{}", command.category, command.name, id.0);
+                    if ui
+                        .selectable_config(label)
+                        .selected(index == self.selected)
+                        .build()
+                    {
+                        chosen = Some(*id);
+                    }
+                    if let Some(shortcut) = command
+                        .binding
+                        .as_ref()
+                        .and_then(|binding| bindings.chord(binding))
+                    {
+                        let shortcut = shortcut.to_string();
+                        ui.same_line_with_pos(
+                            ui.window_content_region_max()[0] - ui.calc_text_size(&shortcut)[0],
+                        );
+                        ui.text_disabled(shortcut);
+                    }
+                }
+            });
+
+        if let Some(id) = chosen {
+            self.open = false;
+            self.run(id);
+        }
+    }
+
+    /// Adds a menu per category with an entry for each command, showing its shortcut
+    pub fn menus(&mut self, ui: &Ui, bindings: &Keybindings) {
+        let mut categories: Vec<&str> = Vec::new();
+        for command in self.commands.iter() {
+            if !categories.contains(&command.category.as_str()) {
+                categories.push(&command.category);
+            }
+        }
+
+        let mut chosen = None;
+        for category in categories {
+            ui.menu(category, || {
+                for (index, command) in self.commands.iter().enumerate() {
+                    if command.category != category {
+                        continue;
+                    }
+                    let shortcut = command
+                        .binding
+                        .as_ref()
+                        .map(|binding| bindings.shortcut(binding))
+                        .unwrap_or_default();
+                    if ui
+                        .menu_item_config(&command.name)
+                        .shortcut(shortcut)
+                        .build()
+                    {
+                        chosen = Some(CommandId(index));
+                    }
+                }
+            });
+        }
+
+        if let Some(id) = chosen {
+            self.run(id);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+struct Composite {
+    opacity: f32,
+    // seconds since the offscreen mode was enabled, for animated effects
+    time: f32,
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var ui_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var ui_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> composite: Composite;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle covering the whole frame
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// The UI texture holds premultiplied colors, so opacity scales all channels
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(ui_texture, ui_sampler, in.uv) * composite.opacity;
+}
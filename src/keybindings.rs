@@ -0,0 +1,320 @@
+use imgui::{Condition, Key, KeyChord, Ui, WindowFocusedFlags};
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+
+/// Where a keybinding is active
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingScope {
+    /// Active anywhere, unless a text field is being edited
+    Global,
+    /// Active while the named window, or one of its children, is focused. These bindings have to
+    /// be checked while building that window, named as given to `ui.window`.
+    Window(String),
+    /// Active only while a text field is being edited
+    TextInput,
+}
+
+impl BindingScope {
+    /// Returns true if both scopes can be active at the same time
+    pub fn overlaps(&self, other: &BindingScope) -> bool {
+        match (self, other) {
+            (BindingScope::TextInput, BindingScope::TextInput) => true,
+            (BindingScope::TextInput, _) | (_, BindingScope::TextInput) => false,
+            (BindingScope::Window(a), BindingScope::Window(b)) => a == b,
+            _ => true,
+        }
+    }
+
+    /// Returns true if the scope is active in the current window
+    pub fn is_active(&self, ui: &Ui) -> bool {
+        let text_input = ui.io().want_text_input;
+        match self {
+            BindingScope::Global => !text_input,
+            BindingScope::Window(name) => {
+                !text_input
+                    && root_window_name(ui).map(CStr::to_bytes) == Some(name.as_bytes())
+                    && ui.is_window_focused_with_flags(WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS)
+            }
+            BindingScope::TextInput => text_input,
+        }
+    }
+}
+
+/// Name of the root of the window being built
+fn root_window_name(_ui: &Ui) -> Option<&CStr> {
+    unsafe {
+        let window = imgui::sys::igGetCurrentWindowRead();
+        if window.is_null() || (*window).RootWindow.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr((*(*window).RootWindow).Name))
+    }
+}
+
+/// An action that can be triggered from the keyboard
+#[derive(Debug, Clone)]
+pub struct Binding {
+    /// Unique identifier, also used as key in the saved file
+    pub id: String,
+    /// Name shown in the keybindings window
+    pub name: String,
+    pub scope: BindingScope,
+    pub default: Option<KeyChord>,
+    /// The chord currently bound, `None` if unbound
+    pub chord: Option<KeyChord>,
+}
+
+/// Registry of keybindings, with a window to remap them
+#[derive(Debug, Default)]
+pub struct Keybindings {
+    bindings: Vec<Binding>,
+    capturing: Option<usize>,
+    /// File the bindings are saved to from the keybindings window
+    pub path: Option<PathBuf>,
+    /// Shows the keybindings window
+    pub show_window: bool,
+    /// Error of the last save from the keybindings window, cleared when a save succeeds
+    pub save_error: Option<std::io::Error>,
+}
+
+impl Keybindings {
+    /// Registers an action with its default chord. Registering an existing id updates its name,
+    /// scope and default, keeping a chord that was remapped or loaded.
+    pub fn register(
+        &mut self,
+        id: impl Into<String>,
+        name: impl Into<String>,
+        scope: BindingScope,
+        default: Option<KeyChord>,
+    ) {
+        let id = id.into();
+        let name = name.into();
+        match self.bindings.iter_mut().find(|binding| binding.id == id) {
+            Some(binding) => {
+                if binding.chord == binding.default {
+                    binding.chord = default;
+                }
+                binding.name = name;
+                binding.scope = scope;
+                binding.default = default;
+            }
+            None => self.bindings.push(Binding {
+                id,
+                name,
+                scope,
+                default,
+                chord: default,
+            }),
+        }
+    }
+
+    /// All registered bindings
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Returns a registered binding
+    pub fn get(&self, id: &str) -> Option<&Binding> {
+        self.bindings.iter().find(|binding| binding.id == id)
+    }
+
+    /// The chord currently bound to an action
+    pub fn chord(&self, id: &str) -> Option<KeyChord> {
+        self.get(id).and_then(|binding| binding.chord)
+    }
+
+    /// The text to show as shortcut of an action, empty if unbound
+    pub fn shortcut(&self, id: &str) -> String {
+        self.chord(id)
+            .map(|chord| chord.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Binds a new chord to an action, `None` unbinds it
+    pub fn rebind(&mut self, id: &str, chord: Option<KeyChord>) {
+        if let Some(binding) = self.bindings.iter_mut().find(|binding| binding.id == id) {
+            binding.chord = chord;
+        }
+    }
+
+    /// Restores the default chord of an action
+    pub fn reset(&mut self, id: &str) {
+        if let Some(binding) = self.bindings.iter_mut().find(|binding| binding.id == id) {
+            binding.chord = binding.default;
+        }
+    }
+
+    /// Restores the default chord of every action
+    pub fn reset_all(&mut self) {
+        for binding in self.bindings.iter_mut() {
+            binding.chord = binding.default;
+        }
+    }
+
+    /// Pairs of action ids bound to the same chord in overlapping scopes
+    pub fn conflicts(&self) -> Vec<(&str, &str)> {
+        let mut conflicts = Vec::new();
+        for (index, a) in self.bindings.iter().enumerate() {
+            for b in self.bindings.iter().skip(index + 1) {
+                if a.chord.is_some() && a.chord == b.chord && a.scope.overlaps(&b.scope) {
+                    conflicts.push((a.id.as_str(), b.id.as_str()));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Returns true if the action's chord was pressed this frame and its scope is active.
+    ///
+    /// Window scoped actions have to be checked while building their window.
+    pub fn pressed(&self, ui: &Ui, id: &str) -> bool {
+        if self.capturing.is_some() {
+            return false;
+        }
+        match self.get(id) {
+            Some(Binding {
+                chord: Some(chord),
+                scope,
+                ..
+            }) => scope.is_active(ui) && ui.is_key_chord_pressed(*chord),
+            _ => false,
+        }
+    }
+
+    /// Adds a menu item for the action, with its shortcut taken from the current binding
+    pub fn menu_item(&self, ui: &Ui, id: &str) -> bool {
+        match self.get(id) {
+            Some(binding) => ui
+                .menu_item_config(&binding.name)
+                .shortcut(self.shortcut(id))
+                .build(),
+            None => false,
+        }
+    }
+
+    /// Saves the bound chords to a file, one `id = chord` per line
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut contents = String::new();
+        for binding in self.bindings.iter() {
+            let chord = binding
+                .chord
+                .map(|chord| chord.to_string())
+                .unwrap_or_default();
+            contents.push_str(&format!("{} = {}\n", binding.id, chord));
+        }
+        std::fs::write(path, contents)
+    }
+
+    /// Loads chords saved with `save`. Unknown ids and invalid chords are skipped, so a file from
+    /// an older version doesn't reset everything.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let contents = std::fs::read_to_string(path)?;
+        for line in contents.lines() {
+            let (id, chord) = match line.split_once(" = ") {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let chord = chord.trim();
+            let chord = if chord.is_empty() {
+                None
+            } else {
+                match chord.parse() {
+                    Ok(chord) => Some(chord),
+                    Err(_) => continue,
+                }
+            };
+            self.rebind(id.trim(), chord);
+        }
+        Ok(())
+    }
+
+    /// Waits for a new chord when rebinding, and draws the keybindings window if enabled
+    pub fn render(&mut self, ui: &Ui) {
+        if let Some(index) = self.capturing {
+            if ui.is_key_pressed_no_repeat(Key::Escape) {
+                self.capturing = None;
+            } else if let Some(&key) = Key::VARIANTS
+                .iter()
+                .find(|key| key.modifier().is_empty() && ui.is_key_pressed_no_repeat(**key))
+            {
+                self.bindings[index].chord = Some(KeyChord::new(ui.key_mods(), key));
+                self.capturing = None;
+            }
+        }
+
+        if !self.show_window {
+            return;
+        }
+
+        let mut opened = true;
+        let mut capture = None;
+        let mut reset = None;
+        let mut reset_all = false;
+        let mut save = false;
+        ui.window("Keybindings")
+            .opened(&mut opened)
+            .size([450f32, 400f32], Condition::FirstUseEver)
+            .build(|| {
+                let conflicts = self.conflicts();
+                let column = ui.window_content_region_max()[0] * 0.55f32;
+                for (index, binding) in self.bindings.iter().enumerate() {
+                    let id = binding.id.as_str();
+                    if conflicts.iter().any(|&(a, b)| a == id || b == id) {
+                        ui.text_colored([0.95f32, 0.30f32, 0.30f32, 1f32], &binding.name);
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text("This chord is also bound to another action");
+                        }
+                    } else {
+                        ui.text(&binding.name);
+                    }
+
+                    ui.same_line_with_pos(column);
+                    let label = if self.capturing == Some(index) {
+                        "Press a key...".to_string()
+                    } else {
+                        binding
+                            .chord
+                            .map(|chord| chord.to_string())
+                            .unwrap_or_else(|| "Unbound".to_string())
+                    };
+                    if ui.button_with_size(format!("{}##bind_{}", label, index), [120f32, 0f32]) {
+                        capture = Some(index);
+                    }
+
+                    ui.same_line();
+                    if ui.button(format!("Reset##reset_{}", index)) {
+                        reset = Some(index);
+                    }
+                }
+
+                ui.separator();
+                reset_all = ui.button("Reset all");
+                if self.path.is_some() {
+                    ui.same_line();
+                    save = ui.button("Save");
+                }
+                if let Some(error) = self.save_error.as_ref() {
+                    ui.text_colored(
+                        [0.95f32, 0.30f32, 0.30f32, 1f32],
+                        format!("Failed to save: {}", error),
+                    );
+                }
+            });
+
+        if let Some(index) = capture {
+            self.capturing = Some(index);
+        }
+        if let Some(index) = reset {
+            self.bindings[index].chord = self.bindings[index].default;
+        }
+        if reset_all {
+            self.reset_all();
+        }
+        if save {
+            if let Some(path) = self.path.as_ref() {
+                self.save_error = self.save(path).err();
+            }
+        }
+        self.show_window = opened;
+    }
+}
@@ -1,10 +1,33 @@
 use blue_engine::{
-    Camera, EnginePlugin, Operations, RenderPassColorAttachment, RenderPassDescriptor,
-    Renderer, Window as Win, ObjectStorage,
+    Camera, CommandEncoder, EnginePlugin, ObjectStorage, Operations, RenderPassColorAttachment,
+    RenderPassDescriptor, Renderer, TextureView, Window as Win,
 };
 
 pub use imgui;
-use imgui::{FontSource};
+use imgui::{FontSource, SharedFontAtlas};
+pub use imgui_wgpu;
+pub use imgui_winit_support;
+
+pub mod command_palette;
+pub mod keybindings;
+pub mod mouse_lock;
+pub mod offscreen;
+pub mod scene;
+pub mod screenshot;
+pub mod toast;
+pub mod tool_windows;
+pub mod viewports;
+pub use command_palette::{Command, CommandId, CommandPalette};
+pub use keybindings::{Binding, BindingScope, Keybindings};
+pub use mouse_lock::{MouseLock, MouseOwner};
+pub use offscreen::{CompositeBlend, OffscreenConfig, OffscreenUi};
+pub use scene::SceneAttachments;
+pub use screenshot::{
+    CaptureRect, CaptureSource, FrameTexture, ScreenshotError, ScreenshotRequest, ScreenshotResult,
+};
+pub use toast::{Toast, ToastCorner, ToastId, ToastLevel, Toasts};
+pub use tool_windows::{ToolWindow, ToolWindows};
+pub use viewports::Viewports;
 
 /// The imgui plugin
 pub struct ImGUI {
@@ -13,12 +36,49 @@ pub struct ImGUI {
     pub renderer: imgui_wgpu::Renderer,
     pub last_frame: std::time::Instant,
     pub draw_data: Option<imgui::DrawData>,
+    pub toasts: Toasts,
+    pub commands: CommandPalette,
+    pub keybindings: Keybindings,
+    pub mouse_lock: MouseLock,
+    pub tool_windows: ToolWindows,
+    pub viewports: Viewports,
+    pub gamepad: Option<Box<dyn imgui_winit_support::GamepadInput>>,
+    /// Format of the surface the UI is drawn to
+    pub surface_format: blue_engine::TextureFormat,
+    /// Texture the UI is drawn to before it's composited onto the frame, if enabled
+    pub offscreen: Option<OffscreenUi>,
+    scene: Option<SceneAttachments>,
+    screenshots: screenshot::Screenshots,
+    /// Codepoint the next icons merged into the font start at
+    next_icon: char,
+    /// Textures loaded from image files
+    #[cfg(feature = "image")]
+    pub texture_loader: imgui_wgpu::TextureLoader,
 }
 
 impl ImGUI {
-    /// Creates the imgui context and platform details
+    /// Creates the imgui context and platform details, drawing to the format the engine
+    /// configures its surface with
     pub fn new(window: &Win, renderer: &mut Renderer) -> Self {
-        let mut imgui = imgui::Context::create();
+        let surface_format = renderer
+            .surface
+            .as_ref()
+            .map(|surface| surface.get_supported_formats(&renderer.adapter)[0])
+            .unwrap_or(blue_engine::TextureFormat::Rgba8Unorm);
+        Self::with_format(window, renderer, surface_format)
+    }
+
+    /// Creates the imgui context and platform details for a surface of the given format.
+    ///
+    /// The UI is drawn in the color space the format expects, so it looks the same on sRGB,
+    /// linear and float surfaces.
+    pub fn with_format(
+        window: &Win,
+        renderer: &mut Renderer,
+        surface_format: blue_engine::TextureFormat,
+    ) -> Self {
+        // Tool windows share the font atlas, and with it the font texture of the renderer
+        let mut imgui = imgui::Context::create_with_shared_font_atlas(SharedFontAtlas::create());
         let mut platform = imgui_winit_support::WinitPlatform::init(&mut imgui);
 
         platform.attach_window(
@@ -38,29 +98,425 @@ impl ImGUI {
             &renderer.device,
             &renderer.queue,
             imgui_wgpu::RendererConfig {
-                #[cfg(not(feature = "android"))]
-                texture_format: renderer
-                    .surface
-                    .as_ref()
-                    .unwrap()
-                    .get_supported_formats(&renderer.adapter)[0],
+                texture_format: surface_format,
                 ..Default::default()
             },
         );
 
         let last_frame = std::time::Instant::now();
 
+        let commands = CommandPalette::default();
+        let mouse_lock = MouseLock::default();
+        let mut keybindings = Keybindings::default();
+        keybindings.register(
+            commands.toggle_binding.clone(),
+            "Toggle command palette",
+            BindingScope::Global,
+            Some(imgui::KeyMods::CTRL + imgui::KeyMods::SHIFT + imgui::Key::P),
+        );
+        keybindings.register(
+            mouse_lock.toggle_binding.clone(),
+            "Toggle mouse lock",
+            BindingScope::Global,
+            Some(imgui::Key::F1.into()),
+        );
+
         Self {
             context: imgui,
             platform,
             renderer: imgui_renderer,
             last_frame,
             draw_data: None,
+            toasts: Toasts::default(),
+            commands,
+            keybindings,
+            mouse_lock,
+            tool_windows: ToolWindows::default(),
+            viewports: Viewports::default(),
+            gamepad: None,
+            surface_format,
+            offscreen: None,
+            scene: None,
+            screenshots: Default::default(),
+            next_icon: '\u{e000}',
+            #[cfg(feature = "image")]
+            texture_loader: imgui_wgpu::TextureLoader::new(),
         }
     }
 
     pub fn ui<F: FnOnce(&mut imgui::Ui)>(&mut self, callback: F) {
-        callback(self.context.frame());
+        self.ui_with_keybindings(|ui, _| callback(ui));
+    }
+
+    /// Builds the UI like `ui`, with the keybindings to check actions with
+    pub fn ui_with_keybindings<F: FnOnce(&mut imgui::Ui, &Keybindings)>(&mut self, callback: F) {
+        let ui = self.context.frame();
+        callback(ui, &self.keybindings);
+        self.commands.render(ui, &self.keybindings);
+        self.keybindings.render(ui);
+        self.toasts.render(ui);
+        self.mouse_lock.update(ui, &self.keybindings);
+        self.platform.prepare_render_ui(ui);
+    }
+
+    /// Opens a tool window with its own imgui context. The surface has to be created for the
+    /// window from the same wgpu instance the engine uses.
+    pub fn add_tool_window(
+        &mut self,
+        window: Win,
+        surface: blue_engine::Surface,
+        renderer: &Renderer,
+    ) -> imgui_winit_support::winit::window::WindowId {
+        self.tool_windows
+            .add(&mut self.context, &self.renderer, window, surface, renderer)
+    }
+
+    /// Builds the UI of a tool window, it's drawn on the next update along with the main one
+    pub fn tool_window_ui<F: FnOnce(&mut imgui::Ui)>(
+        &mut self,
+        id: imgui_winit_support::winit::window::WindowId,
+        callback: F,
+    ) {
+        self.tool_windows.ui(&mut self.context, id, callback);
+    }
+
+    /// Lets imgui windows be dragged out of the game window, into the windows added with
+    /// [`add_viewport_window`](Self::add_viewport_window)
+    pub fn enable_viewports(&mut self, window: &Win) {
+        self.viewports
+            .enable(&mut self.context, &mut self.platform, window);
+    }
+
+    /// Adds a hidden window an imgui window can be dragged into. The surface has to be created for
+    /// the window from the same wgpu instance the engine uses.
+    pub fn add_viewport_window(
+        &mut self,
+        window: Win,
+        surface: blue_engine::Surface,
+        renderer: &Renderer,
+    ) {
+        self.viewports.add_window(
+            &mut self.platform,
+            window,
+            surface,
+            self.surface_format,
+            renderer,
+        );
+    }
+
+    /// Draws the UI into a texture of its own from the next frame on, which is then composited
+    /// onto the frame with the blend and opacity of the config
+    pub fn enable_offscreen(&mut self, config: OffscreenConfig, renderer: &Renderer) {
+        match self.offscreen.as_mut() {
+            Some(offscreen) => offscreen.config = config,
+            None => self.offscreen = Some(OffscreenUi::new(config, &renderer.device)),
+        }
+    }
+
+    /// Draws the UI straight onto the frame again
+    pub fn disable_offscreen(&mut self, renderer: &Renderer) {
+        if self.offscreen.take().is_some() {
+            self.reconfigure_for_frame(renderer);
+        }
+    }
+
+    /// Draws the UI into the multisampled texture and along with the depth buffer of the scene,
+    /// rebuilding the pipeline to match their sample count and depth format.
+    ///
+    /// These can't be read from the engine when the plugin is created: the engine hands plugins
+    /// its device, queue and surface, and the encoder and view of the frame after its own pass
+    /// ended, but not the textures a game renders its scene into. Only the game has them, so it
+    /// attaches them here.
+    pub fn attach_scene(&mut self, attachments: SceneAttachments, renderer: &Renderer) {
+        self.scene = Some(attachments);
+        if self.offscreen.is_none() {
+            self.reconfigure_for_frame(renderer);
+        }
+    }
+
+    /// Draws the UI straight onto the frame again, without the attachments of the scene
+    pub fn detach_scene(&mut self, renderer: &Renderer) -> Option<SceneAttachments> {
+        let attachments = self.scene.take();
+        if attachments.is_some() && self.offscreen.is_none() {
+            self.reconfigure_for_frame(renderer);
+        }
+        attachments
+    }
+
+    /// Switches the pipeline to drawing onto the frame, or the scene attachments if any
+    fn reconfigure_for_frame(&mut self, renderer: &Renderer) {
+        let scene = self.scene.as_ref();
+        self.renderer.retarget(
+            &renderer.device,
+            self.surface_format,
+            scene.map_or(1, SceneAttachments::sample_count),
+            scene.and_then(SceneAttachments::depth_format),
+        );
+    }
+
+    /// Loads a PNG, JPEG, TGA or KTX2 file into a texture for the UI. Loading a file again
+    /// shares its texture, which is unloaded once every load was released with
+    /// [`release_texture`](Self::release_texture).
+    #[cfg(feature = "image")]
+    pub fn load_texture(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        renderer: &Renderer,
+    ) -> Result<imgui::TextureId, imgui_wgpu::LoadError> {
+        self.texture_loader.load_path(
+            path,
+            &imgui_wgpu::LoadOptions::default(),
+            &mut self.renderer,
+            &renderer.device,
+            &renderer.queue,
+        )
+    }
+
+    /// Releases a texture loaded with [`load_texture`](Self::load_texture)
+    #[cfg(feature = "image")]
+    pub fn release_texture(&mut self, id: imgui::TextureId) {
+        self.texture_loader.release(id, &mut self.renderer);
+    }
+
+    /// Packs named images into one texture to draw them from as icons, with
+    /// `imgui_wgpu::IconUi::icon` or `imgui_wgpu::AtlasImage::from_atlas`
+    pub fn build_sprite_atlas(
+        &mut self,
+        builder: &imgui_wgpu::SpriteAtlasBuilder,
+        renderer: &Renderer,
+    ) -> Result<imgui_wgpu::SpriteAtlas, imgui_wgpu::AtlasError> {
+        builder.build(&renderer.device, &renderer.queue, &mut self.renderer)
+    }
+
+    /// Adds named images as glyphs of the default font, to write them inline in text. They're
+    /// placed in the private use area of Unicode, after the icons merged before.
+    pub fn merge_icons(
+        &mut self,
+        builder: &imgui_wgpu::SpriteAtlasBuilder,
+        renderer: &Renderer,
+    ) -> imgui_wgpu::FontIcons {
+        let first = self.next_icon;
+        self.next_icon = char::from_u32(first as u32 + builder.len() as u32)
+            .expect("icon codepoint isn't a char");
+        let icons = builder.merge_into_fonts(
+            &mut self.context,
+            None,
+            first,
+            &mut self.renderer,
+            &renderer.device,
+            &renderer.queue,
+        );
+        self.tool_windows
+            .share_font_texture(&mut self.context, &self.renderer);
+        icons
+    }
+
+    /// Takes a screenshot of the UI or the frame on the next frame, written to a PNG file once
+    /// the GPU is done with it a frame later. The result is sent to the returned receiver.
+    pub fn request_screenshot(&mut self, request: ScreenshotRequest) -> ScreenshotResult {
+        self.screenshots.request(request)
+    }
+
+    /// Draws the UI onto the frame, straight or through the offscreen texture
+    fn draw(
+        &mut self,
+        renderer: &Renderer,
+        window: &Win,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+    ) {
+        if let Some(offscreen) = self.offscreen.as_mut() {
+            let window_size = window.inner_size();
+            let size = offscreen.prepare(
+                renderer,
+                &mut self.renderer,
+                self.surface_format,
+                [window_size.width, window_size.height],
+            );
+            let draw_data = match self.context.main_viewport_mut().draw_data_mut() {
+                Some(draw_data) => draw_data,
+                None => return,
+            };
+            // The texture is stretched over the window, whatever its resolution
+            let [width, height] = draw_data.display_size;
+            if width > 0.0 && height > 0.0 {
+                draw_data.framebuffer_scale = [size[0] as f32 / width, size[1] as f32 / height];
+            }
+            offscreen.render(
+                draw_data,
+                &mut self.renderer,
+                renderer,
+                encoder,
+                view,
+                self.surface_format,
+            );
+            // Viewports are drawn with the same renderer onto surfaces of the format of the
+            // frame, so the pipeline is switched back to the one for it
+            if self.viewports.is_enabled() {
+                self.reconfigure_for_frame(renderer);
+            }
+            return;
+        }
+
+        let draw_data = match self.context.main_viewport().draw_data() {
+            Some(draw_data) => draw_data,
+            None => return,
+        };
+
+        let (color_attachment, depth_stencil_attachment) = match self.scene.as_ref() {
+            Some(scene) => (scene.color_attachment(view), scene.depth_attachment()),
+            None => (
+                RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: blue_engine::LoadOp::Load,
+                        store: true,
+                    },
+                },
+                None,
+            ),
+        };
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Render pass"),
+            color_attachments: &[Some(color_attachment)],
+            depth_stencil_attachment,
+        });
+
+        self.renderer
+            .render(
+                draw_data,
+                &renderer.queue,
+                &renderer.device,
+                &mut render_pass,
+            )
+            .unwrap();
+    }
+
+    /// Copies the textures of the requested screenshots after the UI was drawn
+    fn capture_screenshots(&mut self, renderer: &Renderer, encoder: &mut CommandEncoder) {
+        let requests = self.screenshots.take_requests();
+        if requests.is_empty() {
+            return;
+        }
+        let draw_data = match self.context.main_viewport().draw_data() {
+            Some(draw_data) => draw_data,
+            None => {
+                for (_, sender) in requests {
+                    let _ = sender.send(Err(ScreenshotError::NoDrawData));
+                }
+                return;
+            }
+        };
+
+        // The UI is drawn once for all screenshots of it without the offscreen mode
+        let mut ui_texture = None;
+        for (request, sender) in requests {
+            let frame;
+            let source = match &request.source {
+                CaptureSource::Frame(frame_texture) => {
+                    frame = frame_texture.clone();
+                    screenshot::CaptureTexture {
+                        texture: &frame.texture,
+                        format: frame.format,
+                        size: frame.size,
+                        premultiplied: false,
+                    }
+                }
+                CaptureSource::Ui => {
+                    let offscreen = self.offscreen.as_ref().and_then(|offscreen| {
+                        Some((offscreen.texture()?, offscreen.format()?, offscreen.size()?))
+                    });
+                    let (texture, format, size) = match offscreen {
+                        Some(offscreen) => offscreen,
+                        None => {
+                            if ui_texture.is_none() {
+                                ui_texture = screenshot::draw_ui(
+                                    draw_data,
+                                    &mut self.renderer,
+                                    renderer,
+                                    encoder,
+                                );
+                            }
+                            match ui_texture.as_ref() {
+                                Some((texture, size)) => {
+                                    (texture, self.renderer.texture_format(), *size)
+                                }
+                                None => {
+                                    let _ = sender.send(Err(ScreenshotError::NoDrawData));
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    screenshot::CaptureTexture {
+                        texture,
+                        format,
+                        size,
+                        premultiplied: true,
+                    }
+                }
+            };
+            self.screenshots.copy(
+                &renderer.device,
+                encoder,
+                source,
+                draw_data,
+                request,
+                sender,
+            );
+        }
+    }
+
+    /// Uses a gamepad for navigating the UI, and enables gamepad navigation
+    pub fn set_gamepad(&mut self, gamepad: impl imgui_winit_support::GamepadInput + 'static) {
+        self.context
+            .io_mut()
+            .config_flags
+            .insert(imgui::ConfigFlags::NAV_ENABLE_GAMEPAD);
+        self.gamepad = Some(Box::new(gamepad));
+    }
+
+    /// Gives the mouse to the UI or to the game. The game grabs and hides the cursor, and the UI
+    /// ignores the mouse until it gets it back.
+    pub fn set_mouse_owner(&mut self, owner: MouseOwner) {
+        self.mouse_lock.set_owner(owner);
+    }
+
+    /// Queues a toast notification, shown on top of the UI for the given duration
+    pub fn push_toast(
+        &mut self,
+        level: ToastLevel,
+        title: impl Into<String>,
+        body: impl Into<String>,
+        duration: std::time::Duration,
+    ) -> ToastId {
+        self.toasts.push(level, title, body, duration)
+    }
+
+    /// Registers a command for the command palette and the generated menus.
+    ///
+    /// A global keybinding with the id `category.name` is registered along with it, so the
+    /// shortcut can be remapped from the keybindings window.
+    pub fn register_command(
+        &mut self,
+        name: impl Into<String>,
+        category: impl Into<String>,
+        shortcut: Option<imgui::KeyChord>,
+        callback: impl FnMut() + 'static,
+    ) -> CommandId {
+        let name = name.into();
+        let category = category.into();
+        let binding = format!("{}.{}", category, name);
+        self.keybindings.register(
+            binding.clone(),
+            name.clone(),
+            BindingScope::Global,
+            shortcut,
+        );
+
+        self.commands
+            .register(name, category, Some(binding), callback)
     }
 }
 
@@ -78,6 +534,8 @@ impl EnginePlugin for ImGUI {
         if _renderer.surface.is_some() {
             self.platform
                 .handle_event(self.context.io_mut(), &_window, &_events);
+            self.tool_windows
+                .handle_event(&mut self.context, _renderer, _events);
         }
     }
 
@@ -92,39 +550,45 @@ impl EnginePlugin for ImGUI {
         encoder: &mut blue_engine::CommandEncoder,
         view: &blue_engine::TextureView,
     ) {
+        // Screenshots taken last frame were submitted since
+        self.screenshots.finish(&renderer.device);
+
         let now = std::time::Instant::now();
         self.context
             .io_mut()
             .update_delta_time(now - self.last_frame);
         self.last_frame = now;
 
+        #[cfg(feature = "image")]
+        self.texture_loader
+            .reload_changed(&mut self.renderer, &renderer.device, &renderer.queue);
+
+        if let Some(gamepad) = self.gamepad.as_mut() {
+            self.platform
+                .update_gamepad(self.context.io_mut(), gamepad.as_mut());
+        }
+
         self.platform
             .prepare_frame(self.context.io_mut(), &window)
             .expect("Failed to prepare frame");
 
-        let draw_data = self.context.render();
+        if self.platform.touch_detected() {
+            self.platform.apply_touch_style(self.context.style_mut());
+        }
 
-        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-            label: Some("Render pass"),
-            color_attachments: &[Some(RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: Operations {
-                    load: blue_engine::LoadOp::Load,
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        self.mouse_lock
+            .apply(self.context.io_mut(), &mut self.platform, &window);
+        self.platform.prepare_render_window(&window);
+        self.tool_windows.render(&mut self.context, renderer);
 
-        self.renderer
-            .render(
-                draw_data,
-                &renderer.queue,
-                &renderer.device,
-                &mut render_pass,
-            )
-            .unwrap();
+        self.context.render();
+        self.viewports
+            .render(&mut self.context, &self.platform, &self.renderer, renderer);
+
+        self.draw(renderer, window, encoder, view);
+        if !self.screenshots.is_empty() {
+            self.capture_screenshots(renderer, encoder);
+        }
     }
 }
 
@@ -159,23 +623,26 @@ fn imgui_redesign(imgui: &mut imgui::Context, hidpi_factor: f64) {
 
     imgui.io_mut().font_global_scale = (1.0 / hidpi_factor) as f32;
 
-    imgui.fonts().add_font(&[FontSource::TtfData {
-        data: include_bytes!("../resources/JetBrainsMono-Medium.ttf"),
-        size_pixels: 20f32,
-        config: Some(imgui::FontConfig {
-            name: Some("JetBrainsMono".to_string()),
-            ..Default::default()
-        }),
-    }]);
-
-    imgui.fonts().add_font(&[FontSource::DefaultFontData {
-        config: Some(imgui::FontConfig {
-            oversample_h: 1,
-            pixel_snap_h: true,
-            size_pixels: font_size,
-            ..Default::default()
-        }),
-    }]);
+    // A font atlas shared with other contexts already has the fonts
+    if imgui.fonts().fonts().is_empty() {
+        imgui.fonts().add_font(&[FontSource::TtfData {
+            data: include_bytes!("../resources/JetBrainsMono-Medium.ttf"),
+            size_pixels: 20f32,
+            config: Some(imgui::FontConfig {
+                name: Some("JetBrainsMono".to_string()),
+                ..Default::default()
+            }),
+        }]);
+
+        imgui.fonts().add_font(&[FontSource::DefaultFontData {
+            config: Some(imgui::FontConfig {
+                oversample_h: 1,
+                pixel_snap_h: true,
+                size_pixels: font_size,
+                ..Default::default()
+            }),
+        }]);
+    }
 
     imgui.set_renderer_name(Some("Blue Engine".to_string()));
 
@@ -0,0 +1,106 @@
+use crate::keybindings::Keybindings;
+use imgui::{ConfigFlags, Io, Ui};
+use imgui_winit_support::winit::window::{CursorGrabMode, Window};
+use imgui_winit_support::WinitPlatform;
+
+/// Who receives the mouse
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MouseOwner {
+    /// The cursor is visible and free, the UI receives the mouse
+    Ui,
+    /// The cursor is grabbed and hidden, the UI ignores the mouse
+    Game,
+}
+
+/// Switches the mouse between the UI and the game, for games that lock the cursor
+#[derive(Debug)]
+pub struct MouseLock {
+    owner: MouseOwner,
+    modal_open: bool,
+    applied: MouseOwner,
+    /// Flags that were already set before the game took the mouse, kept when giving it back
+    saved_flags: ConfigFlags,
+    /// Id of the keybinding that toggles the owner
+    pub toggle_binding: String,
+    /// Gives the mouse to the UI while a modal popup is open
+    pub release_for_modals: bool,
+}
+
+impl Default for MouseLock {
+    fn default() -> Self {
+        Self {
+            owner: MouseOwner::Ui,
+            modal_open: false,
+            applied: MouseOwner::Ui,
+            saved_flags: ConfigFlags::empty(),
+            toggle_binding: "mouse_lock.toggle".to_string(),
+            release_for_modals: true,
+        }
+    }
+}
+
+impl MouseLock {
+    /// The owner that was asked for, a modal popup can temporarily give the mouse to the UI
+    pub fn owner(&self) -> MouseOwner {
+        self.owner
+    }
+
+    /// The owner in effect, taking modal popups into account
+    pub fn effective_owner(&self) -> MouseOwner {
+        if self.modal_open && self.release_for_modals {
+            MouseOwner::Ui
+        } else {
+            self.owner
+        }
+    }
+
+    /// Gives the mouse to the UI or to the game, applied on the next frame
+    pub fn set_owner(&mut self, owner: MouseOwner) {
+        self.owner = owner;
+    }
+
+    /// Gives the mouse to the other side
+    pub fn toggle(&mut self) {
+        self.owner = match self.owner {
+            MouseOwner::Ui => MouseOwner::Game,
+            MouseOwner::Game => MouseOwner::Ui,
+        };
+    }
+
+    /// Handles the toggle keybinding and looks for open modal popups, at the end of the frame
+    pub fn update(&mut self, ui: &Ui, bindings: &Keybindings) {
+        if bindings.pressed(ui, &self.toggle_binding) {
+            self.toggle();
+        }
+        self.modal_open = unsafe { !imgui::sys::igGetTopMostPopupModal().is_null() };
+    }
+
+    /// Grabs or releases the cursor when the owner changed
+    pub fn apply(&mut self, io: &mut Io, platform: &mut WinitPlatform, window: &Window) {
+        let owner = self.effective_owner();
+        if owner == self.applied {
+            return;
+        }
+        self.applied = owner;
+
+        let flags = ConfigFlags::NO_MOUSE | ConfigFlags::NO_MOUSE_CURSOR_CHANGE;
+        match owner {
+            MouseOwner::Game => {
+                self.saved_flags = io.config_flags & flags;
+                io.config_flags.insert(flags);
+                io.mouse_pos = [-f32::MAX, -f32::MAX];
+                // Locking isn't supported everywhere, confining is the closest fallback
+                if window.set_cursor_grab(CursorGrabMode::Locked).is_err() {
+                    window.set_cursor_grab(CursorGrabMode::Confined).ok();
+                }
+                window.set_cursor_visible(false);
+            }
+            MouseOwner::Ui => {
+                io.config_flags.remove(flags - self.saved_flags);
+                window.set_cursor_grab(CursorGrabMode::None).ok();
+                window.set_cursor_visible(true);
+                platform.invalidate_cursor();
+            }
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use blue_engine::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendComponent,
+    BlendFactor, BlendOperation, BlendState, Buffer, BufferBindingType, BufferDescriptor,
+    BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Device, Extent3d,
+    FilterMode, FragmentState, LoadOp, MultisampleState, Operations, PipelineLayoutDescriptor,
+    PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, Renderer, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages, Texture, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
+    TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+use imgui::DrawData;
+use std::time::Instant;
+
+/// How the UI texture is blended onto the frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompositeBlend {
+    /// Drawn over the frame, as the UI is without a texture
+    Over,
+    /// Added to the frame, for glowing overlays
+    Additive,
+    /// Replaces the frame, transparent parts included
+    Replace,
+}
+
+impl CompositeBlend {
+    fn state(self) -> BlendState {
+        match self {
+            CompositeBlend::Over => BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            CompositeBlend::Additive => BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent::OVER,
+            },
+            CompositeBlend::Replace => BlendState::REPLACE,
+        }
+    }
+}
+
+/// Settings of the texture the UI is drawn to, changes apply on the next frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OffscreenConfig {
+    /// Size of the texture, stretched over the whole frame. The framebuffer size of the window
+    /// times `scale` when `None`
+    pub size: Option<[u32; 2]>,
+    /// Resolution of the UI relative to the window, used when `size` is `None`
+    pub scale: f32,
+    pub sample_count: u32,
+    /// Format of the texture, the format of the surface when `None`
+    pub format: Option<TextureFormat>,
+    pub blend: CompositeBlend,
+    /// Opacity the texture is composited with, for fading the UI in and out
+    pub opacity: f32,
+    /// Filter used when the texture is scaled to the frame
+    pub filter: FilterMode,
+}
+
+impl OffscreenConfig {
+    /// Size of the texture for a window with the given framebuffer size, at least a pixel wide
+    /// and high
+    pub fn texture_size(&self, window_size: [u32; 2]) -> [u32; 2] {
+        let size = self.size.unwrap_or_else(|| {
+            [
+                (window_size[0] as f32 * self.scale) as u32,
+                (window_size[1] as f32 * self.scale) as u32,
+            ]
+        });
+        [size[0].max(1), size[1].max(1)]
+    }
+}
+
+impl Default for OffscreenConfig {
+    fn default() -> Self {
+        Self {
+            size: None,
+            scale: 1.0,
+            sample_count: 1,
+            format: None,
+            blend: CompositeBlend::Over,
+            opacity: 1.0,
+            filter: FilterMode::Linear,
+        }
+    }
+}
+
+/// The texture the UI is drawn to, with its multisampled counterpart
+struct Target {
+    size: [u32; 2],
+    format: TextureFormat,
+    sample_count: u32,
+    texture: Texture,
+    view: TextureView,
+    msaa_view: Option<TextureView>,
+}
+
+impl Target {
+    fn new(device: &Device, size: [u32; 2], format: TextureFormat, sample_count: u32) -> Self {
+        let extent = Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("ImGUI offscreen texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC,
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let msaa_view = (sample_count > 1).then(|| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some("ImGUI offscreen multisampled texture"),
+                    size: extent,
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::RENDER_ATTACHMENT,
+                })
+                .create_view(&TextureViewDescriptor::default())
+        });
+
+        Self {
+            size,
+            format,
+            sample_count,
+            texture,
+            view,
+            msaa_view,
+        }
+    }
+}
+
+/// Draws the UI into a texture of its own, which is then composited onto the frame.
+///
+/// The texture can be given a lower resolution than the window, run through a custom composite
+/// shader for effects like a CRT filter, or be used elsewhere, such as on a surface in the scene.
+pub struct OffscreenUi {
+    pub config: OffscreenConfig,
+    target: Option<Target>,
+    shader: ShaderModule,
+    layout: BindGroupLayout,
+    uniform: Buffer,
+    sampler: (FilterMode, Sampler),
+    bind_group: Option<BindGroup>,
+    pipeline: Option<(CompositeBlend, TextureFormat, RenderPipeline)>,
+    started: Instant,
+}
+
+impl OffscreenUi {
+    pub fn new(config: OffscreenConfig, device: &Device) -> Self {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("ImGUI composite shader"),
+            source: ShaderSource::Wgsl(include_str!("composite.wgsl").into()),
+        });
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("ImGUI composite bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let uniform = device.create_buffer(&BufferDescriptor {
+            label: Some("ImGUI composite uniform buffer"),
+            size: 16,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            sampler: (config.filter, create_sampler(device, config.filter)),
+            config,
+            target: None,
+            shader,
+            layout,
+            uniform,
+            bind_group: None,
+            pipeline: None,
+            started: Instant::now(),
+        }
+    }
+
+    /// Replaces the composite shader with one for effects like a CRT filter.
+    ///
+    /// The shader needs a `vs_main` and an `fs_main` entry point, and draws a triangle of 3
+    /// vertices covering the frame. Group 0 holds the UI texture at binding 0, its sampler at
+    /// binding 1 and a uniform at binding 2 with the opacity, the seconds since the offscreen
+    /// mode was enabled and the texture size, as `f32`, `f32` and `vec2<f32>`. The texture holds
+    /// premultiplied colors.
+    pub fn set_shader(&mut self, device: &Device, shader: ShaderModuleDescriptor) {
+        self.shader = device.create_shader_module(shader);
+        self.pipeline = None;
+    }
+
+    /// The texture the UI was last drawn to
+    pub fn texture(&self) -> Option<&Texture> {
+        self.target.as_ref().map(|target| &target.texture)
+    }
+
+    /// The view of the texture the UI was last drawn to
+    pub fn view(&self) -> Option<&TextureView> {
+        self.target.as_ref().map(|target| &target.view)
+    }
+
+    /// Size of the texture the UI was last drawn to
+    pub fn size(&self) -> Option<[u32; 2]> {
+        self.target.as_ref().map(|target| target.size)
+    }
+
+    /// Format of the texture the UI was last drawn to
+    pub fn format(&self) -> Option<TextureFormat> {
+        self.target.as_ref().map(|target| target.format)
+    }
+
+    /// Creates the texture for the frame, and switches the imgui renderer to a pipeline drawing
+    /// into it. Returns the size of the texture.
+    pub(crate) fn prepare(
+        &mut self,
+        renderer: &Renderer,
+        imgui_renderer: &mut imgui_wgpu::Renderer,
+        surface_format: TextureFormat,
+        window_size: [u32; 2],
+    ) -> [u32; 2] {
+        let size = self.config.texture_size(window_size);
+        let format = self.config.format.unwrap_or(surface_format);
+        let sample_count = self.config.sample_count.max(1);
+
+        let current = matches!(&self.target, Some(target)
+            if target.size == size && target.format == format && target.sample_count == sample_count);
+        if !current {
+            self.target = Some(Target::new(&renderer.device, size, format, sample_count));
+            self.bind_group = None;
+        }
+
+        imgui_renderer.retarget(&renderer.device, format, sample_count, None);
+
+        if self.sampler.0 != self.config.filter {
+            self.sampler = (
+                self.config.filter,
+                create_sampler(&renderer.device, self.config.filter),
+            );
+            self.bind_group = None;
+        }
+
+        size
+    }
+
+    /// Draws the UI into the texture and composites it onto the view
+    pub(crate) fn render(
+        &mut self,
+        draw_data: &DrawData,
+        imgui_renderer: &mut imgui_wgpu::Renderer,
+        renderer: &Renderer,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        surface_format: TextureFormat,
+    ) {
+        let target = match self.target.as_ref() {
+            Some(target) => target,
+            None => return,
+        };
+
+        {
+            let (view, resolve_target) = match target.msaa_view.as_ref() {
+                Some(msaa_view) => (msaa_view, Some(&target.view)),
+                None => (&target.view, None),
+            };
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("ImGUI offscreen render pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            imgui_renderer
+                .render(
+                    draw_data,
+                    &renderer.queue,
+                    &renderer.device,
+                    &mut render_pass,
+                )
+                .unwrap();
+        }
+
+        let uniform = [
+            self.config.opacity,
+            self.started.elapsed().as_secs_f32(),
+            target.size[0] as f32,
+            target.size[1] as f32,
+        ];
+        let mut bytes = [0u8; 16];
+        for (chunk, value) in bytes.chunks_exact_mut(4).zip(uniform) {
+            chunk.copy_from_slice(&value.to_ne_bytes());
+        }
+        renderer.queue.write_buffer(&self.uniform, 0, &bytes);
+
+        let bind_group = self.bind_group.get_or_insert_with(|| {
+            renderer.device.create_bind_group(&BindGroupDescriptor {
+                label: Some("ImGUI composite bind group"),
+                layout: &self.layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&target.view),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&self.sampler.1),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: self.uniform.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let blend = self.config.blend;
+        if !matches!(&self.pipeline, Some((b, f, _)) if *b == blend && *f == surface_format) {
+            let pipeline = create_pipeline(
+                &renderer.device,
+                &self.shader,
+                &self.layout,
+                blend,
+                surface_format,
+            );
+            self.pipeline = Some((blend, surface_format, pipeline));
+        }
+        let (_, _, pipeline) = self.pipeline.as_ref().unwrap();
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("ImGUI composite render pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_sampler(device: &Device, filter: FilterMode) -> Sampler {
+    device.create_sampler(&SamplerDescriptor {
+        label: Some("ImGUI composite sampler"),
+        address_mode_u: AddressMode::ClampToEdge,
+        address_mode_v: AddressMode::ClampToEdge,
+        mag_filter: filter,
+        min_filter: filter,
+        ..Default::default()
+    })
+}
+
+fn create_pipeline(
+    device: &Device,
+    shader: &ShaderModule,
+    layout: &BindGroupLayout,
+    blend: CompositeBlend,
+    format: TextureFormat,
+) -> RenderPipeline {
+    let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("ImGUI composite pipeline layout"),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("ImGUI composite pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: Some(blend.state()),
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_follows_the_window_at_its_scale() {
+        let config = OffscreenConfig::default();
+        assert_eq!(config.texture_size([1280, 720]), [1280, 720]);
+
+        let config = OffscreenConfig {
+            scale: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(config.texture_size([1280, 720]), [640, 360]);
+        assert_eq!(config.texture_size([1281, 721]), [640, 360]);
+
+        // a minimized window still gets a texture
+        assert_eq!(config.texture_size([0, 0]), [1, 1]);
+        assert_eq!(config.texture_size([1, 3]), [1, 1]);
+    }
+
+    #[test]
+    fn fixed_size_ignores_the_window() {
+        let config = OffscreenConfig {
+            size: Some([320, 240]),
+            scale: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(config.texture_size([1280, 720]), [320, 240]);
+        assert_eq!(config.texture_size([0, 0]), [320, 240]);
+
+        let config = OffscreenConfig {
+            size: Some([0, 240]),
+            ..Default::default()
+        };
+        assert_eq!(config.texture_size([1280, 720]), [1, 240]);
+    }
+}
@@ -0,0 +1,78 @@
+use blue_engine::{
+    LoadOp, Operations, RenderPassColorAttachment, RenderPassDepthStencilAttachment, TextureFormat,
+    TextureView,
+};
+
+/// Attachments of the scene the UI is drawn along with, when the game renders with MSAA or a
+/// depth buffer.
+///
+/// The UI is drawn into the multisampled texture of the scene and resolved into the frame, with
+/// the depth buffer attached and left untouched. The views have to be given again when the
+/// textures are recreated, such as after a resize.
+#[derive(Debug)]
+pub struct SceneAttachments {
+    /// Sample count and view of the multisampled texture the scene is drawn to. Its content has
+    /// to be stored by the scene pass.
+    pub msaa: Option<(u32, TextureView)>,
+    /// Format and view of the depth buffer of the scene
+    pub depth: Option<(TextureFormat, TextureView)>,
+}
+
+impl SceneAttachments {
+    pub fn sample_count(&self) -> u32 {
+        self.msaa
+            .as_ref()
+            .map_or(1, |(sample_count, _)| *sample_count)
+    }
+
+    pub fn depth_format(&self) -> Option<TextureFormat> {
+        self.depth.as_ref().map(|(format, _)| *format)
+    }
+
+    /// The color attachment drawing over the scene and resolving into the frame
+    pub(crate) fn color_attachment<'a>(
+        &'a self,
+        frame: &'a TextureView,
+    ) -> RenderPassColorAttachment<'a> {
+        let (view, resolve_target) = match self.msaa.as_ref() {
+            Some((_, msaa_view)) => (msaa_view, Some(frame)),
+            None => (frame, None),
+        };
+        RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: Operations {
+                load: LoadOp::Load,
+                store: true,
+            },
+        }
+    }
+
+    pub(crate) fn depth_attachment(&self) -> Option<RenderPassDepthStencilAttachment<'_>> {
+        self.depth
+            .as_ref()
+            .map(|(format, view)| depth_attachment(*format, view))
+    }
+}
+
+/// Keeps the depth and stencil of the scene as they are
+pub(crate) fn depth_attachment(
+    format: TextureFormat,
+    view: &TextureView,
+) -> RenderPassDepthStencilAttachment<'_> {
+    let has_stencil = matches!(
+        format,
+        TextureFormat::Depth24PlusStencil8 | TextureFormat::Depth32FloatStencil8
+    );
+    RenderPassDepthStencilAttachment {
+        view,
+        depth_ops: Some(Operations {
+            load: LoadOp::Load,
+            store: true,
+        }),
+        stencil_ops: has_stencil.then_some(Operations {
+            load: LoadOp::Load,
+            store: true,
+        }),
+    }
+}
@@ -0,0 +1,610 @@
+use blue_engine::{
+    Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder, Device, Extent3d,
+    ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, LoadOp, Maintain, MapMode, Operations,
+    Origin3d, RenderPassColorAttachment, RenderPassDescriptor, Renderer, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
+    COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+use imgui::{DrawData, Ui};
+use std::fmt;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+
+/// A texture holding the final frame. wgpu doesn't tell the format and size of textures, so
+/// they're given along with it.
+#[derive(Clone, Debug)]
+pub struct FrameTexture {
+    /// The texture behind the view the UI is drawn onto, created with `COPY_SRC` usage
+    pub texture: Arc<Texture>,
+    pub format: TextureFormat,
+    pub size: [u32; 2],
+}
+
+/// What a screenshot shows
+#[derive(Clone, Debug)]
+pub enum CaptureSource {
+    /// The UI alone on a transparent background. In the offscreen mode, the texture the UI is
+    /// drawn to is copied as it is.
+    Ui,
+    /// The final frame, copied once the UI is drawn onto it. The surface texture of a window
+    /// can't be read, this is for frames rendered into a texture of the game, such as when
+    /// rendering without a window.
+    Frame(FrameTexture),
+}
+
+/// A rectangle of the UI in the logical coordinates ImGui places windows with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaptureRect {
+    pub pos: [f32; 2],
+    pub size: [f32; 2],
+}
+
+impl CaptureRect {
+    /// The rectangle of the window being built
+    pub fn current_window(ui: &Ui) -> Self {
+        Self {
+            pos: ui.window_pos(),
+            size: ui.window_size(),
+        }
+    }
+}
+
+/// A screenshot to take on the next frame
+#[derive(Clone, Debug)]
+pub struct ScreenshotRequest {
+    pub source: CaptureSource,
+    /// Part of the UI to keep, such as the rectangle of one window
+    pub crop: Option<CaptureRect>,
+    /// Where the PNG file is written
+    pub path: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    /// There was no UI drawn to capture
+    NoDrawData,
+    /// The crop rectangle is outside of the captured texture
+    EmptyCrop,
+    /// PNG files can't be written from textures of this format
+    UnsupportedFormat(TextureFormat),
+    /// The copy couldn't be read back from the GPU
+    Map(BufferAsyncError),
+    Io(std::io::Error),
+    Encode(png::EncodingError),
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::NoDrawData => write!(f, "no UI was drawn to capture"),
+            ScreenshotError::EmptyCrop => write!(f, "the crop rectangle is outside of the capture"),
+            ScreenshotError::UnsupportedFormat(format) => {
+                write!(f, "can't write {:?} textures to PNG", format)
+            }
+            ScreenshotError::Map(err) => write!(f, "couldn't read the capture back: {}", err),
+            ScreenshotError::Io(err) => write!(f, "{}", err),
+            ScreenshotError::Encode(err) => write!(f, "couldn't encode the PNG file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {}
+
+impl From<std::io::Error> for ScreenshotError {
+    fn from(err: std::io::Error) -> Self {
+        ScreenshotError::Io(err)
+    }
+}
+
+impl From<png::EncodingError> for ScreenshotError {
+    fn from(err: png::EncodingError) -> Self {
+        ScreenshotError::Encode(err)
+    }
+}
+
+/// Where the result of a screenshot is sent
+pub type ScreenshotResult = Receiver<Result<PathBuf, ScreenshotError>>;
+
+/// A texture to copy for a screenshot, with how its pixels are to be read
+pub(crate) struct CaptureTexture<'a> {
+    pub(crate) texture: &'a Texture,
+    pub(crate) format: TextureFormat,
+    pub(crate) size: [u32; 2],
+    /// Whether colors are premultiplied by alpha, as the UI is drawn onto transparency
+    pub(crate) premultiplied: bool,
+}
+
+/// A copy submitted to the GPU, read back on the next frame
+struct Pending {
+    buffer: Buffer,
+    size: [u32; 2],
+    bytes_per_row: u32,
+    format: TextureFormat,
+    premultiplied: bool,
+    path: PathBuf,
+    sender: Sender<Result<PathBuf, ScreenshotError>>,
+}
+
+/// Screenshots requested, and copies waiting to be read back
+#[derive(Default)]
+pub(crate) struct Screenshots {
+    requests: Vec<(ScreenshotRequest, Sender<Result<PathBuf, ScreenshotError>>)>,
+    pending: Vec<Pending>,
+}
+
+impl Screenshots {
+    pub(crate) fn request(&mut self, request: ScreenshotRequest) -> ScreenshotResult {
+        let (sender, receiver) = channel();
+        self.requests.push((request, sender));
+        receiver
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.requests.is_empty() && self.pending.is_empty()
+    }
+
+    pub(crate) fn take_requests(
+        &mut self,
+    ) -> Vec<(ScreenshotRequest, Sender<Result<PathBuf, ScreenshotError>>)> {
+        std::mem::take(&mut self.requests)
+    }
+
+    /// Records the copy of a texture, cropped to a rectangle of the UI, to read back once the
+    /// encoder was submitted.
+    pub(crate) fn copy(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: CaptureTexture,
+        draw_data: &DrawData,
+        request: ScreenshotRequest,
+        sender: Sender<Result<PathBuf, ScreenshotError>>,
+    ) {
+        let bytes_per_pixel = match pixel_size(source.format) {
+            Some(bytes_per_pixel) => bytes_per_pixel,
+            None => {
+                let _ = sender.send(Err(ScreenshotError::UnsupportedFormat(source.format)));
+                return;
+            }
+        };
+        let [x, y, width, height] = match request.crop {
+            Some(rect) => match crop_pixels(rect, display_rect(draw_data), source.size) {
+                Some(crop) => crop,
+                None => {
+                    let _ = sender.send(Err(ScreenshotError::EmptyCrop));
+                    return;
+                }
+            },
+            None => [0, 0, source.size[0], source.size[1]],
+        };
+
+        let bytes_per_row = (width * bytes_per_pixel).div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT)
+            * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ImGUI screenshot buffer"),
+            size: bytes_per_row as u64 * height as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture: source.texture,
+                mip_level: 0,
+                origin: Origin3d { x, y, z: 0 },
+                aspect: TextureAspect::All,
+            },
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(bytes_per_row),
+                    rows_per_image: NonZeroU32::new(height),
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.pending.push(Pending {
+            buffer,
+            size: [width, height],
+            bytes_per_row,
+            format: source.format,
+            premultiplied: source.premultiplied,
+            path: request.path,
+            sender,
+        });
+    }
+
+    /// Reads back the copies submitted with the last frame and writes them to PNG files.
+    pub(crate) fn finish(&mut self, device: &Device) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let (map_sender, map_receiver) = channel();
+        for (index, capture) in pending.iter().enumerate() {
+            let map_sender = map_sender.clone();
+            capture
+                .buffer
+                .slice(..)
+                .map_async(MapMode::Read, move |result| {
+                    let _ = map_sender.send((index, result));
+                });
+        }
+        // Nothing else drives the device without a window
+        device.poll(Maintain::Wait);
+
+        let mut mapped: Vec<Option<Result<(), BufferAsyncError>>> = vec![None; pending.len()];
+        for (index, result) in map_receiver.try_iter() {
+            mapped[index] = Some(result);
+        }
+        for (capture, mapped) in pending.into_iter().zip(mapped) {
+            let result = match mapped {
+                Some(Ok(())) => capture.write(),
+                Some(Err(err)) => Err(ScreenshotError::Map(err)),
+                None => Err(ScreenshotError::Map(BufferAsyncError)),
+            };
+            let _ = capture.sender.send(result.map(|()| capture.path.clone()));
+        }
+    }
+}
+
+impl Pending {
+    fn write(&self) -> Result<(), ScreenshotError> {
+        let [width, height] = self.size;
+        let pixels = {
+            let data = self.buffer.slice(..).get_mapped_range();
+            let bytes_per_pixel = pixel_size(self.format).unwrap();
+            let row = (width * bytes_per_pixel) as usize;
+            let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+            for padded_row in data.chunks_exact(self.bytes_per_row as usize) {
+                to_rgba8(
+                    &padded_row[..row],
+                    self.format,
+                    self.premultiplied,
+                    &mut pixels,
+                );
+            }
+            pixels
+        };
+        self.buffer.unmap();
+
+        let file = std::io::BufWriter::new(std::fs::File::create(&self.path)?);
+        let mut encoder = png::Encoder::new(file, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&pixels)?;
+        Ok(())
+    }
+}
+
+/// Draws the UI into a texture of its own for a screenshot, made to match the pipeline of the
+/// imgui renderer. Returns the texture and its size.
+pub(crate) fn draw_ui(
+    draw_data: &DrawData,
+    imgui_renderer: &mut imgui_wgpu::Renderer,
+    renderer: &Renderer,
+    encoder: &mut CommandEncoder,
+) -> Option<(Texture, [u32; 2])> {
+    let size = [
+        (draw_data.display_size[0] * draw_data.framebuffer_scale[0]).round() as u32,
+        (draw_data.display_size[1] * draw_data.framebuffer_scale[1]).round() as u32,
+    ];
+    if size[0] == 0 || size[1] == 0 {
+        return None;
+    }
+    let extent = Extent3d {
+        width: size[0],
+        height: size[1],
+        depth_or_array_layers: 1,
+    };
+    let attachment = |label, format, sample_count, usage| {
+        renderer.device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: extent,
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+        })
+    };
+
+    let format = imgui_renderer.texture_format();
+    let sample_count = imgui_renderer.sample_count();
+    let texture = attachment(
+        "ImGUI screenshot texture",
+        format,
+        1,
+        TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+    );
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    let msaa_view = (sample_count > 1).then(|| {
+        attachment(
+            "ImGUI screenshot multisampled texture",
+            format,
+            sample_count,
+            TextureUsages::RENDER_ATTACHMENT,
+        )
+        .create_view(&TextureViewDescriptor::default())
+    });
+    let depth = imgui_renderer.depth_format().map(|depth_format| {
+        let view = attachment(
+            "ImGUI screenshot depth texture",
+            depth_format,
+            sample_count,
+            TextureUsages::RENDER_ATTACHMENT,
+        )
+        .create_view(&TextureViewDescriptor::default());
+        (depth_format, view)
+    });
+
+    let (view, resolve_target) = match msaa_view.as_ref() {
+        Some(msaa_view) => (msaa_view, Some(&view)),
+        None => (&view, None),
+    };
+    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("ImGUI screenshot render pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: Operations {
+                load: LoadOp::Clear(blue_engine::Color::TRANSPARENT),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: depth
+            .as_ref()
+            .map(|(format, view)| crate::scene::depth_attachment(*format, view)),
+    });
+    imgui_renderer
+        .render(
+            draw_data,
+            &renderer.queue,
+            &renderer.device,
+            &mut render_pass,
+        )
+        .unwrap();
+    drop(render_pass);
+
+    Some((texture, size))
+}
+
+/// Bytes per pixel of the formats that can be written to PNG
+fn pixel_size(format: TextureFormat) -> Option<u32> {
+    match format {
+        TextureFormat::Rgba8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Bgra8Unorm
+        | TextureFormat::Bgra8UnormSrgb => Some(4),
+        TextureFormat::Rgba16Float => Some(8),
+        _ => None,
+    }
+}
+
+/// The rectangle of the UI the draw data covers
+fn display_rect(draw_data: &DrawData) -> CaptureRect {
+    CaptureRect {
+        pos: draw_data.display_pos,
+        size: draw_data.display_size,
+    }
+}
+
+/// Converts a rectangle of the UI to pixels of a texture stretched over the display, clamped to
+/// the texture. `None` if nothing of it is left.
+fn crop_pixels(rect: CaptureRect, display: CaptureRect, size: [u32; 2]) -> Option<[u32; 4]> {
+    let mut pixels = [0; 4];
+    for axis in 0..2 {
+        let scale = size[axis] as f32 / display.size[axis];
+        let start = (rect.pos[axis] - display.pos[axis]) * scale;
+        let end = start + rect.size[axis] * scale;
+        let start = (start.floor().max(0.0) as u32).min(size[axis]);
+        let end = (end.ceil().max(0.0) as u32).min(size[axis]);
+        if end <= start {
+            return None;
+        }
+        pixels[axis] = start;
+        pixels[axis + 2] = end - start;
+    }
+    Some(pixels)
+}
+
+/// Converts a row of pixels to 8 bit sRGB RGBA with straight alpha, as PNG files hold.
+fn to_rgba8(row: &[u8], format: TextureFormat, premultiplied: bool, out: &mut Vec<u8>) {
+    let unpremultiply = |color: f32, alpha: f32| {
+        if premultiplied && alpha > 0.0 {
+            (color / alpha).min(1.0)
+        } else {
+            color
+        }
+    };
+    match format {
+        TextureFormat::Rgba16Float => {
+            for pixel in row.chunks_exact(8) {
+                let channel =
+                    |index: usize| f16_to_f32(u16::from_le_bytes([pixel[index], pixel[index + 1]]));
+                let alpha = channel(6).clamp(0.0, 1.0);
+                for index in [0, 2, 4] {
+                    let color = unpremultiply(channel(index).max(0.0), alpha);
+                    out.push((linear_to_srgb(color.min(1.0)) * 255.0).round() as u8);
+                }
+                out.push((alpha * 255.0).round() as u8);
+            }
+        }
+        _ => {
+            let bgra = matches!(
+                format,
+                TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+            );
+            // blending onto sRGB textures happens in linear space, so colors were multiplied by
+            // alpha before they were encoded
+            let srgb = matches!(
+                format,
+                TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8UnormSrgb
+            );
+            for pixel in row.chunks_exact(4) {
+                let alpha = pixel[3] as f32 / 255.0;
+                let rgb = if bgra {
+                    [pixel[2], pixel[1], pixel[0]]
+                } else {
+                    [pixel[0], pixel[1], pixel[2]]
+                };
+                for color in rgb {
+                    let color = color as f32 / 255.0;
+                    let color = if srgb {
+                        linear_to_srgb(unpremultiply(srgb_to_linear(color), alpha))
+                    } else {
+                        unpremultiply(color, alpha)
+                    };
+                    out.push((color * 255.0).round() as u8);
+                }
+                out.push(pixel[3]);
+            }
+        }
+    }
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32 / 1024.0;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-14),
+        0x1f => f32::INFINITY,
+        _ => (1.0 + mantissa) * 2f32.powi(exponent - 15),
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba8(row: &[u8], format: TextureFormat, premultiplied: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        to_rgba8(row, format, premultiplied, &mut out);
+        out
+    }
+
+    #[test]
+    fn crops_are_scaled_to_the_texture_and_clamped() {
+        let display = CaptureRect {
+            pos: [100.0, 50.0],
+            size: [200.0, 100.0],
+        };
+        let rect = |pos, size| CaptureRect { pos, size };
+
+        // the texture has twice the pixels of the display, partial pixels are kept
+        assert_eq!(
+            crop_pixels(rect([110.0, 60.0], [20.5, 10.0]), display, [400, 200]),
+            Some([20, 20, 41, 20])
+        );
+        // parts outside the display are cut off
+        assert_eq!(
+            crop_pixels(rect([50.0, 0.0], [100.0, 100.0]), display, [400, 200]),
+            Some([0, 0, 100, 100])
+        );
+        assert_eq!(
+            crop_pixels(rect([250.0, 120.0], [100.0, 100.0]), display, [400, 200]),
+            Some([300, 140, 100, 60])
+        );
+        // nothing is left of rectangles beside the display or without a size
+        assert_eq!(
+            crop_pixels(rect([0.0, 0.0], [100.0, 50.0]), display, [400, 200]),
+            None
+        );
+        assert_eq!(
+            crop_pixels(rect([400.0, 60.0], [10.0, 10.0]), display, [400, 200]),
+            None
+        );
+        assert_eq!(
+            crop_pixels(rect([110.0, 60.0], [0.0, 10.0]), display, [400, 200]),
+            None
+        );
+    }
+
+    #[test]
+    fn bgra_is_swizzled() {
+        let row = [1, 2, 3, 255, 10, 20, 30, 128];
+        assert_eq!(
+            rgba8(&row, TextureFormat::Bgra8Unorm, false),
+            [3, 2, 1, 255, 30, 20, 10, 128]
+        );
+        assert_eq!(rgba8(&row, TextureFormat::Rgba8Unorm, false), row.to_vec());
+    }
+
+    #[test]
+    fn f16_is_decoded() {
+        assert_eq!(f16_to_f32(0x0000), 0.0);
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x3800), 0.5);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+
+        // half white at half alpha, premultiplied and linear
+        let half = 0x3800u16.to_le_bytes();
+        let quarter = 0x3400u16.to_le_bytes();
+        let row = [quarter, quarter, quarter, half].concat();
+        assert_eq!(
+            rgba8(&row, TextureFormat::Rgba16Float, true),
+            [188, 188, 188, 128]
+        );
+    }
+
+    #[test]
+    fn premultiplied_colors_are_divided_by_alpha() {
+        // half transparent, 0.5 of red premultiplied to 0.25
+        let row = [64, 0, 0, 128];
+        assert_eq!(
+            rgba8(&row, TextureFormat::Rgba8Unorm, true),
+            [128, 0, 0, 128]
+        );
+        assert_eq!(rgba8(&row, TextureFormat::Rgba8Unorm, false), row.to_vec());
+        // nothing to divide when it's transparent
+        assert_eq!(
+            rgba8(&[0, 0, 0, 0], TextureFormat::Rgba8Unorm, true),
+            [0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn srgb_colors_are_unpremultiplied_in_linear_space() {
+        // linear 0.5 of red at half alpha is premultiplied to 0.25 and encoded as 137. Dividing
+        // the encoded value by alpha would saturate at 255.
+        assert_eq!(
+            rgba8(&[137, 0, 0, 128], TextureFormat::Rgba8UnormSrgb, true),
+            [187, 0, 0, 128]
+        );
+        assert_eq!(
+            rgba8(&[0, 0, 137, 128], TextureFormat::Bgra8UnormSrgb, true),
+            [187, 0, 0, 128]
+        );
+        assert_eq!(
+            rgba8(&[137, 0, 0, 128], TextureFormat::Rgba8UnormSrgb, false),
+            [137, 0, 0, 128]
+        );
+    }
+}
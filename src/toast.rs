@@ -0,0 +1,277 @@
+use imgui::{Condition, MouseButton, ProgressBar, StyleVar, Ui, WindowFlags};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Identifies a toast pushed to the queue
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ToastId(u64);
+
+/// Severity of a toast, decides the accent color
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    /// The accent color used for the title of the toast
+    pub fn color(&self) -> [f32; 4] {
+        match self {
+            ToastLevel::Info => [0.33f32, 0.67f32, 0.86f32, 1f32],
+            ToastLevel::Success => [0.36f32, 0.80f32, 0.42f32, 1f32],
+            ToastLevel::Warning => [0.95f32, 0.75f32, 0.25f32, 1f32],
+            ToastLevel::Error => [0.95f32, 0.30f32, 0.30f32, 1f32],
+        }
+    }
+}
+
+/// The screen corner toasts are stacked from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ToastCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// A single notification
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub id: ToastId,
+    pub level: ToastLevel,
+    pub title: String,
+    pub body: String,
+    /// How long the toast stays on screen, fading included
+    pub duration: Duration,
+    /// Optional progress shown below the body, from 0.0 to 1.0
+    pub progress: Option<f32>,
+    elapsed: Duration,
+}
+
+impl Toast {
+    /// A toast with an unfinished progress bar doesn't expire
+    fn is_waiting(&self) -> bool {
+        matches!(self.progress, Some(progress) if progress < 1f32)
+    }
+
+    fn is_expired(&self) -> bool {
+        !self.is_waiting() && self.elapsed >= self.duration
+    }
+}
+
+/// The toast queue, stores the toasts on screen and the history of past ones
+#[derive(Debug)]
+pub struct Toasts {
+    active: Vec<Toast>,
+    history: VecDeque<Toast>,
+    next_id: u64,
+    /// The screen corner toasts are stacked from
+    pub corner: ToastCorner,
+    /// Distance of the stack from the screen edges
+    pub margin: [f32; 2],
+    /// Vertical space between two toasts
+    pub spacing: f32,
+    /// Width of each toast
+    pub width: f32,
+    /// Maximum amount of toasts shown at once, the rest waits in the queue
+    pub max_visible: usize,
+    /// How long before expiring a toast starts fading out
+    pub fade_time: Duration,
+    /// Amount of past toasts kept for the history panel
+    pub history_limit: usize,
+    /// Shows the history panel
+    pub show_history: bool,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self {
+            active: Vec::new(),
+            history: VecDeque::new(),
+            next_id: 0,
+            corner: ToastCorner::BottomRight,
+            margin: [15f32, 15f32],
+            spacing: 8f32,
+            width: 300f32,
+            max_visible: 5,
+            fade_time: Duration::from_millis(500),
+            history_limit: 50,
+            show_history: false,
+        }
+    }
+}
+
+impl Toasts {
+    /// Queues a new toast
+    pub fn push(
+        &mut self,
+        level: ToastLevel,
+        title: impl Into<String>,
+        body: impl Into<String>,
+        duration: Duration,
+    ) -> ToastId {
+        let id = ToastId(self.next_id);
+        self.next_id += 1;
+
+        self.active.push(Toast {
+            id,
+            level,
+            title: title.into(),
+            body: body.into(),
+            duration,
+            progress: None,
+            elapsed: Duration::ZERO,
+        });
+
+        id
+    }
+
+    /// Sets or clears the progress bar of a toast, the toast expires normally once it reaches 1.0
+    pub fn set_progress(&mut self, id: ToastId, progress: Option<f32>) {
+        if let Some(toast) = self.active.iter_mut().find(|toast| toast.id == id) {
+            toast.progress = progress.map(|progress| progress.clamp(0f32, 1f32));
+            if toast.is_waiting() {
+                toast.elapsed = Duration::ZERO;
+            }
+        }
+    }
+
+    /// Removes a toast from the screen and moves it to the history
+    pub fn dismiss(&mut self, id: ToastId) {
+        if let Some(index) = self.active.iter().position(|toast| toast.id == id) {
+            let toast = self.active.remove(index);
+            self.archive(toast);
+        }
+    }
+
+    /// The toasts currently queued or on screen
+    pub fn active(&self) -> &[Toast] {
+        &self.active
+    }
+
+    /// The past toasts, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &Toast> {
+        self.history.iter()
+    }
+
+    /// Clears the history
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    fn archive(&mut self, toast: Toast) {
+        if self.history_limit == 0 {
+            return;
+        }
+        while self.history.len() >= self.history_limit {
+            self.history.pop_front();
+        }
+        self.history.push_back(toast);
+    }
+
+    /// Ages the toasts and draws them, along with the history panel if enabled
+    pub fn render(&mut self, ui: &Ui) {
+        let delta = Duration::from_secs_f32(ui.io().delta_time.max(0f32));
+        let display_size = ui.io().display_size;
+
+        let (pivot, direction) = match self.corner {
+            ToastCorner::TopLeft => ([0f32, 0f32], 1f32),
+            ToastCorner::TopRight => ([1f32, 0f32], 1f32),
+            ToastCorner::BottomLeft => ([0f32, 1f32], -1f32),
+            ToastCorner::BottomRight => ([1f32, 1f32], -1f32),
+        };
+        let x = self.margin[0] + pivot[0] * (display_size[0] - 2f32 * self.margin[0]);
+        let mut y = self.margin[1] + pivot[1] * (display_size[1] - 2f32 * self.margin[1]);
+
+        let mut dismissed = Vec::new();
+        for toast in self.active.iter_mut().take(self.max_visible) {
+            let remaining = toast.duration.saturating_sub(toast.elapsed);
+            let alpha = if toast.is_waiting() || self.fade_time.is_zero() {
+                1f32
+            } else {
+                (remaining.as_secs_f32() / self.fade_time.as_secs_f32()).min(1f32)
+            };
+
+            let alpha_token = ui.push_style_var(StyleVar::Alpha(alpha));
+            let height = ui
+                .window(format!("##toast_{}", toast.id.0))
+                .position([x, y], Condition::Always)
+                .position_pivot(pivot)
+                .size([self.width, 0f32], Condition::Always)
+                .flags(
+                    WindowFlags::NO_DECORATION
+                        | WindowFlags::NO_SAVED_SETTINGS
+                        | WindowFlags::NO_FOCUS_ON_APPEARING
+                        | WindowFlags::NO_NAV
+                        | WindowFlags::NO_DOCKING,
+                )
+                .build(|| {
+                    ui.text_colored(toast.level.color(), &toast.title);
+                    if !toast.body.is_empty() {
+                        ui.text_wrapped(&toast.body);
+                    }
+                    if let Some(progress) = toast.progress {
+                        ProgressBar::new(progress).build(ui);
+                    }
+
+                    // Hovering pauses the toast so it can be read, clicking dismisses it
+                    if ui.is_window_hovered() {
+                        if ui.is_mouse_clicked(MouseButton::Left) {
+                            dismissed.push(toast.id);
+                        }
+                    } else {
+                        toast.elapsed += delta;
+                    }
+
+                    ui.window_size()[1]
+                })
+                .unwrap_or(0f32);
+            alpha_token.end();
+
+            y += direction * (height + self.spacing);
+        }
+
+        let mut index = 0;
+        while index < self.active.len() {
+            if self.active[index].is_expired() {
+                let toast = self.active.remove(index);
+                self.archive(toast);
+            } else {
+                index += 1;
+            }
+        }
+        for id in dismissed {
+            self.dismiss(id);
+        }
+
+        if self.show_history {
+            self.render_history(ui);
+        }
+    }
+
+    fn render_history(&mut self, ui: &Ui) {
+        let mut opened = true;
+        let mut clear = false;
+        ui.window("Notifications")
+            .opened(&mut opened)
+            .size([350f32, 300f32], Condition::FirstUseEver)
+            .build(|| {
+                clear = ui.button("Clear");
+                ui.separator();
+                for toast in self.history.iter().rev() {
+                    ui.text_colored(toast.level.color(), &toast.title);
+                    if !toast.body.is_empty() {
+                        ui.text_wrapped(&toast.body);
+                    }
+                    ui.separator();
+                }
+            });
+
+        if clear {
+            self.clear_history();
+        }
+        self.show_history = opened;
+    }
+}
@@ -0,0 +1,308 @@
+use blue_engine::{
+    Color, CommandEncoderDescriptor, CompositeAlphaMode, LoadOp, Operations, PresentMode,
+    RenderPassColorAttachment, RenderPassDescriptor, Renderer, Surface, SurfaceConfiguration,
+    TextureFormat, TextureUsages, TextureViewDescriptor,
+};
+use imgui::{Context, SuspendedContext, Ui};
+use imgui_winit_support::winit::event::{Event, WindowEvent};
+use imgui_winit_support::winit::window::{Window, WindowId};
+use imgui_winit_support::{HiDpiMode, WinitPlatform};
+use std::time::Instant;
+
+/// A separate OS window with its own imgui context, such as a profiler next to the game window
+pub struct ToolWindow {
+    pub window: Window,
+    /// Tracks the DPI, mouse and cursor of this window only
+    pub platform: WinitPlatform,
+    pub renderer: imgui_wgpu::Renderer,
+    /// Color the window is cleared with before the UI is drawn
+    pub clear_color: [f64; 4],
+    context: SuspendedContext,
+    surface: Surface,
+    surface_config: SurfaceConfiguration,
+    last_frame: Instant,
+    frame_open: bool,
+    /// Draws text with the font texture of the main renderer
+    shares_fonts: bool,
+}
+
+impl ToolWindow {
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    fn resize(&mut self, width: u32, height: u32, renderer: &Renderer) {
+        if width > 0 && height > 0 {
+            self.surface_config.width = width;
+            self.surface_config.height = height;
+            self.surface
+                .configure(&renderer.device, &self.surface_config);
+        }
+    }
+}
+
+/// The tool windows of the plugin, each drawn by its own imgui context.
+///
+/// The contexts take turns with the main one, so the main context stays active while the game
+/// builds its UI.
+pub struct ToolWindows {
+    windows: Vec<ToolWindow>,
+    /// Makes tool windows added from now on share the font atlas and texture of the main context
+    /// instead of building their own, so icons merged into its fonts show up in them too
+    pub share_fonts: bool,
+    /// Format the surfaces of tool windows added from now on are configured with, the first
+    /// format a surface supports when `None`
+    pub surface_format: Option<TextureFormat>,
+}
+
+impl Default for ToolWindows {
+    fn default() -> Self {
+        Self {
+            windows: Vec::new(),
+            share_fonts: true,
+            surface_format: None,
+        }
+    }
+}
+
+impl ToolWindows {
+    /// Adds a window, the surface has to be created for it from the instance the engine uses
+    pub fn add(
+        &mut self,
+        main: &mut Context,
+        main_renderer: &imgui_wgpu::Renderer,
+        window: Window,
+        surface: Surface,
+        renderer: &Renderer,
+    ) -> WindowId {
+        let font_atlas = if self.share_fonts {
+            main.clone_shared_font_atlas()
+        } else {
+            None
+        };
+        let shares_fonts = font_atlas.is_some();
+        let mut context = match font_atlas {
+            Some(font_atlas) => SuspendedContext::create_with_shared_font_atlas(font_atlas),
+            None => SuspendedContext::create(),
+        };
+
+        let size = window.inner_size();
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: self
+                .surface_format
+                .unwrap_or_else(|| surface.get_supported_formats(&renderer.adapter)[0]),
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: PresentMode::Fifo,
+            alpha_mode: CompositeAlphaMode::Auto,
+        };
+        surface.configure(&renderer.device, &surface_config);
+
+        let (platform, imgui_renderer) = main.with_suspended(&mut context, |imgui| {
+            let mut platform = WinitPlatform::init(imgui);
+            platform.attach_window(imgui.io_mut(), &window, HiDpiMode::Default);
+            imgui.set_ini_filename(None);
+            crate::imgui_redesign(imgui, window.scale_factor());
+
+            let config = imgui_wgpu::RendererConfig {
+                texture_format: surface_config.format,
+                ..Default::default()
+            };
+            let imgui_renderer = if shares_fonts {
+                imgui_wgpu::Renderer::with_shared_font_texture(
+                    imgui,
+                    &renderer.device,
+                    config,
+                    main_renderer,
+                )
+            } else {
+                imgui_wgpu::Renderer::new(imgui, &renderer.device, &renderer.queue, config)
+            };
+            (platform, imgui_renderer)
+        });
+
+        let id = window.id();
+        self.windows.push(ToolWindow {
+            window,
+            platform,
+            renderer: imgui_renderer,
+            clear_color: [0.1, 0.1, 0.1, 1.0],
+            context,
+            surface,
+            surface_config,
+            last_frame: Instant::now(),
+            frame_open: false,
+            shares_fonts,
+        });
+        id
+    }
+
+    /// Removes a window and its context, giving the window back so it can be kept or closed
+    pub fn remove(&mut self, id: WindowId) -> Option<Window> {
+        let index = self.windows.iter().position(|tool| tool.id() == id)?;
+        Some(self.windows.remove(index).window)
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&ToolWindow> {
+        self.windows.iter().find(|tool| tool.id() == id)
+    }
+
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut ToolWindow> {
+        self.windows.iter_mut().find(|tool| tool.id() == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ToolWindow> {
+        self.windows.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Draws text with the current font texture of the main renderer again, after it was reloaded
+    pub fn share_font_texture(&mut self, main: &mut Context, main_renderer: &imgui_wgpu::Renderer) {
+        for tool in self.windows.iter_mut().filter(|tool| tool.shares_fonts) {
+            let ToolWindow {
+                renderer, context, ..
+            } = tool;
+            main.with_suspended(context, |imgui| {
+                renderer.share_font_texture(imgui, main_renderer)
+            });
+        }
+    }
+
+    /// Builds the UI of a window for this frame, it's drawn on the next update
+    pub fn ui<F: FnOnce(&mut Ui)>(&mut self, main: &mut Context, id: WindowId, callback: F) {
+        let tool = match self.windows.iter_mut().find(|tool| tool.window.id() == id) {
+            Some(tool) => tool,
+            None => return,
+        };
+
+        let ToolWindow {
+            window,
+            platform,
+            context,
+            last_frame,
+            frame_open,
+            ..
+        } = tool;
+        main.with_suspended(context, |imgui| {
+            if *frame_open {
+                // The previous frame was never drawn, it's dropped so a new one can start
+                imgui.render();
+            }
+
+            let now = Instant::now();
+            imgui.io_mut().update_delta_time(now - *last_frame);
+            *last_frame = now;
+            platform
+                .prepare_frame(imgui.io_mut(), window)
+                .expect("Failed to prepare frame");
+
+            let ui = imgui.frame();
+            callback(ui);
+            platform.prepare_render_ui(ui);
+        });
+        *frame_open = true;
+    }
+
+    /// Routes an event to the window it belongs to, and resizes its surface
+    pub fn handle_event<T>(&mut self, main: &mut Context, renderer: &Renderer, event: &Event<T>) {
+        for tool in self.windows.iter_mut() {
+            if let Event::WindowEvent { window_id, event } = event {
+                if *window_id == tool.window.id() {
+                    match event {
+                        WindowEvent::Resized(size) => {
+                            tool.resize(size.width, size.height, renderer)
+                        }
+                        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                            tool.resize(new_inner_size.width, new_inner_size.height, renderer)
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            let ToolWindow {
+                window,
+                platform,
+                context,
+                ..
+            } = tool;
+            main.with_suspended(context, |imgui| {
+                platform.handle_event(imgui.io_mut(), window, event)
+            });
+        }
+    }
+
+    /// Draws the windows that built a UI this frame into their own surfaces
+    pub fn render(&mut self, main: &mut Context, renderer: &Renderer) {
+        for tool in self.windows.iter_mut().filter(|tool| tool.frame_open) {
+            tool.frame_open = false;
+
+            let ToolWindow {
+                window,
+                platform,
+                renderer: imgui_renderer,
+                clear_color,
+                context,
+                surface,
+                surface_config,
+                ..
+            } = tool;
+            main.with_suspended(context, |imgui| {
+                platform.prepare_render_window(window);
+                let draw_data = imgui.render();
+
+                let frame = match surface.get_current_texture() {
+                    Ok(frame) => frame,
+                    Err(_) => {
+                        // The surface is outdated or lost, it's drawn again on the next frame
+                        surface.configure(&renderer.device, surface_config);
+                        return;
+                    }
+                };
+                let view = frame.texture.create_view(&TextureViewDescriptor::default());
+                let mut encoder =
+                    renderer
+                        .device
+                        .create_command_encoder(&CommandEncoderDescriptor {
+                            label: Some("Tool window encoder"),
+                        });
+
+                {
+                    let [r, g, b, a] = *clear_color;
+                    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                        label: Some("Tool window render pass"),
+                        color_attachments: &[Some(RenderPassColorAttachment {
+                            view: &view,
+                            resolve_target: None,
+                            ops: Operations {
+                                load: LoadOp::Clear(Color { r, g, b, a }),
+                                store: true,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                    });
+
+                    imgui_renderer
+                        .render(
+                            draw_data,
+                            &renderer.queue,
+                            &renderer.device,
+                            &mut render_pass,
+                        )
+                        .unwrap();
+                }
+
+                renderer.queue.submit(Some(encoder.finish()));
+                frame.present();
+            });
+        }
+    }
+}
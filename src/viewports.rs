@@ -0,0 +1,273 @@
+use blue_engine::{
+    Color, CommandEncoderDescriptor, CompositeAlphaMode, Device, Extent3d, LoadOp, Operations,
+    PresentMode, RenderPassColorAttachment, RenderPassDescriptor, Renderer, Surface,
+    SurfaceConfiguration, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView, TextureViewDescriptor,
+};
+use imgui::{BackendFlags, ConfigFlags, Context, ViewportFlags};
+use imgui_winit_support::winit::window::{Window, WindowId};
+use imgui_winit_support::WinitPlatform;
+use std::collections::HashMap;
+
+/// The surface of a window imgui viewports are drawn to
+struct ViewportSurface {
+    surface: Surface,
+    surface_config: SurfaceConfiguration,
+    render_data: Option<imgui_wgpu::RenderData>,
+    attachments: Option<Attachments>,
+}
+
+/// Multisampled and depth textures matching the pipeline of the renderer, which is built for
+/// the attachments of the main window
+struct Attachments {
+    size: [u32; 2],
+    sample_count: u32,
+    depth_format: Option<TextureFormat>,
+    msaa: Option<TextureView>,
+    depth: Option<TextureView>,
+}
+
+impl Attachments {
+    fn new(
+        device: &Device,
+        size: [u32; 2],
+        format: TextureFormat,
+        sample_count: u32,
+        depth_format: Option<TextureFormat>,
+    ) -> Self {
+        let create = |label, format, sample_count| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: size[0],
+                        height: size[1],
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::RENDER_ATTACHMENT,
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+        Self {
+            size,
+            sample_count,
+            depth_format,
+            msaa: (sample_count > 1)
+                .then(|| create("Viewport multisampled texture", format, sample_count)),
+            depth: depth_format
+                .map(|depth_format| create("Viewport depth texture", depth_format, sample_count)),
+        }
+    }
+}
+
+impl ViewportSurface {
+    fn resize(&mut self, width: u32, height: u32, renderer: &Renderer) {
+        if width > 0
+            && height > 0
+            && (width != self.surface_config.width || height != self.surface_config.height)
+        {
+            self.surface_config.width = width;
+            self.surface_config.height = height;
+            self.surface
+                .configure(&renderer.device, &self.surface_config);
+        }
+    }
+}
+
+/// Lets imgui windows be dragged out of the game window into OS windows of their own.
+///
+/// The engine doesn't hand out its event loop, so the windows are created up front and kept
+/// hidden until a viewport needs one. Viewports are drawn by the renderer of the main context.
+pub struct Viewports {
+    surfaces: HashMap<WindowId, ViewportSurface>,
+    enabled: bool,
+    /// Color the windows are cleared with before the UI is drawn
+    pub clear_color: [f64; 4],
+}
+
+impl Default for Viewports {
+    fn default() -> Self {
+        Self {
+            surfaces: HashMap::new(),
+            enabled: false,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+impl Viewports {
+    /// Sets up the context and the platform for viewports
+    pub fn enable(&mut self, context: &mut Context, platform: &mut WinitPlatform, window: &Window) {
+        if self.enabled {
+            return;
+        }
+        let io = context.io_mut();
+        io.config_flags.insert(ConfigFlags::VIEWPORTS_ENABLE);
+        io.backend_flags
+            .insert(BackendFlags::RENDERER_HAS_VIEWPORTS);
+        platform.enable_viewports(context, window);
+        self.enabled = true;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Adds a window viewports can use, the surface has to be created for it from the instance
+    /// the engine uses
+    pub fn add_window(
+        &mut self,
+        platform: &mut WinitPlatform,
+        window: Window,
+        surface: Surface,
+        format: TextureFormat,
+        renderer: &Renderer,
+    ) {
+        let size = window.inner_size();
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: PresentMode::Fifo,
+            alpha_mode: CompositeAlphaMode::Auto,
+        };
+        surface.configure(&renderer.device, &surface_config);
+
+        self.surfaces.insert(
+            window.id(),
+            ViewportSurface {
+                surface,
+                surface_config,
+                render_data: None,
+                attachments: None,
+            },
+        );
+        platform.add_viewport_window(window);
+    }
+
+    /// Number of windows added for viewports
+    pub fn len(&self) -> usize {
+        self.surfaces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.surfaces.is_empty()
+    }
+
+    /// Updates the windows of the viewports and draws every viewport other than the main one.
+    /// Has to be called after the main context rendered the frame.
+    pub fn render(
+        &mut self,
+        context: &mut Context,
+        platform: &WinitPlatform,
+        imgui_renderer: &imgui_wgpu::Renderer,
+        renderer: &Renderer,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        context.update_platform_windows();
+
+        for viewport in context.viewports_mut() {
+            if viewport.is_main() || viewport.flags.contains(ViewportFlags::MINIMIZED) {
+                continue;
+            }
+            let window = match platform.viewport_window(viewport.id) {
+                Some(window) => window,
+                None => continue,
+            };
+            let target = match self.surfaces.get_mut(&window.id()) {
+                Some(target) => target,
+                None => continue,
+            };
+            let draw_data = match viewport.draw_data_mut() {
+                Some(draw_data) => draw_data,
+                None => continue,
+            };
+
+            // The window may sit on a monitor with another DPI than the main one
+            let scale = window.scale_factor() as f32;
+            draw_data.framebuffer_scale = [scale, scale];
+            let size = window.inner_size();
+            target.resize(size.width, size.height, renderer);
+
+            let frame = match target.surface.get_current_texture() {
+                Ok(frame) => frame,
+                Err(_) => {
+                    // The surface is outdated or lost, it's drawn again on the next frame
+                    target
+                        .surface
+                        .configure(&renderer.device, &target.surface_config);
+                    continue;
+                }
+            };
+            let view = frame.texture.create_view(&TextureViewDescriptor::default());
+
+            let config = &target.surface_config;
+            let size = [config.width, config.height];
+            let sample_count = imgui_renderer.sample_count();
+            let depth_format = imgui_renderer.depth_format();
+            let current = matches!(&target.attachments, Some(attachments)
+                if attachments.size == size
+                    && attachments.sample_count == sample_count
+                    && attachments.depth_format == depth_format);
+            if !current {
+                target.attachments = Some(Attachments::new(
+                    &renderer.device,
+                    size,
+                    config.format,
+                    sample_count,
+                    depth_format,
+                ));
+            }
+            let attachments = target.attachments.as_ref().unwrap();
+            let (color_view, resolve_target) = match attachments.msaa.as_ref() {
+                Some(msaa) => (msaa, Some(&view)),
+                None => (&view, None),
+            };
+            let mut encoder = renderer
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some("Viewport encoder"),
+                });
+
+            let render_data = imgui_renderer.prepare(
+                draw_data,
+                target.render_data.take(),
+                &renderer.queue,
+                &renderer.device,
+            );
+            {
+                let [r, g, b, a] = self.clear_color;
+                let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Viewport render pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: color_view,
+                        resolve_target,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color { r, g, b, a }),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: attachments
+                        .depth
+                        .as_ref()
+                        .map(|depth| crate::scene::depth_attachment(depth_format.unwrap(), depth)),
+                });
+
+                imgui_renderer
+                    .split_render(draw_data, &render_data, &mut render_pass)
+                    .unwrap();
+            }
+            target.render_data = Some(render_data);
+
+            renderer.queue.submit(Some(encoder.finish()));
+            frame.present();
+        }
+    }
+}